@@ -48,6 +48,11 @@ enum Command {
         /// Output file. Standard output by default
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Only export the field at the given dot-separated path. Fields outside of this path
+        /// aren't evaluated
+        #[arg(long)]
+        field: Option<String>,
     },
     /// Prints the metadata attached to an attribute, given as a path
     Query {
@@ -130,7 +135,11 @@ fn handle_eval_commands(opts: Opt) {
             &mut std::io::BufWriter::new(Box::new(std::io::stdout())),
             transform,
         ),
-        Some(Command::Export { format, output }) => export(&mut program, format, output),
+        Some(Command::Export {
+            format,
+            output,
+            field,
+        }) => export(&mut program, format, output, field),
         Some(Command::Query {
             path,
             doc,
@@ -205,8 +214,9 @@ fn export(
     program: &mut Program<CacheImpl>,
     format: ExportFormat,
     output: Option<PathBuf>,
+    field: Option<String>,
 ) -> Result<(), Error> {
-    let rt = program.eval_full_for_export().map(RichTerm::from)?;
+    let rt = program.eval_full_for_export_at(field).map(RichTerm::from)?;
 
     // We only add a trailing newline for JSON exports. Both YAML and TOML
     // exporters already append a trailing newline by default.
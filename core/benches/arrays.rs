@@ -167,6 +167,11 @@ config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flam
         path = "arrays/random",
         args = (50),
         eval_mode = EvalMode::DeepSeq,
+    }, {
+        name = "merge deepseq 500",
+        path = "arrays/merge",
+        args = (500),
+        eval_mode = EvalMode::DeepSeq,
     }
 }
 criterion_main!(benches);
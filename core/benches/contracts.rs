@@ -0,0 +1,20 @@
+use criterion::{criterion_main, Criterion};
+use nickel_lang_utils::{bench::EvalMode, ncl_bench_group};
+use pprof::criterion::{Output, PProfProfiler};
+
+ncl_bench_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    {
+        name = "open record contract 2000",
+        path = "contracts/open_record",
+        args = (2000),
+        eval_mode = EvalMode::DeepSeq,
+    }, {
+        name = "revert 50 pending contracts",
+        path = "contracts/pending_contracts",
+        args = (50, 200),
+        eval_mode = EvalMode::DeepSeq,
+    }
+}
+criterion_main!(benches);
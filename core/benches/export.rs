@@ -0,0 +1,15 @@
+use criterion::{criterion_main, Criterion};
+use nickel_lang_utils::{bench::EvalMode, ncl_bench_group};
+use pprof::criterion::{Output, PProfProfiler};
+
+ncl_bench_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    {
+        name = "merge not exported",
+        path = "export/merge",
+        args = (500, 1000),
+        eval_mode = EvalMode::Export,
+    }
+}
+criterion_main!(benches);
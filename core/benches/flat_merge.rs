@@ -0,0 +1,67 @@
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pprof::criterion::{Output, PProfProfiler};
+
+use nickel_lang_core::{
+    eval::{cache::CacheImpl, EvalConfig},
+    program::Program,
+};
+
+const FIELDS: usize = 1_000;
+const MERGES: usize = 100;
+
+/// Generate a program merging `MERGES` times two records of `FIELDS` fields each. The fields of
+/// the left record are computed, and each one depends on the previous one in the recursive
+/// variant, such that none of them can be passed through without being reverted.
+fn source(recursive: bool) -> String {
+    let left: Vec<String> = (0..FIELDS)
+        .map(|i| match i {
+            0 => "f0 = 0 + 1".to_owned(),
+            i if recursive => format!("f{i} = f{} + 1", i - 1),
+            i => format!("f{i} = {i} + 1"),
+        })
+        .collect();
+    let right: Vec<String> = (0..FIELDS).map(|i| format!("g{i} = {i} + 1")).collect();
+
+    format!(
+        "let left = {{ {} }} in
+        let right = {{ {} }} in
+        std.array.map (fun _i => std.record.length (left & right)) (std.array.range 0 {MERGES})",
+        left.join(", "),
+        right.join(", "),
+    )
+}
+
+fn program(source: &str, skip_flat_reverts: bool) -> Program<CacheImpl> {
+    let mut program =
+        Program::new_from_source(Cursor::new(source), "flat_merge", std::io::sink()).unwrap();
+    program.set_eval_config(EvalConfig {
+        skip_flat_reverts,
+        ..Default::default()
+    });
+    program
+}
+
+pub fn flat_vs_recursive_merge(c: &mut Criterion) {
+    for (recursive, kind) in [(false, "flat"), (true, "recursive")] {
+        let source = source(recursive);
+
+        for (skip_flat_reverts, config) in [(false, "reverting"), (true, "skipping flat reverts")] {
+            c.bench_function(&format!("merge {kind} records {FIELDS} ({config})"), |b| {
+                b.iter_batched(
+                    || program(&source, skip_flat_reverts),
+                    |mut program| program.eval_deep().unwrap(),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+}
+
+criterion_group!(
+name = benches;
+config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+targets = flat_vs_recursive_merge
+);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pprof::criterion::{Output, PProfProfiler};
+
+use nickel_lang_core::{
+    error::MergeSide,
+    eval::merge::{merge_report, IncrementalMerge},
+    identifier::Ident,
+    term::{make as mk_term, record::RecordData, IndexMap, RichTerm, Term},
+};
+
+const FIELDS: usize = 3_000;
+
+/// Generate a record of `FIELDS` fields, each one being a small record, where the field `f0` has
+/// the given value.
+fn record(value: i64) -> RecordData {
+    let fields: IndexMap<Ident, RichTerm> = (0..FIELDS)
+        .map(|i| {
+            let inner: IndexMap<Ident, RichTerm> = [("x", i as i64), ("y", 1)]
+                .into_iter()
+                .map(|(id, n)| (Ident::from(id), mk_term::integer(n)))
+                .collect();
+            let inner = if i == 0 {
+                mk_term::integer(value)
+            } else {
+                Term::Record(RecordData::with_field_values(inner)).into()
+            };
+
+            (Ident::from(format!("f{i}")), inner)
+        })
+        .collect();
+
+    RecordData::with_field_values(fields)
+}
+
+pub fn remerge_single_field(c: &mut Criterion) {
+    let merge = IncrementalMerge::new(record(0), record(0));
+    let changed = HashSet::from([Ident::from("f0")]);
+
+    c.bench_function("full re-merge 3000 fields", |b| {
+        b.iter_batched(
+            || (record(0), record(1)),
+            |(r1, r2)| merge_report(r1, r2),
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("incremental re-merge 3000 fields", |b| {
+        b.iter_batched(
+            || (merge.clone(), record(1)),
+            |(mut merge, r2)| merge.update(MergeSide::Right, r2, &changed),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(
+name = benches;
+config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+targets = remerge_single_field
+);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+use criterion::{criterion_main, Criterion};
+use nickel_lang_utils::{bench::EvalMode, ncl_bench_group};
+use pprof::criterion::{Output, PProfProfiler};
+
+ncl_bench_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    {
+        name = "merge with empty record 3000",
+        path = "merge/empty_record",
+        args = (3000),
+        eval_mode = EvalMode::DeepSeq,
+    }, {
+        name = "merge wide records 10000",
+        path = "merge/wide_records",
+        args = (10000),
+        eval_mode = EvalMode::DeepSeq,
+    }, {
+        name = "merge deep records as final result 1000",
+        path = "merge/deep_records",
+        args = (1000),
+        eval_mode = EvalMode::Export,
+    }
+}
+criterion_main!(benches);
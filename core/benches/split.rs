@@ -0,0 +1,133 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    BatchSize, Criterion, Throughput,
+};
+use pprof::criterion::{Output, PProfProfiler};
+
+use nickel_lang_core::{eval::merge::split::split, term::IndexMap};
+
+/// A global allocator counting the number of allocations, in order to report how many
+/// allocations a split performs (see [Allocations]).
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// A criterion measurement counting the allocations performed by a routine instead of timing it.
+pub struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> usize {
+        ALLOCATIONS.load(Ordering::Relaxed)
+    }
+
+    fn end(&self, start: usize) -> usize {
+        ALLOCATIONS.load(Ordering::Relaxed) - start
+    }
+
+    fn add(&self, v1: &usize, v2: &usize) -> usize {
+        v1 + v2
+    }
+
+    fn zero(&self) -> usize {
+        0
+    }
+
+    fn to_f64(&self, value: &usize) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let n = match throughput {
+            Throughput::Elements(n) => n,
+            _ => return "allocs",
+        };
+
+        for value in values {
+            *value /= *n as f64;
+        }
+
+        "allocs/elem"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+const FIELDS: usize = 10_000;
+
+/// Generate two maps of `FIELDS` keys each, sharing half of their keys.
+fn maps() -> (IndexMap<usize, usize>, IndexMap<usize, usize>) {
+    let m1 = (0..FIELDS).map(|i| (i, i)).collect();
+    let m2 = (FIELDS / 2..FIELDS + FIELDS / 2).map(|i| (i, i)).collect();
+    (m1, m2)
+}
+
+pub fn split_wide_records(c: &mut Criterion) {
+    c.bench_function("split 10k fields", |b| {
+        b.iter_batched(maps, |(m1, m2)| split(m1, m2), BatchSize::LargeInput)
+    });
+}
+
+pub fn split_wide_records_allocations(c: &mut Criterion<Allocations>) {
+    c.bench_function("split 10k fields allocations", |b| {
+        b.iter_batched(maps, |(m1, m2)| split(m1, m2), BatchSize::LargeInput)
+    });
+}
+
+criterion_group!(
+name = benches;
+config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+targets = split_wide_records
+);
+criterion_group!(
+name = allocations;
+config = Criterion::default().with_measurement(Allocations);
+targets = split_wide_records_allocations
+);
+criterion_main!(benches, allocations);
@@ -76,6 +76,9 @@ pub struct Cache {
     wildcards: HashMap<FileId, Wildcards>,
     /// Whether processing should try to continue even in case of errors. Needed by the NLS.
     error_tolerance: ErrorTolerance,
+    /// Whether imports are left unresolved until they are evaluated. See
+    /// [Self::set_lazy_imports].
+    lazy_imports: bool,
 
     #[cfg(debug_assertions)]
     /// Skip loading the stdlib, used for debugging purpose
@@ -248,12 +251,25 @@ impl Cache {
             rev_imports: HashMap::new(),
             stdlib_ids: None,
             error_tolerance,
+            lazy_imports: false,
 
             #[cfg(debug_assertions)]
             skip_stdlib: false,
         }
     }
 
+    /// Defer the resolution of imports until they are evaluated (disabled by default).
+    ///
+    /// By default, the imports of a source are resolved transitively before it is typechecked,
+    /// which loads and parses the whole import graph upfront. When imports are lazy, an import is
+    /// only loaded, parsed, typechecked and transformed the first time the evaluator forces it,
+    /// such that merging with a large imported configuration doesn't pay for the parts of the
+    /// import graph that are never used. As a consequence, lazy imports are typed as `Dyn` by the
+    /// typechecker, and errors in imported files are only reported if the import is evaluated.
+    pub fn set_lazy_imports(&mut self, lazy: bool) {
+        self.lazy_imports = lazy;
+    }
+
     /// Load a file in the file database. Do not insert an entry in the name-id table.
     fn load_file(&mut self, path: impl Into<OsString>) -> io::Result<FileId> {
         let path = path.into();
@@ -691,6 +707,12 @@ impl Cache {
             Some(state) if state >= EntryState::ImportsResolved => {
                 Ok(CacheOp::Cached((Vec::new(), Vec::new())))
             }
+            // Lazy imports are left untouched, and resolved by the evaluator when forced. See
+            // [ImportResolver::resolve_lazily].
+            Some(state) if state >= EntryState::Parsed && self.lazy_imports => {
+                self.update_state(file_id, EntryState::ImportsResolved);
+                Ok(CacheOp::Done((Vec::new(), Vec::new())))
+            }
             Some(state) if state >= EntryState::Parsed => {
                 let (pending, errors) = if state < EntryState::ImportsResolving {
                     let TermEntry { term, .. } = self.terms.get(&file_id).unwrap();
@@ -1170,6 +1192,23 @@ pub trait ImportResolver {
         pos: &TermPos,
     ) -> Result<(ResolvedTerm, FileId), ImportError>;
 
+    /// Resolve an import encountered during evaluation, which happens when imports are resolved
+    /// lazily (see [Cache::set_lazy_imports]). Contrary to [Self::resolve], the imported file must
+    /// be ready to be evaluated once this method returns.
+    ///
+    /// The default implementation simply calls [Self::resolve], which is enough for resolvers
+    /// that don't need to further process imported terms.
+    fn resolve_lazily(
+        &mut self,
+        path: &OsStr,
+        parent: Option<PathBuf>,
+        pos: &TermPos,
+    ) -> Result<FileId, Error> {
+        self.resolve(path, parent, pos)
+            .map(|(_, file_id)| file_id)
+            .map_err(Error::from)
+    }
+
     /// Get a resolved import from the term cache.
     fn get(&self, file_id: FileId) -> Option<RichTerm>;
     /// Return the (potentially normalized) file path corresponding to the ID of a resolved import.
@@ -1212,6 +1251,22 @@ impl ImportResolver for Cache {
         Ok((result, file_id))
     }
 
+    fn resolve_lazily(
+        &mut self,
+        path: &OsStr,
+        parent: Option<PathBuf>,
+        pos: &TermPos,
+    ) -> Result<FileId, Error> {
+        let (_, file_id) = self.resolve(path, parent, pos)?;
+        // The import is typechecked against the standard library, as it would have been if it had
+        // been resolved upfront.
+        let initial_ctxt = self
+            .mk_type_ctxt()
+            .unwrap_or_else(|_| typecheck::Context::new());
+        self.prepare(file_id, &initial_ctxt)?;
+        Ok(file_id)
+    }
+
     fn get(&self, file_id: FileId) -> Option<RichTerm> {
         self.terms
             .get(&file_id)
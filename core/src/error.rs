@@ -12,7 +12,7 @@ use crate::{
     label::{
         self,
         ty_path::{self, PathSpan},
-        MergeKind, MergeLabel,
+        ContractId, MergeKind, MergeLabel, MergePath,
     },
     parser::{
         self,
@@ -23,7 +23,7 @@ use crate::{
     position::{RawSpan, TermPos},
     repl,
     serialize::ExportFormat,
-    term::{record::FieldMetadata, RichTerm},
+    term::{record::FieldMetadata, MergeStrategy, RichTerm, Term},
     types::{TypeF, Types, VarKindDiscriminant},
 };
 
@@ -112,6 +112,130 @@ pub enum EvalError {
         /// Additional error-reporting data.
         merge_label: MergeLabel,
     },
+    /// Merging processed more record fields than allowed by the evaluator configuration. See
+    /// [`crate::eval::EvalConfig::merge_fuel`].
+    MergeFuelExhausted {
+        /// The maximum number of fields that merging was allowed to process.
+        budget: usize,
+        /// The label of the merge which exhausted the budget.
+        merge_label: MergeLabel,
+    },
+    /// Merging would have produced a record with more fields than allowed by the evaluator
+    /// configuration. See [`crate::eval::EvalConfig::max_fields`].
+    RecordTooLarge {
+        /// The number of fields of the record that merging would have produced.
+        fields: usize,
+        /// The maximum number of fields of a merged record.
+        max_fields: usize,
+        /// The label of the merge which produced the oversized record.
+        merge_label: MergeLabel,
+    },
+    /// Attempted to merge two arrays of different lengths element-wise.
+    MergeArrayLengthMismatch {
+        /// The length of the left operand.
+        left_len: usize,
+        /// The length of the right operand.
+        right_len: usize,
+        /// The position of the merge expression.
+        pos_op: TermPos,
+    },
+    /// Two fields of a merged record have the same name once normalized by the key normalization
+    /// of the evaluator configuration. See [`crate::eval::EvalConfig::key_normalization`]. This is
+    /// also reported when two fields of a record checked by a record contract only differ by
+    /// case, and record contracts are case-insensitive. See
+    /// [`crate::eval::EvalConfig::case_insensitive_contracts`].
+    MergeKeyCollision {
+        /// The first of the two fields.
+        first: Ident,
+        /// The second of the two fields.
+        second: Ident,
+        /// The label of the merge.
+        merge_label: MergeLabel,
+    },
+    /// Records merged with the `disjoint` strategy define common fields.
+    MergeOverlappingFields {
+        /// The fields defined in both records.
+        fields: Vec<Ident>,
+        /// The label of the merge.
+        merge_label: MergeLabel,
+    },
+    /// Merging two records would add fields to a frozen record.
+    CannotExtendFrozenRecord {
+        /// The operand of the merge which is frozen.
+        side: MergeSide,
+        /// The fields which would be added to the frozen record.
+        fields: Vec<Ident>,
+        /// The label of the merge.
+        merge_label: MergeLabel,
+    },
+    /// Two merged records both declare a required field, but neither gives it a value. Only
+    /// reported when [`crate::eval::EvalConfig::strict_required_fields`] is set.
+    MergeMissingFieldDef {
+        /// The position of the declaration of the field in the left record.
+        left_pos: TermPos,
+        /// The position of the declaration of the field in the right record.
+        right_pos: TermPos,
+        /// The label of the merge. Its path is the path of the field.
+        merge_label: MergeLabel,
+    },
+    /// The contracts accumulated on a field merged from both sides can never both hold, such as
+    /// `Number` and `String`. Only reported when
+    /// [`crate::eval::EvalConfig::check_contradictory_contracts`] is set.
+    MergeContradictoryContracts {
+        /// The first contract.
+        contract1: Types,
+        /// The position of the first contract.
+        span1: RawSpan,
+        /// The second contract, which contradicts the first one.
+        contract2: Types,
+        /// The position of the second contract.
+        span2: RawSpan,
+        /// The label of the merge. Its path is the path of the field.
+        merge_label: MergeLabel,
+    },
+    /// A field is annotated with `merge "name"`, but no merge function is registered under this
+    /// name. See [`crate::eval::EvalConfig::register_merge_fn`].
+    UnknownMergeFn {
+        /// The name of the merge function.
+        name: String,
+        /// The label of the merge.
+        merge_label: MergeLabel,
+    },
+    /// The two definitions of a field, of the same priority, select different merge strategies,
+    /// such as in `{foo | merge sum = 1} & {foo | merge max = 2}`.
+    MergeConflictingStrategies {
+        /// The strategy selected by the left definition.
+        left: MergeStrategy,
+        /// The strategy selected by the right definition.
+        right: MergeStrategy,
+        /// The label of the merge. Its path is the path of the field.
+        merge_label: MergeLabel,
+    },
+    /// A merge function registered by the host application failed.
+    MergeFnFailed {
+        /// The name of the merge function.
+        name: String,
+        /// The error message returned by the merge function.
+        message: String,
+        /// The label of the merge.
+        merge_label: MergeLabel,
+    },
+    /// Merging two records created a cycle of fields which are defined as each other, such as `a`
+    /// and `b` in `{a | default = 1, b = a} & {a = b, b | default = 2}`, while neither record
+    /// had such a cycle.
+    ///
+    /// Only the cycles of plain aliases, whose values are variables referring to other fields, are
+    /// detected. A cycle going through any other expression, as in `{a = b + 1} & {b = a}`, is
+    /// reported as an infinite recursion once evaluated.
+    MergeCycle {
+        /// The fields of the cycle.
+        fields: Vec<Ident>,
+        /// The label of the merge.
+        merge_label: MergeLabel,
+    },
+    /// The evaluation was cancelled through its cancellation token. See
+    /// [`crate::eval::EvalConfig::cancellation`].
+    Cancelled,
     /// An unbound identifier was referenced.
     UnboundIdentifier(Ident, TermPos),
     /// An element in the evaluation Cache was entered during its own update.
@@ -142,18 +266,429 @@ pub enum EvalError {
         /// Evaluated expression
         value: RichTerm,
     },
+    /// An import resolved during evaluation couldn't be loaded, parsed or typechecked. See
+    /// [crate::cache::Cache::set_lazy_imports].
+    ImportFailed(Box<Error>),
     /// An unexpected internal error.
     InternalError(String, TermPos),
     /// Errors occurring rarely enough to not deserve a dedicated variant.
     Other(String, TermPos),
 }
 
+/// An error occurring during the evaluation of a merge. This is a restriction of [EvalError] to
+/// the errors that merging can produce, which can be converted back to an [EvalError].
+#[derive(Debug, PartialEq, Clone)]
+pub enum MergeError {
+    /// Attempted to merge incompatible values. See [EvalError::MergeIncompatibleArgs].
+    IncompatibleArgs {
+        /// The left operand of the merge.
+        left_arg: RichTerm,
+        /// The right operand of the merge.
+        right_arg: RichTerm,
+        /// Additional error-reporting data.
+        merge_label: MergeLabel,
+    },
+    /// A record contract applied through merging was broken, either because the value isn't a
+    /// record or because it has extra fields. See [EvalError::BlameError].
+    BlameError {
+        evaluated_arg: Option<RichTerm>,
+        label: label::Label,
+        call_stack: CallStack,
+    },
+    /// Attempted to merge a record sealed by a polymorphic contract. See
+    /// [EvalError::IllegalPolymorphicTailAccess].
+    IllegalPolymorphicTailAccess {
+        /// The operand of the merge which has a sealed tail.
+        side: MergeSide,
+        /// The label of the merge, locating the merge and the path of the sealed record.
+        merge_label: MergeLabel,
+        evaluated_arg: Option<RichTerm>,
+        label: label::Label,
+        call_stack: CallStack,
+    },
+    /// The merge budget was exhausted. See [EvalError::MergeFuelExhausted].
+    FuelExhausted {
+        budget: usize,
+        merge_label: MergeLabel,
+    },
+    /// The merged record has too many fields. See [EvalError::RecordTooLarge].
+    RecordTooLarge {
+        fields: usize,
+        max_fields: usize,
+        merge_label: MergeLabel,
+    },
+    /// Attempted to merge two arrays of different lengths element-wise.
+    ArrayLengthMismatch {
+        left_len: usize,
+        right_len: usize,
+        /// The position of the merge expression.
+        pos_op: TermPos,
+    },
+    /// Two fields have the same normalized name. See [EvalError::MergeKeyCollision].
+    KeyCollision {
+        first: Ident,
+        second: Ident,
+        merge_label: MergeLabel,
+    },
+    /// Records merged with the `disjoint` strategy define common fields. See
+    /// [EvalError::MergeOverlappingFields].
+    OverlappingFields {
+        fields: Vec<Ident>,
+        merge_label: MergeLabel,
+    },
+    /// Merging would add fields to a frozen record. See [EvalError::CannotExtendFrozenRecord].
+    CannotExtendFrozenRecord {
+        side: MergeSide,
+        fields: Vec<Ident>,
+        merge_label: MergeLabel,
+    },
+    /// A required field is declared on both sides without a value. See
+    /// [EvalError::MergeMissingFieldDef].
+    MissingFieldDef {
+        left_pos: TermPos,
+        right_pos: TermPos,
+        merge_label: MergeLabel,
+    },
+    /// Two contracts of a merged field contradict each other. See
+    /// [EvalError::MergeContradictoryContracts].
+    ContradictoryContracts {
+        contract1: Types,
+        span1: RawSpan,
+        contract2: Types,
+        span2: RawSpan,
+        merge_label: MergeLabel,
+    },
+    /// No merge function is registered under the requested name. See
+    /// [EvalError::UnknownMergeFn].
+    UnknownMergeFn {
+        name: String,
+        merge_label: MergeLabel,
+    },
+    /// The merged definitions select different strategies. See
+    /// [EvalError::MergeConflictingStrategies].
+    ConflictingStrategies {
+        left: MergeStrategy,
+        right: MergeStrategy,
+        merge_label: MergeLabel,
+    },
+    /// A merge function failed. See [EvalError::MergeFnFailed].
+    MergeFnFailed {
+        name: String,
+        message: String,
+        merge_label: MergeLabel,
+    },
+    /// The evaluation was cancelled. See [EvalError::Cancelled].
+    Cancelled,
+}
+
+impl From<MergeError> for EvalError {
+    fn from(error: MergeError) -> Self {
+        match error {
+            MergeError::IncompatibleArgs {
+                left_arg,
+                right_arg,
+                merge_label,
+            } => EvalError::MergeIncompatibleArgs {
+                left_arg,
+                right_arg,
+                merge_label,
+            },
+            MergeError::BlameError {
+                evaluated_arg,
+                label,
+                call_stack,
+            } => EvalError::BlameError {
+                evaluated_arg,
+                label,
+                call_stack,
+            },
+            MergeError::IllegalPolymorphicTailAccess {
+                side,
+                merge_label,
+                evaluated_arg,
+                label,
+                call_stack,
+            } => EvalError::IllegalPolymorphicTailAccess {
+                action: IllegalPolymorphicTailAction::Merge {
+                    side,
+                    path: merge_label.path,
+                    merge_span: merge_label.span,
+                },
+                evaluated_arg,
+                label,
+                call_stack,
+            },
+            MergeError::FuelExhausted {
+                budget,
+                merge_label,
+            } => EvalError::MergeFuelExhausted {
+                budget,
+                merge_label,
+            },
+            MergeError::RecordTooLarge {
+                fields,
+                max_fields,
+                merge_label,
+            } => EvalError::RecordTooLarge {
+                fields,
+                max_fields,
+                merge_label,
+            },
+            MergeError::ArrayLengthMismatch {
+                left_len,
+                right_len,
+                pos_op,
+            } => EvalError::MergeArrayLengthMismatch {
+                left_len,
+                right_len,
+                pos_op,
+            },
+            MergeError::KeyCollision {
+                first,
+                second,
+                merge_label,
+            } => EvalError::MergeKeyCollision {
+                first,
+                second,
+                merge_label,
+            },
+            MergeError::OverlappingFields {
+                fields,
+                merge_label,
+            } => EvalError::MergeOverlappingFields {
+                fields,
+                merge_label,
+            },
+            MergeError::CannotExtendFrozenRecord {
+                side,
+                fields,
+                merge_label,
+            } => EvalError::CannotExtendFrozenRecord {
+                side,
+                fields,
+                merge_label,
+            },
+            MergeError::MissingFieldDef {
+                left_pos,
+                right_pos,
+                merge_label,
+            } => EvalError::MergeMissingFieldDef {
+                left_pos,
+                right_pos,
+                merge_label,
+            },
+            MergeError::ContradictoryContracts {
+                contract1,
+                span1,
+                contract2,
+                span2,
+                merge_label,
+            } => EvalError::MergeContradictoryContracts {
+                contract1,
+                span1,
+                contract2,
+                span2,
+                merge_label,
+            },
+            MergeError::UnknownMergeFn { name, merge_label } => {
+                EvalError::UnknownMergeFn { name, merge_label }
+            }
+            MergeError::ConflictingStrategies {
+                left,
+                right,
+                merge_label,
+            } => EvalError::MergeConflictingStrategies {
+                left,
+                right,
+                merge_label,
+            },
+            MergeError::MergeFnFailed {
+                name,
+                message,
+                merge_label,
+            } => EvalError::MergeFnFailed {
+                name,
+                message,
+                merge_label,
+            },
+            MergeError::Cancelled => EvalError::Cancelled,
+        }
+    }
+}
+
+impl EvalError {
+    /// Return the label of the merge which caused this error, if this error is a merge conflict,
+    /// an exhausted merge budget, an oversized merged record, a key collision, an overlap in a
+    /// disjoint merge, an extension of a frozen record, a required field left without a value,
+    /// contradictory contracts, conflicting merge strategies, a failure of a merge function or a
+    /// cycle created by a merge. This lets consumers of the evaluation API find out where a
+    /// failing merge happened and on which field path, without inspecting the rendered message.
+    pub fn merge_label(&self) -> Option<&MergeLabel> {
+        match self {
+            EvalError::MergeIncompatibleArgs { merge_label, .. }
+            | EvalError::MergeFuelExhausted { merge_label, .. }
+            | EvalError::RecordTooLarge { merge_label, .. }
+            | EvalError::MergeKeyCollision { merge_label, .. }
+            | EvalError::MergeOverlappingFields { merge_label, .. }
+            | EvalError::CannotExtendFrozenRecord { merge_label, .. }
+            | EvalError::MergeMissingFieldDef { merge_label, .. }
+            | EvalError::MergeContradictoryContracts { merge_label, .. }
+            | EvalError::UnknownMergeFn { merge_label, .. }
+            | EvalError::MergeConflictingStrategies { merge_label, .. }
+            | EvalError::MergeFnFailed { merge_label, .. }
+            | EvalError::MergeCycle { merge_label, .. } => Some(merge_label),
+            _ => None,
+        }
+    }
+
+    /// Return `true` if this error originates from a merge, be it a conflict between two values,
+    /// an exhausted merge budget, an oversized merged record, a length mismatch during an
+    /// element-wise array merge, a collision between normalized field names, an overlap in a
+    /// disjoint merge, an extension of a frozen record, a required field left without a value,
+    /// contradictory contracts, conflicting merge strategies, a failure of a merge function or a
+    /// cycle created by a merge.
+    pub fn is_merge_error(&self) -> bool {
+        matches!(
+            self,
+            EvalError::MergeIncompatibleArgs { .. }
+                | EvalError::MergeFuelExhausted { .. }
+                | EvalError::RecordTooLarge { .. }
+                | EvalError::MergeArrayLengthMismatch { .. }
+                | EvalError::MergeKeyCollision { .. }
+                | EvalError::MergeOverlappingFields { .. }
+                | EvalError::CannotExtendFrozenRecord { .. }
+                | EvalError::MergeMissingFieldDef { .. }
+                | EvalError::MergeContradictoryContracts { .. }
+                | EvalError::UnknownMergeFn { .. }
+                | EvalError::MergeConflictingStrategies { .. }
+                | EvalError::MergeFnFailed { .. }
+                | EvalError::MergeCycle { .. }
+        )
+    }
+
+    /// Return the identifier of the record contract a blame error originates from, if any. This
+    /// lets consumers of the evaluation API group the violations of each record contract, as in
+    /// `MyServiceContract: 3 violations`.
+    pub fn contract_id(&self) -> Option<&ContractId> {
+        match self {
+            EvalError::BlameError { label, .. } => label.contract_id.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Return the positions involved in a merge error in a structured form, for editors to render
+    /// the merge site together with the values involved. Returns `None` if this error doesn't
+    /// originate from a merge. The values without a known position are left out.
+    pub fn merge_ranges(&self) -> Option<MergeRanges> {
+        let merge_label = self.merge_label()?;
+
+        let related: Vec<(TermPos, String)> = match self {
+            EvalError::MergeIncompatibleArgs {
+                left_arg,
+                right_arg,
+                ..
+            } => vec![
+                (left_arg.pos, "cannot merge this expression".into()),
+                (right_arg.pos, "with this expression".into()),
+            ],
+            EvalError::MergeKeyCollision { first, second, .. } => vec![
+                (first.pos, format!("field `{first}`")),
+                (
+                    second.pos,
+                    format!("has the same normalized name as `{first}`"),
+                ),
+            ],
+            EvalError::MergeMissingFieldDef {
+                left_pos,
+                right_pos,
+                ..
+            } => vec![
+                (*left_pos, "required here".into()),
+                (*right_pos, "and here".into()),
+            ],
+            EvalError::MergeContradictoryContracts {
+                contract1,
+                span1,
+                contract2,
+                span2,
+                ..
+            } => vec![
+                (
+                    TermPos::Original(*span1),
+                    format!("expected a value of type `{contract1}`"),
+                ),
+                (
+                    TermPos::Original(*span2),
+                    format!("but also of type `{contract2}`"),
+                ),
+            ],
+            _ => Vec::new(),
+        };
+
+        let primary_message = match merge_label.kind {
+            MergeKind::Standard => "originally merged here",
+            MergeKind::PiecewiseDef => "when combining the definitions of this field",
+        };
+
+        Some(MergeRanges {
+            primary: merge_label.span,
+            primary_message: primary_message.into(),
+            related: related
+                .into_iter()
+                .filter_map(|(pos, message)| Some((pos.into_opt()?, message)))
+                .collect(),
+        })
+    }
+}
+
+/// The positions involved in a merge error, for editor integration such as the language server.
+/// Each span can be converted to a range of its source: the merge site is the primary range of
+/// the diagnostic, and the values involved are related information. See
+/// [EvalError::merge_ranges].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeRanges {
+    /// The span of the merge expression, or of the field for a piecewise definition.
+    pub primary: RawSpan,
+    /// A description of the primary span.
+    pub primary_message: String,
+    /// The spans of the values involved in the error, such as the two conflicting values of a
+    /// field, each with a description.
+    pub related: Vec<(RawSpan, String)>,
+}
+
+/// One of the two operands of a merge.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MergeSide {
+    Left,
+    Right,
+}
+
+impl std::fmt::Display for MergeSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeSide::Left => write!(f, "left"),
+            MergeSide::Right => write!(f, "right"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum IllegalPolymorphicTailAction {
-    FieldAccess { field: String },
+    FieldAccess {
+        field: String,
+    },
     Map,
-    Merge,
-    RecordRemove { field: String },
+    Merge {
+        /// The operand of the merge which has a sealed tail.
+        side: MergeSide,
+        /// The path of the sealed record, relative to the original merge.
+        path: MergePath,
+        /// The span of the original merge.
+        merge_span: RawSpan,
+    },
+    RecordRemove {
+        field: String,
+    },
 }
 
 impl IllegalPolymorphicTailAction {
@@ -165,7 +700,10 @@ impl IllegalPolymorphicTailAction {
                 format!("cannot access field `{field}` sealed by a polymorphic contract")
             }
             Map => "cannot map over a record sealed by a polymorphic contract".to_owned(),
-            Merge => "cannot merge a record sealed by a polymorphic contract".to_owned(),
+            Merge { side, .. } => format!(
+                "cannot merge a record sealed by a polymorphic contract: \
+                the record on the {side} of the merge has a sealed polymorphic tail"
+            ),
             RecordRemove { field } => {
                 format!("cannot remove field `{field}` sealed by a polymorphic contract")
             }
@@ -460,6 +998,8 @@ pub enum ParseError {
         /// The previous instance of the duplicated identifier.
         prev_ident: Ident,
     },
+    /// A `merge` annotation used a name which doesn't correspond to any known merge strategy.
+    UnknownMergeStrategy(Ident),
 }
 
 /// An error occurring during the resolution of an import.
@@ -658,6 +1198,9 @@ impl ParseError {
                 InternalParseError::DuplicateIdentInRecordPattern { ident, prev_ident } => {
                     ParseError::DuplicateIdentInRecordPattern { ident, prev_ident }
                 }
+                InternalParseError::UnknownMergeStrategy(strategy) => {
+                    ParseError::UnknownMergeStrategy(strategy)
+                }
             },
         }
     }
@@ -879,6 +1422,47 @@ fn cardinal(number: usize) -> String {
     format!("{number}{suffix}")
 }
 
+/// Render the start of a span as `file:line`, or as the bare file name if the line can't be
+/// determined.
+fn source_location(span: &RawSpan, files: &Files<String>) -> String {
+    let name = files.name(span.src_id).to_string_lossy();
+
+    match files.location(span.src_id, span.start) {
+        Ok(location) => format!("{name}:{}", location.line.number()),
+        Err(_) => name.into_owned(),
+    }
+}
+
+/// Return additional notes for a merge between a record and a value of another type, or nothing
+/// if the operands are not in this situation.
+fn record_merge_mismatch_notes(left_arg: &RichTerm, right_arg: &RichTerm) -> Vec<String> {
+    let other = match (left_arg.as_ref(), right_arg.as_ref()) {
+        (Term::Record(..) | Term::RecRecord(..), Term::Record(..) | Term::RecRecord(..)) => {
+            return Vec::new()
+        }
+        (Term::Record(..) | Term::RecRecord(..), other)
+        | (other, Term::Record(..) | Term::RecRecord(..)) => other,
+        _ => return Vec::new(),
+    };
+
+    let other_type = other
+        .type_of()
+        .unwrap_or_else(|| String::from("<unevaluated>"));
+    let mut notes = vec![format!(
+        "A record can only be merged with another record, but the other operand evaluated to a \
+        value of type `{other_type}`."
+    )];
+
+    if matches!(
+        other,
+        Term::Fun(..) | Term::FunPattern(..) | Term::Match { .. }
+    ) {
+        notes.push("Have you forgotten to apply a function to all of its arguments?".into());
+    }
+
+    notes
+}
+
 impl IntoDiagnostics<FileId> for Error {
     fn into_diagnostics(
         self,
@@ -993,6 +1577,7 @@ impl IntoDiagnostics<FileId> for EvalError {
                     .with_notes(vec![msg])]
             }
             EvalError::ParseError(parse_error) => parse_error.into_diagnostics(files, stdlib_ids),
+            EvalError::ImportFailed(error) => (*error).into_diagnostics(files, stdlib_ids),
             EvalError::NotAFunc(t, arg, pos_opt) => vec![Diagnostic::error()
                 .with_message("not a function")
                 .with_labels(vec![
@@ -1076,19 +1661,277 @@ impl IntoDiagnostics<FileId> for EvalError {
 
                 labels.push(secondary(&merge_label.span).with_message(span_label));
 
+                let mut message = if merge_label.path.is_empty() {
+                    String::from("non mergeable terms")
+                } else {
+                    format!("non mergeable terms at `{}`", merge_label.path)
+                };
+
+                if let Some(name) = &merge_label.name {
+                    message.push_str(&format!(" while applying `{name}`"));
+                }
+
+                let mut notes = vec![
+                    "Both values have the same merge priority but they can't \
+                    be combined."
+                        .into(),
+                    "Primitive values (Number, String, and Bool) or arrays can be merged \
+                    only if they are equal."
+                        .into(),
+                    "Functions can never be merged.".into(),
+                ];
+                notes.extend(record_merge_mismatch_notes(&left_arg, &right_arg));
+
+                // In a configuration split across several files, the labels alone don't make it
+                // obvious that the conflicting values come from different files.
+                if let (Some(left_span), Some(right_span)) =
+                    (left_arg.pos.as_opt_ref(), right_arg.pos.as_opt_ref())
+                {
+                    if left_span.src_id != right_span.src_id {
+                        notes.push(format!(
+                            "The left value comes from {}, and the right value from {}.",
+                            source_location(left_span, files),
+                            source_location(right_span, files)
+                        ));
+                    }
+                }
+
                 vec![Diagnostic::error()
-                    .with_message("non mergeable terms")
+                    .with_message(message)
                     .with_labels(labels)
+                    .with_notes(notes)]
+            }
+            EvalError::MergeFuelExhausted {
+                budget,
+                merge_label,
+            } => vec![Diagnostic::error()
+                .with_message("merge fuel exhausted")
+                .with_labels(vec![
+                    primary(&merge_label.span).with_message("while evaluating this merge")
+                ])
+                .with_notes(vec![
+                    format!(
+                        "Merging processed more than {budget} record fields, which is the \
+                        maximum allowed by the evaluator configuration."
+                    ),
+                    "This may indicate a merge expression whose size grows exponentially.".into(),
+                ])],
+            EvalError::RecordTooLarge {
+                fields,
+                max_fields,
+                merge_label,
+            } => vec![Diagnostic::error()
+                .with_message("merged record too large")
+                .with_labels(vec![
+                    primary(&merge_label.span).with_message("while evaluating this merge")
+                ])
+                .with_notes(vec![format!(
+                    "This merge would produce a record with {fields} fields, but the evaluator \
+                    configuration allows at most {max_fields} fields per record."
+                )])],
+            EvalError::MergeKeyCollision {
+                first,
+                second,
+                merge_label,
+            } => vec![Diagnostic::error()
+                .with_message(format!(
+                    "fields `{first}` and `{second}` have the same normalized name"
+                ))
+                .with_labels(vec![
+                    primary(&merge_label.span).with_message("while evaluating this merge")
+                ])
+                .with_notes(vec![
+                    "The evaluator configuration normalizes the names of the fields of merged \
+                    records, or matches the fields of record contracts case-insensitively. A \
+                    record can't have two fields with the same normalized name, as it would be \
+                    ambiguous which one is merged or checked."
+                        .into(),
+                ])],
+            EvalError::MergeOverlappingFields {
+                fields,
+                merge_label,
+            } => {
+                let fields: Vec<String> = fields.iter().map(|id| format!("`{id}`")).collect();
+
+                vec![Diagnostic::error()
+                    .with_message(format!(
+                        "fields defined in both layers of a disjoint merge: {}",
+                        fields.join(", ")
+                    ))
+                    .with_labels(vec![
+                        primary(&merge_label.span).with_message("while evaluating this merge")
+                    ])
                     .with_notes(vec![
-                        "Both values have the same merge priority but they can't \
-                        be combined."
+                        "The records are merged with the `disjoint` strategy, which requires \
+                        them to define different fields."
                             .into(),
-                        "Primitive values (Number, String, and Bool) or arrays can be merged \
-                        only if they are equal."
+                    ])]
+            }
+            EvalError::CannotExtendFrozenRecord {
+                side,
+                fields,
+                merge_label,
+            } => {
+                let fields: Vec<String> = fields.iter().map(|id| format!("`{id}`")).collect();
+                let plural = if fields.len() == 1 { "" } else { "s" };
+
+                vec![Diagnostic::error()
+                    .with_message(format!(
+                        "cannot add field{plural} {} to a frozen record",
+                        fields.join(", ")
+                    ))
+                    .with_labels(vec![
+                        primary(&merge_label.span).with_message("while evaluating this merge")
+                    ])
+                    .with_notes(vec![
+                        format!(
+                            "The {side} operand of the merge is frozen by a `frozen` annotation. \
+                            Its fields can be overridden, but no field can be added to it."
+                        ),
+                        "Remove the `frozen` annotation to allow the record to be extended.".into(),
+                    ])]
+            }
+            EvalError::MergeMissingFieldDef {
+                left_pos,
+                right_pos,
+                merge_label,
+            } => {
+                let mut labels = vec![];
+
+                if let Some(span) = left_pos.into_opt() {
+                    labels.push(primary(&span).with_message("required here"));
+                }
+
+                if let Some(span) = right_pos.into_opt() {
+                    labels.push(primary(&span).with_message("and here"));
+                }
+
+                labels.push(secondary(&merge_label.span).with_message("merged here"));
+
+                vec![Diagnostic::error()
+                    .with_message(format!(
+                        "missing definition for `{}` after merging",
+                        merge_label.path
+                    ))
+                    .with_labels(labels)
+                    .with_notes(vec![
+                        "Both records require this field, but neither gives it a value.".into(),
+                        "Provide a value for this field in one of the records, or make it \
+                        optional."
+                            .into(),
+                    ])]
+            }
+            EvalError::MergeContradictoryContracts {
+                contract1,
+                span1,
+                contract2,
+                span2,
+                merge_label,
+            } => vec![Diagnostic::error()
+                .with_message(format!(
+                    "contradictory contracts for `{}` after merging",
+                    merge_label.path
+                ))
+                .with_labels(vec![
+                    primary(&span1).with_message(format!("expected a value of type `{contract1}`")),
+                    primary(&span2).with_message(format!("but also of type `{contract2}`")),
+                    secondary(&merge_label.span).with_message("merged here"),
+                ])
+                .with_notes(vec![
+                    "No value can satisfy both contracts, so using this field would always fail."
+                        .into(),
+                    "Remove one of the contracts, or fix the one which doesn't match the intended \
+                    type."
+                        .into(),
+                ])],
+            EvalError::UnknownMergeFn { name, merge_label } => vec![Diagnostic::error()
+                .with_message(format!("unknown merge function `{name}`"))
+                .with_labels(vec![
+                    primary(&merge_label.span).with_message("while evaluating this merge")
+                ])
+                .with_notes(vec![format!(
+                    "The field `{}` is annotated with `merge \"{name}\"`, but the \
+                    application evaluating this program didn't register a merge function \
+                    under this name.",
+                    merge_label.path
+                )])],
+            EvalError::MergeConflictingStrategies {
+                left,
+                right,
+                merge_label,
+            } => vec![Diagnostic::error()
+                .with_message(format!(
+                    "conflicting merge strategies `{left}` and `{right}`"
+                ))
+                .with_labels(vec![
+                    primary(&merge_label.span).with_message("while evaluating this merge")
+                ])
+                .with_notes(vec![
+                    format!(
+                        "Both definitions of the field `{}` have the same priority, but select \
+                        different merge strategies, so it's ambiguous how to merge them.",
+                        merge_label.path
+                    ),
+                    "Annotate only one of the definitions, or give them different priorities: \
+                    the strategy of the definition with the highest priority is used."
+                        .into(),
+                ])],
+            EvalError::MergeFnFailed {
+                name,
+                message,
+                merge_label,
+            } => vec![Diagnostic::error()
+                .with_message(format!("merge function `{name}` failed"))
+                .with_labels(vec![
+                    primary(&merge_label.span).with_message("while evaluating this merge")
+                ])
+                .with_notes(vec![message])],
+            EvalError::MergeCycle {
+                fields,
+                merge_label,
+            } => {
+                let fields: Vec<String> = fields.iter().map(|id| format!("`{id}`")).collect();
+
+                vec![Diagnostic::error()
+                    .with_message(format!(
+                        "merging created a cycle between the fields {}",
+                        fields.join(", ")
+                    ))
+                    .with_labels(vec![
+                        primary(&merge_label.span).with_message("while evaluating this merge")
+                    ])
+                    .with_notes(vec![
+                        "Each of these fields is defined as another field of the cycle, so \
+                        evaluating one of them requires evaluating itself."
+                            .into(),
+                        "Neither of the merged records has this cycle: it appears when the \
+                        definitions of one record override the definitions of the other."
                             .into(),
-                        "Functions can never be merged.".into(),
                     ])]
             }
+            EvalError::MergeArrayLengthMismatch {
+                left_len,
+                right_len,
+                pos_op,
+            } => {
+                let labels = pos_op
+                    .as_opt_ref()
+                    .map(|span| vec![primary(span).with_message("in this merge")])
+                    .unwrap_or_default();
+
+                vec![Diagnostic::error()
+                    .with_message("cannot merge arrays element-wise: array length mismatch")
+                    .with_labels(labels)
+                    .with_notes(vec![format!(
+                        "The left array has {left_len} elements, but the right array has \
+                        {right_len} elements."
+                    )])]
+            }
+            EvalError::Cancelled => vec![Diagnostic::error()
+                .with_message("evaluation cancelled")
+                .with_notes(vec![
+                    "The evaluation was cancelled before it could complete.".into(),
+                ])],
             EvalError::UnboundIdentifier(ident, span_opt) => vec![Diagnostic::error()
                 .with_message(format!("unbound identifier `{ident}`"))
                 .with_labels(vec![primary_alt(
@@ -1165,14 +2008,46 @@ impl IntoDiagnostics<FileId> for EvalError {
                 label: contract_label,
                 evaluated_arg,
                 call_stack,
-            } => blame_error::blame_diagnostics(
-                files,
-                stdlib_ids,
-                contract_label,
-                evaluated_arg,
-                &call_stack,
-                &format!(": {}", &action.message()),
-            ),
+            } => {
+                let mut diagnostics = blame_error::blame_diagnostics(
+                    files,
+                    stdlib_ids,
+                    contract_label,
+                    evaluated_arg,
+                    &call_stack,
+                    &format!(": {}", &action.message()),
+                );
+
+                // The polymorphic contract which sealed the tail is already reported by the blame
+                // diagnostics. We add the location of the merge and the path of the sealed record
+                // inside the merged value, which can be far apart in a large configuration.
+                if let (
+                    IllegalPolymorphicTailAction::Merge {
+                        path, merge_span, ..
+                    },
+                    Some(head),
+                ) = (&action, diagnostics.first_mut())
+                {
+                    head.labels
+                        .push(secondary(merge_span).with_message("while merging here"));
+
+                    if !path.is_empty() {
+                        head.notes.push(format!(
+                            "The record with a sealed tail is the value of the field `{path}` of \
+                            the merged records."
+                        ));
+                    }
+
+                    head.notes.push(
+                        "To merge into a record with a polymorphic tail, restructure the code \
+                        such that the merge happens before the record is sealed, or move the \
+                        fields stored in the tail into an explicit field of the record type."
+                            .into(),
+                    );
+                }
+
+                diagnostics
+            }
             EvalError::UnaryPrimopTypeError {
                 primop,
                 ref expected,
@@ -1785,6 +2660,16 @@ impl IntoDiagnostics<FileId> for ParseError {
                         secondary(&prev_ident.pos.unwrap()).with_message("previous binding here"),
                         primary(&ident.pos.unwrap()).with_message("duplicated binding here"),
                     ]),
+            ParseError::UnknownMergeStrategy(strategy) => Diagnostic::error()
+                .with_message(format!("unknown merge strategy `{strategy}`"))
+                .with_labels(vec![primary(&strategy.pos.unwrap())])
+                .with_notes(vec![
+                    "The available merge strategies are `standard`, `element-wise`, `set`, \
+                    `broadcast`, `concat`, `intersect`, `sum`, `max`, `min`, `unsealed`, \
+                    `disjoint`, `left`, `right` and `compare`, which must be followed by a \
+                    comparator between parentheses."
+                        .into(),
+                ]),
         };
 
         vec![diagnostic]
@@ -2169,6 +3054,33 @@ impl IntoDiagnostics<FileId> for ExportError {
     }
 }
 
+impl IntoDiagnostics<FileId> for crate::eval::merge::DocOverrideWarning {
+    fn into_diagnostics(
+        self,
+        _files: &mut Files<String>,
+        _stdlib_ids: Option<&Vec<FileId>>,
+    ) -> Vec<Diagnostic<FileId>> {
+        let labels = self
+            .pos
+            .as_opt_ref()
+            .map(|span| vec![primary(span).with_message("this documentation is dropped")])
+            .unwrap_or_default();
+
+        vec![Diagnostic::warning()
+            .with_message(format!(
+                "documentation on field `{}` was overridden during merge",
+                self.path
+            ))
+            .with_labels(labels)
+            .with_notes(vec![
+                format!("Dropped documentation: {}", self.doc),
+                "The merged field keeps the documentation of the left operand, whichever value \
+                takes precedence."
+                    .into(),
+            ])]
+    }
+}
+
 impl IntoDiagnostics<FileId> for IOError {
     fn into_diagnostics(
         self,
@@ -5,6 +5,7 @@ use crate::{
     term::{record::FieldDeps, BindingType, RichTerm, Term},
 };
 use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
 use std::rc::{Rc, Weak};
 
 /// The state of a thunk.
@@ -560,6 +561,76 @@ impl Thunk {
         self.data.borrow().deps()
     }
 }
+/// A deep copy of the thunks reachable from an environment. Evaluating the copies never updates
+/// the original thunks, nor any thunk reachable from them, which lets the evaluation of a term
+/// run in isolation (see [crate::eval::VirtualMachine::merge_preview]).
+///
+/// The copy preserves sharing and cycles, such as the ones of recursive records: each thunk is
+/// copied once, and every reference to it is replaced by the same copy. The thunks are copied
+/// with their current state, so that the values already evaluated aren't evaluated again.
+/// Thunks are only reachable through environments, as terms don't embed thunks directly: the
+/// terms themselves are shared with the originals.
+#[derive(Default)]
+pub struct ThunkCopier {
+    /// The copy of each original thunk, indexed by the address of its data.
+    copies: HashMap<*const RefCell<ThunkData>, Rc<RefCell<ThunkData>>>,
+    /// The thunks copied whose environments haven't been copied yet, paired with their copy.
+    /// Using an explicit work list instead of recursing keeps the native stack bounded, however
+    /// deep the graph of thunks is.
+    pending: Vec<(Rc<RefCell<ThunkData>>, Rc<RefCell<ThunkData>>)>,
+}
+
+impl ThunkCopier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a copy of `env` where each thunk, and each thunk reachable from it, is replaced by
+    /// an independent copy.
+    pub fn copy_env(&mut self, env: &Environment) -> Environment {
+        let copy = self.copy_bindings(env);
+
+        while let Some((original, copy)) = self.pending.pop() {
+            let data = original.borrow().map(|closure| Closure {
+                body: closure.body.clone(),
+                env: self.copy_bindings(&closure.env),
+            });
+            *copy.borrow_mut() = data;
+        }
+
+        copy
+    }
+
+    /// Copy the bindings of an environment, leaving the environments of the copied thunks to be
+    /// processed by [Self::copy_env].
+    fn copy_bindings(&mut self, env: &Environment) -> Environment {
+        env.iter()
+            .map(|(id, thunk)| (*id, self.copy_thunk(thunk)))
+            .collect()
+    }
+
+    fn copy_thunk(&mut self, thunk: &Thunk) -> Thunk {
+        let key = Rc::as_ptr(&thunk.data);
+
+        let data = match self.copies.get(&key) {
+            Some(copy) => Rc::clone(copy),
+            None => {
+                // The content is a placeholder until the environments are copied.
+                let copy = Rc::new(RefCell::new(thunk.data.borrow().clone()));
+                self.copies.insert(key, Rc::clone(&copy));
+                self.pending
+                    .push((Rc::clone(&thunk.data), Rc::clone(&copy)));
+                copy
+            }
+        };
+
+        Thunk {
+            data,
+            ident_kind: thunk.ident_kind,
+        }
+    }
+}
+
 /// A thunk update frame.
 ///
 /// A thunk update frame is put on the stack whenever a variable is entered, such that once this
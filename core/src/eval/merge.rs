@@ -24,33 +24,536 @@
 //! One can think of merge to be defined on metadata as well. When merging two fields, the
 //! resulting metadata is the result of merging the two original field's metadata. The semantics
 //! depend on each metadata.
+//!
+//! When a record contract is applied, a default value provided by the contract is considered of
+//! strictly lower priority than a default value of the checked record: the latter wins instead of
+//! being merged with the contract's default.
+//!
+//! ## Sealed tails
+//!
+//! Merging a record whose tail has been sealed by a polymorphic contract is an error, as it would
+//! break parametricity. The `unsealed` merge strategy lifts this restriction by unsealing the
+//! tails first, at the cost of giving up on the guarantees of the polymorphic contract.
+//!
+//! ## Ordering
+//!
+//! The result of a merge only depends on its operands, and not on hashing or allocation. The
+//! fields of the merged record are stored in an [IndexMap], whose iteration order is the insertion
+//! order, and the merge guarantees the following order: the fields of `r1` come first, in the
+//! order of `r1`, followed by the fields of `r2` that aren't in `r1`, in the order of `r2`. In
+//! particular, a field defined in both operands stays at the position where `r1` introduced it.
+//! Recursive fields are saturated following this order as well. Serialization and
+//! pretty-printing sort the fields by name anyway, so that the exported result of a merge is
+//! reproducible, but consumers of the evaluated record which care about the order of fields can
+//! rely on this one.
+//!
+//! ## Parallelism
+//!
+//! The fields defined in both operands are merged independently of each other, but they are
+//! merged sequentially nonetheless. Merging a field allocates elements in the evaluation cache and
+//! binds them in the environment of the merged record, and both are built on reference-counted,
+//! single-threaded data structures. Merging fields in parallel would require the cache and the
+//! environments to be shared between threads, which is a much larger change than the merge
+//! itself. The `merge wide records` benchmark measures the cost of merging many common fields.
 use super::*;
-use crate::error::{EvalError, IllegalPolymorphicTailAction};
-use crate::label::{Label, MergeLabel};
+use crate::error::{EvalError, MergeError, MergeSide};
+use crate::label::{ContractId, Label, MergeLabel, MergePath};
 use crate::position::TermPos;
 use crate::term::{
-    record::{self, Field, FieldDeps, FieldMetadata, RecordAttrs, RecordData},
-    BinaryOp, IndexMap, RichTerm, Term, TypeAnnotation,
+    array::Array,
+    record::{self, Field, FieldDeps, FieldMetadata, PriorityResolution, RecordAttrs, RecordData},
+    BinaryOp, IndexMap, LabeledType, MergePriority, MergeStrategy, RichTerm, Term, TypeAnnotation,
+    UnaryOp,
 };
 use crate::transform::Closurizable;
 
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
 /// Merging mode. Merging is used both to combine standard data and to apply contracts defined as
 /// records.
 #[derive(Clone, PartialEq, Debug)]
 pub enum MergeMode {
     /// Standard merging, for combining data.
     Standard(MergeLabel),
-    /// Merging to apply a record contract to a value, with the associated label.
-    Contract(Label),
+    /// Merging to apply a record contract to a value, with the associated label and the handling
+    /// of the fields declared by the contract but absent from the value.
+    Contract(Label, AbsentFields),
+    /// Merging to fill a record with default values. The fields defined on both sides always
+    /// take the value of the left operand, without being recursively merged, while the fields
+    /// defined on only one side are combined as usual. The operands must be records.
+    FillDefaults(MergeLabel),
+    /// Merging records recursively while preferring the left operand. As opposed to
+    /// `FillDefaults`, the records defined on both sides are merged recursively in the same mode,
+    /// but any other pair of values takes the value of the left operand without conflicting.
+    /// Priorities are still honored. The top-level operands must be records.
+    LeftBiased(MergeLabel),
+}
+
+impl MergeMode {
+    /// Return the merge strategy to use. Contract application always uses the standard strategy.
+    pub fn strategy(&self) -> MergeStrategy {
+        match self {
+            MergeMode::Standard(merge_label)
+            | MergeMode::FillDefaults(merge_label)
+            | MergeMode::LeftBiased(merge_label) => merge_label.strategy.clone(),
+            MergeMode::Contract(..) => MergeStrategy::Standard,
+        }
+    }
+
+    /// In contract mode, set the identifier of the record contract on its own label, such that
+    /// the errors raised by the record contract itself can be grouped with the errors of its
+    /// fields. See [ContractId].
+    fn identify_contract(self) -> Self {
+        match self {
+            MergeMode::Contract(label, absent_fields) => {
+                let contract_id = ContractId::of(&label);
+                MergeMode::Contract(label.with_contract_id(contract_id), absent_fields)
+            }
+            mode => mode,
+        }
+    }
+}
+
+/// A compact rendering meant for logs, such as `Standard(span=3..12)` or `Contract("Number")`,
+/// instead of the verbose derived `Debug` implementation. A contract is shown as the type it
+/// checks.
+impl std::fmt::Display for MergeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MergeMode::Standard(merge_label) => write!(f, "Standard({merge_label})"),
+            MergeMode::Contract(label, AbsentFields::Lenient) => {
+                write!(f, "Contract(\"{}\")", label.types)
+            }
+            MergeMode::Contract(label, AbsentFields::Strict) => {
+                write!(f, "StrictContract(\"{}\")", label.types)
+            }
+            MergeMode::FillDefaults(merge_label) => write!(f, "FillDefaults({merge_label})"),
+            MergeMode::LeftBiased(merge_label) => write!(f, "LeftBiased({merge_label})"),
+        }
+    }
 }
 
 impl From<MergeMode> for MergeLabel {
-    /// Either takes the inner merge label if the mode is `Standard`, or converts a contract label
-    /// to a merge label if the mode is `Contract`.
+    /// Either takes the inner merge label if the mode is `Standard`, `FillDefaults` or
+    /// `LeftBiased`, or converts a contract label to a merge label if the mode is `Contract`.
     fn from(mode: MergeMode) -> Self {
         match mode {
-            MergeMode::Standard(merge_label) => merge_label,
-            MergeMode::Contract(label) => label.into(),
+            MergeMode::Standard(merge_label)
+            | MergeMode::FillDefaults(merge_label)
+            | MergeMode::LeftBiased(merge_label) => merge_label,
+            MergeMode::Contract(label, _) => label.into(),
+        }
+    }
+}
+
+/// How a record contract handles the fields that it declares, but which the checked value doesn't
+/// define. In both modes, a field with a default value is filled with this value, and the fields
+/// defined by the value are checked as usual. See [`EvalConfig::absent_fields`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AbsentFields {
+    /// An absent optional field is left absent. An absent required field is only reported when
+    /// it is used, for example when the value is exported, which allows a value to be completed by
+    /// merging after the contract has been applied.
+    #[default]
+    Lenient,
+    /// Every declared field without a default value, including optional fields, must be defined
+    /// by the value. Absent fields are reported right away when the contract is applied.
+    Strict,
+}
+
+/// The remaining budget of record fields that merging is allowed to process. See
+/// [`EvalConfig::merge_fuel`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MergeFuel {
+    /// The initial budget, or `None` if merging is unbounded.
+    budget: Option<usize>,
+    /// The number of fields processed so far.
+    consumed: usize,
+}
+
+impl MergeFuel {
+    pub fn new(budget: Option<usize>) -> Self {
+        MergeFuel {
+            budget,
+            consumed: 0,
+        }
+    }
+
+    /// Consume `amount` units of fuel, or fail if the budget is exhausted.
+    fn consume(&mut self, amount: usize, merge_label: &MergeLabel) -> Result<(), MergeError> {
+        self.consumed = self.consumed.saturating_add(amount);
+
+        match self.budget {
+            Some(budget) if self.consumed > budget => Err(MergeError::FuelExhausted {
+                budget,
+                merge_label: merge_label.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A flag shared between the evaluator and its embedder, which can be set to cancel an ongoing
+/// evaluation, for example from another thread. Merging checks it once per merged record and once
+/// per merged field, and aborts with [`EvalError::Cancelled`] when it is set. See
+/// [`EvalConfig::cancellation`].
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation of the evaluations using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Two tokens are equal if they share the same flag.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+/// A normalization of field names, such as converting them to snake case, under which the fields
+/// of merged records are matched. See [EvalConfig::key_normalization].
+///
+/// When merging two records, a field of the right record is merged with the field of the left
+/// record which has the same normalized name, and the merged field keeps the name of the left one.
+/// The other fields keep their name. Two fields of the same record with the same normalized name
+/// are ambiguous, and make the merge fail with [`EvalError::MergeKeyCollision`].
+///
+/// Renaming a field of the right record doesn't update the recursive references to its original
+/// name inside this record.
+#[derive(Clone)]
+pub struct KeyNormalization(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl KeyNormalization {
+    pub fn new(normalize: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        KeyNormalization(Arc::new(normalize))
+    }
+
+    /// Normalize a field name.
+    pub fn normalize(&self, key: &str) -> String {
+        (self.0)(key)
+    }
+}
+
+impl std::fmt::Debug for KeyNormalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("KeyNormalization(..)")
+    }
+}
+
+/// Two normalizations are equal if they share the same function.
+impl PartialEq for KeyNormalization {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::as_ptr(&self.0).cast::<()>() == Arc::as_ptr(&other.0).cast::<()>()
+    }
+}
+
+impl Eq for KeyNormalization {}
+
+/// A merge function defined by the host application, which merges the values of the fields
+/// annotated with `merge "name"`, where `name` is the name it's registered under. See
+/// [EvalConfig::register_merge_fn].
+///
+/// The function is given the values to merge, evaluated to a weak head normal form, and returns
+/// the merged value or an error message. Numbers, strings, booleans and enum tags are thus fully
+/// evaluated, while the content of records and arrays may not be. The merged value is evaluated in
+/// the combined environment of both values (see [merge_environments]), such that an unevaluated
+/// value can be returned as it is.
+#[derive(Clone)]
+pub struct MergeFn(Arc<dyn Fn(&RichTerm, &RichTerm) -> Result<RichTerm, String> + Send + Sync>);
+
+impl MergeFn {
+    pub fn new(
+        merge_fn: impl Fn(&RichTerm, &RichTerm) -> Result<RichTerm, String> + Send + Sync + 'static,
+    ) -> Self {
+        MergeFn(Arc::new(merge_fn))
+    }
+
+    /// Merge two values.
+    pub fn call(&self, left: &RichTerm, right: &RichTerm) -> Result<RichTerm, String> {
+        (self.0)(left, right)
+    }
+}
+
+impl std::fmt::Debug for MergeFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MergeFn(..)")
+    }
+}
+
+/// Two merge functions are equal if they share the same function.
+impl PartialEq for MergeFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::as_ptr(&self.0).cast::<()>() == Arc::as_ptr(&other.0).cast::<()>()
+    }
+}
+
+impl Eq for MergeFn {}
+
+/// The note appended to the diagnostic of the equality contract which is generated when merging
+/// two arrays. See [`EvalConfig::array_merge_note`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum ArrayMergeNote {
+    /// Append the default note, explaining where the equality contract comes from.
+    #[default]
+    Default,
+    /// Append a custom note instead of the default one.
+    Custom(String),
+    /// Don't append any note.
+    Disabled,
+}
+
+impl ArrayMergeNote {
+    const DEFAULT_NOTE: &'static str = "\
+        This equality contract was auto-generated from a merge operation on two arrays. \
+        Arrays can only be merged if they are equal.";
+
+    /// Return the text of the note to append, if any.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            ArrayMergeNote::Default => Some(Self::DEFAULT_NOTE),
+            ArrayMergeNote::Custom(note) => Some(note.as_str()),
+            ArrayMergeNote::Disabled => None,
+        }
+    }
+}
+
+/// How to merge two different values of the same priority which can't be combined, such as two
+/// different numbers, strings, booleans or enum tags. See [`EvalConfig::conflict_policy`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConflictPolicy {
+    /// The merge fails with an incompatible arguments error.
+    #[default]
+    Error,
+    /// The value of the left operand is kept.
+    LeftWins,
+    /// The value of the right operand is kept.
+    RightWins,
+}
+
+/// Statistics about the merges performed during an evaluation. They are only collected if
+/// [`EvalConfig::collect_merge_stats`] is set.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MergeStats {
+    /// The number of merges of two records.
+    pub record_merges: usize,
+    /// The total number of fields of the records being merged, once split.
+    pub fields_split: usize,
+    /// The number of fields defined on both sides of a record merge.
+    pub field_merges: usize,
+    /// The number of field values reverted to their original expression.
+    pub reverts: usize,
+    /// The number of field values saturated with the fields of the final record.
+    pub saturations: usize,
+}
+
+/// A documentation dropped when merging a field whose definitions have different priorities, as
+/// collected when [`EvalConfig::warn_overridden_docs`] is set. This is advisory only: the merge
+/// itself succeeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocOverrideWarning {
+    /// The path of the field, relative to the original merge.
+    pub path: MergePath,
+    /// The documentation which has been dropped.
+    pub doc: String,
+    /// The position of the definition whose documentation has been dropped.
+    pub pos: TermPos,
+    /// The side of the merge whose value has been overridden.
+    pub overridden: MergeSide,
+}
+
+/// A field being merged, as reported to a [MergeObserver].
+#[derive(Clone, Copy, Debug)]
+pub struct FieldMergeEvent<'a> {
+    /// The path of the field, relative to the original merge. The last element is the name of
+    /// the field.
+    pub path: &'a MergePath,
+    /// The definition sites of the field in the left operand.
+    pub left_sites: &'a [TermPos],
+    /// The definition sites of the field in the right operand.
+    pub right_sites: &'a [TermPos],
+    /// How the two definitions are combined.
+    pub kind: FieldMergeKind,
+}
+
+/// An observer notified each time a field defined on both sides of a merge is merged. Observers
+/// can't influence the result of merging, but they can be used to track where the values of the
+/// final configuration come from.
+pub trait MergeObserver {
+    fn field_merged(&mut self, event: FieldMergeEvent<'_>);
+}
+
+/// The state and the configuration of the evaluator which are relevant to merging.
+#[derive(Clone, Default)]
+pub struct MergeContext {
+    /// The remaining merge budget.
+    pub fuel: MergeFuel,
+    /// If the current evaluation is performed for export. In this case, the values of
+    /// `not_exported` fields are never forced by the final serialization, and merging them is done
+    /// in a cheaper, shallow way whenever it doesn't change the semantics.
+    pub for_export: bool,
+    /// If the result of the current merge is the final result of the evaluation, which can't be
+    /// merged again. In this case, the merged fields are allocated in standard cache elements
+    /// instead of revertible ones, which spares the bookkeeping of their dependencies. This is set
+    /// by the virtual machine for the merges whose result is directly forced by a full evaluation.
+    pub terminal: bool,
+    /// An optional observer notified of each field merge.
+    pub observer: Option<Rc<RefCell<dyn MergeObserver>>>,
+    /// The note appended to the diagnostic of the equality contract generated when merging two
+    /// arrays.
+    pub array_merge_note: ArrayMergeNote,
+    /// The merge statistics, if they are collected.
+    pub stats: Option<MergeStats>,
+    /// The time spent merging each top-level field, if it's collected. See
+    /// [EvalConfig::collect_field_timings].
+    pub field_timings: Option<IndexMap<Ident, Duration>>,
+    /// The token checked to cancel merging, if any.
+    pub cancellation: Option<CancellationToken>,
+    /// Whether the equality contract generated when merging two arrays shows the content of the
+    /// left array. See [EvalConfig::display_merged_arrays].
+    pub display_merged_arrays: bool,
+    /// The normalization of field names, if any. See [EvalConfig::key_normalization].
+    pub key_normalization: Option<KeyNormalization>,
+    /// Whether the enum contracts of merged fields are combined by union. See
+    /// [EvalConfig::union_enum_contracts].
+    pub union_enum_contracts: bool,
+    /// How to merge two different values which can't be combined. See
+    /// [EvalConfig::conflict_policy].
+    pub conflict_policy: ConflictPolicy,
+    /// Whether the fields of merged records are forced right away. See
+    /// [EvalConfig::strict_contracts].
+    pub strict_contracts: bool,
+    /// The documentations dropped when merging overridden fields, if they are collected. See
+    /// [EvalConfig::warn_overridden_docs].
+    pub doc_override_warnings: Option<Vec<DocOverrideWarning>>,
+    /// Whether conflicts between the values of a field which is optional on both sides are
+    /// tolerated. See [EvalConfig::lenient_optional_conflicts].
+    pub lenient_optional_conflicts: bool,
+    /// The merge functions defined by the host application. See [EvalConfig::merge_fns].
+    pub merge_fns: HashMap<String, MergeFn>,
+    /// Whether a required field declared on both sides without a value is reported right away.
+    /// See [EvalConfig::strict_required_fields].
+    pub strict_required_fields: bool,
+    /// Whether the fields which don't depend on other fields are re-closurized without being
+    /// reverted. See [EvalConfig::skip_flat_reverts].
+    pub skip_flat_reverts: bool,
+    /// Whether the contracts of the fields merged from both sides are checked for contradictions.
+    /// See [EvalConfig::check_contradictory_contracts].
+    pub check_contradictory_contracts: bool,
+    /// Whether the fields only defined in the right operand of a merge are flagged. See
+    /// [EvalConfig::track_introduced_fields].
+    pub track_introduced_fields: bool,
+    /// How record contracts handle the fields absent from the value. See
+    /// [EvalConfig::absent_fields].
+    pub absent_fields: AbsentFields,
+    /// The maximum number of fields of a merged record, if any. See [EvalConfig::max_fields].
+    pub max_fields: Option<usize>,
+    /// Whether record contracts match the fields of the value case-insensitively. See
+    /// [EvalConfig::case_insensitive_contracts].
+    pub case_insensitive_contracts: bool,
+}
+
+impl MergeContext {
+    /// Create a fresh merge context for a new evaluation with the given configuration.
+    pub fn new(config: &EvalConfig) -> Self {
+        MergeContext {
+            fuel: MergeFuel::new(config.merge_fuel),
+            for_export: false,
+            terminal: false,
+            observer: None,
+            array_merge_note: config.array_merge_note.clone(),
+            stats: config.collect_merge_stats.then(MergeStats::default),
+            field_timings: config.collect_field_timings.then(IndexMap::new),
+            cancellation: config.cancellation.clone(),
+            display_merged_arrays: config.display_merged_arrays,
+            key_normalization: config.key_normalization.clone(),
+            union_enum_contracts: config.union_enum_contracts,
+            conflict_policy: config.conflict_policy,
+            strict_contracts: config.strict_contracts,
+            doc_override_warnings: config.warn_overridden_docs.then(Vec::new),
+            lenient_optional_conflicts: config.lenient_optional_conflicts,
+            merge_fns: config.merge_fns.clone(),
+            strict_required_fields: config.strict_required_fields,
+            skip_flat_reverts: config.skip_flat_reverts,
+            check_contradictory_contracts: config.check_contradictory_contracts,
+            track_introduced_fields: config.track_introduced_fields,
+            absent_fields: config.absent_fields,
+            max_fields: config.max_fields,
+            case_insensitive_contracts: config.case_insensitive_contracts,
+        }
+    }
+
+    /// Return the policy deciding a conflict between two values merged in the given mode. The
+    /// `left` and `right` merge strategies select the value to keep. Otherwise, the right value
+    /// wins if the field is optional on both sides and such conflicts are tolerated.
+    fn conflict_policy_for(&self, mode: &MergeMode) -> ConflictPolicy {
+        match mode.strategy() {
+            MergeStrategy::Left => return ConflictPolicy::LeftWins,
+            MergeStrategy::Right => return ConflictPolicy::RightWins,
+            _ => (),
+        }
+
+        match mode {
+            MergeMode::Standard(merge_label)
+                if self.lenient_optional_conflicts
+                    && merge_label.optional
+                    && self.conflict_policy == ConflictPolicy::Error =>
+            {
+                ConflictPolicy::RightWins
+            }
+            _ => self.conflict_policy,
+        }
+    }
+
+    /// Reset the state of the context for a new evaluation with the given configuration. The
+    /// observer, if any, is kept.
+    pub fn reset(&mut self, config: &EvalConfig) {
+        *self = MergeContext {
+            observer: self.observer.take(),
+            ..MergeContext::new(config)
+        };
+    }
+
+    /// Start timing the merge of a field, if the timings are collected. See
+    /// [EvalConfig::collect_field_timings].
+    fn start_timing(&self) -> Option<Instant> {
+        self.field_timings.as_ref().map(|_| Instant::now())
+    }
+
+    /// Add the time elapsed since `start` to the time spent merging the top-level field `field`.
+    fn record_timing(&mut self, field: Ident, start: Option<Instant>) {
+        if let (Some(timings), Some(start)) = (&mut self.field_timings, start) {
+            *timings.entry(field).or_default() += start.elapsed();
+        }
+    }
+
+    /// Fail if the evaluation has been cancelled. See [CancellationToken].
+    fn check_cancelled(&self) -> Result<(), MergeError> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(MergeError::Cancelled),
+            _ => Ok(()),
         }
     }
 }
@@ -58,6 +561,18 @@ impl From<MergeMode> for MergeLabel {
 /// Compute the merge of two evaluated operands. Support both standard merging and record contract
 /// application.
 ///
+/// # Operands
+///
+/// Both operands must be in weak head normal form. The merge operators are strict, so the virtual
+/// machine always forces their arguments before calling `merge`: an operand which is produced
+/// lazily, for example by a function application, is thus evaluated to a record before reaching
+/// the record case, and independently of the evaluation order of the rest of the program. An
+/// operand which doesn't evaluate to a record is reported as an incompatible argument.
+///
+/// A record wrapped in annotations, which can be produced by callers of this function other than
+/// the virtual machine, is merged as the underlying record. The annotations are kept on the result,
+/// such that their contracts are applied to the merged record.
+///
 /// # Mode
 ///
 /// In [`MergeMode::Contract`] mode, `t1` must be the value and `t2` must be the contract. It is
@@ -71,8 +586,237 @@ pub fn merge<C: Cache>(
     env2: Environment,
     pos_op: TermPos,
     mode: MergeMode,
+    ctx: &mut MergeContext,
+    call_stack: &mut CallStack,
+) -> Result<Closure, MergeError> {
+    #[cfg(all(debug_assertions, feature = "check-merge-commutativity"))]
+    let swapped = commutativity::swapped_merge(
+        cache, &t1, &env1, &t2, &env2, pos_op, &mode, ctx, call_stack,
+    );
+
+    // Applying a record contract isn't a merge of data: the fields of the value are checked when
+    // the value itself is merged or used.
+    let strict = ctx.strict_contracts && !matches!(mode, MergeMode::Contract(..));
+
+    let result = merge_impl(
+        cache, t1, env1, t2, env2, pos_op, mode, ctx, call_stack, None,
+    );
+
+    #[cfg(all(debug_assertions, feature = "check-merge-commutativity"))]
+    if let Some(swapped) = swapped {
+        commutativity::assert_same_shape(&result, &swapped);
+    }
+
+    if strict {
+        result.map(|closure| force_fields(cache, closure, pos_op))
+    } else {
+        result
+    }
+}
+
+/// Force the value of each field of a merged record, with its pending contracts applied, before
+/// returning the record itself. The fields are accessed through the record, such that they are
+/// evaluated in its recursive environment. Fields which are themselves merged records are in turn
+/// forced when they are merged. See [EvalConfig::strict_contracts].
+fn force_fields<C: Cache>(cache: &mut C, merged: Closure, pos_op: TermPos) -> Closure {
+    use crate::mk_app;
+
+    let fields: Vec<Ident> = match merged.body.as_ref() {
+        Term::Record(record) | Term::RecRecord(record, ..) => record
+            .fields
+            .iter()
+            .filter(|(_, field)| field.value.is_some())
+            .map(|(id, _)| *id)
+            .collect(),
+        _ => return merged,
+    };
+
+    if fields.is_empty() {
+        return merged;
+    }
+
+    let mut env = Environment::new();
+    let record = merged.body.closurize(cache, &mut env, merged.env);
+    let pos_op_inh = pos_op.into_inherited();
+
+    // `%seq% r.a (%seq% r.b r)`: the fields are forced in the order of the record.
+    let body = fields.into_iter().rev().fold(record.clone(), |acc, id| {
+        let field = mk_term::op1(UnaryOp::StaticAccess(id), record.clone());
+        mk_app!(mk_term::op1(UnaryOp::Seq(), field), acc).with_pos(pos_op_inh)
+    });
+
+    Closure { body, env }
+}
+
+/// Same as [merge], but doesn't abort when combining a field of two records fails. Instead, the
+/// error is recorded and the field is replaced with a placeholder which raises this error if it's
+/// ever evaluated, such that the other fields are still available. Return the best-effort result
+/// together with the errors recorded for each field.
+///
+/// Note that merging is lazy: conflicts inside the values of the fields are only raised when
+/// these values are evaluated.
+///
+/// This is meant for tooling, such as the LSP, which needs to extract as much information as
+/// possible from partially invalid programs. It must never be used to produce the actual output
+/// of a configuration.
+#[allow(clippy::too_many_arguments)]
+pub fn merge_lenient<C: Cache>(
+    cache: &mut C,
+    t1: RichTerm,
+    env1: Environment,
+    t2: RichTerm,
+    env2: Environment,
+    pos_op: TermPos,
+    mode: MergeMode,
+    ctx: &mut MergeContext,
+    call_stack: &mut CallStack,
+) -> Result<(Closure, Vec<EvalError>), MergeError> {
+    let mut errors = Vec::new();
+    let closure = merge_impl(
+        cache,
+        t1,
+        env1,
+        t2,
+        env2,
+        pos_op,
+        mode,
+        ctx,
+        call_stack,
+        Some(&mut errors),
+    )?;
+
+    Ok((closure, errors))
+}
+
+/// Return `true` if a term is a record, possibly wrapped in annotations.
+fn is_annotated_record(t: &Term) -> bool {
+    match t {
+        Term::Record(..) => true,
+        Term::Annotated(_, inner) => is_annotated_record(inner.as_ref()),
+        _ => false,
+    }
+}
+
+/// Wrap the result of a merge in an annotation living in `annot_env`, the environment of the
+/// annotated operand.
+fn annotate<C: Cache>(
+    cache: &mut C,
+    merged: Closure,
+    annot: TypeAnnotation,
+    annot_env: Environment,
+    pos_op: TermPos,
+) -> Closure {
+    let mut env = annot_env;
+    let inner = merged.body.closurize(cache, &mut env, merged.env);
+
+    Closure {
+        body: RichTerm::new(Term::Annotated(annot, inner), pos_op.into_inherited()),
+        env,
+    }
+}
+
+/// Resolve a conflict between two different values which can't be combined, such as two different
+/// numbers, according to the conflict policy. The values must be constants, which don't need an
+/// environment.
+fn resolve_conflict(
+    policy: ConflictPolicy,
+    mode: MergeMode,
+    left: RichTerm,
+    right: RichTerm,
+    pos_op: TermPos,
+) -> Result<Closure, MergeError> {
+    let winner = match policy {
+        ConflictPolicy::Error => {
+            return Err(MergeError::IncompatibleArgs {
+                left_arg: left,
+                right_arg: right,
+                merge_label: mode.into(),
+            })
+        }
+        ConflictPolicy::LeftWins => left,
+        ConflictPolicy::RightWins => right,
+    };
+
+    Ok(Closure::atomic_closure(
+        winner.with_pos(pos_op.into_inherited()),
+    ))
+}
+
+/// Put together two environments, for example the environments of two closures built separately
+/// which are then merged. The resulting environment binds all the identifiers of `env1` and
+/// `env2`.
+///
+/// # Collisions
+///
+/// The environments are expected to bind distinct identifiers, which is the case for the fresh
+/// variables introduced by closurization, or to bind a common identifier to the same element, as
+/// happens when they share an ancestor environment. An identifier bound to different elements
+/// in `env1` and `env2` is a bug: this is checked in debug mode, while the binding of `env2`
+/// silently takes precedence in release mode.
+pub fn merge_environments(env1: Environment, env2: &Environment) -> Environment {
+    let mut env = env1;
+
+    // `iter` only yields the most recent binding of each identifier of `env2`, so the lookup
+    // below can only find a binding coming from `env1`.
+    for (id, idx) in env2.iter() {
+        debug_assert!(
+            env.get(id).map_or(true, |prev| prev == idx),
+            "merge_environments: `{id}` is bound to different elements"
+        );
+
+        env.insert(*id, idx.clone());
+    }
+
+    env
+}
+
+/// Implementation of [merge] and [merge_lenient]. If `field_errors` is `Some`, errors raised when
+/// combining the fields of two records are recorded there instead of aborting the merge, and the
+/// placeholders left by the error recovery of the parser behave as wildcards: they are ignored in
+/// favor of the other operand.
+#[allow(clippy::too_many_arguments)]
+fn merge_impl<C: Cache>(
+    cache: &mut C,
+    t1: RichTerm,
+    env1: Environment,
+    t2: RichTerm,
+    env2: Environment,
+    pos_op: TermPos,
+    mode: MergeMode,
+    ctx: &mut MergeContext,
     call_stack: &mut CallStack,
-) -> Result<Closure, EvalError> {
+    mut field_errors: Option<&mut Vec<EvalError>>,
+) -> Result<Closure, MergeError> {
+    // A merge function registered by the host application takes care of the whole merge of the
+    // two values.
+    if let MergeStrategy::Custom { name } = mode.strategy() {
+        let Some(merge_fn) = ctx.merge_fns.get(&name) else {
+            return Err(MergeError::UnknownMergeFn {
+                name,
+                merge_label: MergeLabel::from(mode),
+            });
+        };
+
+        let merged = merge_fn
+            .call(&t1, &t2)
+            .map_err(|message| MergeError::MergeFnFailed {
+                name,
+                message,
+                merge_label: MergeLabel::from(mode),
+            })?;
+
+        let pos = if merged.pos.is_def() {
+            merged.pos
+        } else {
+            pos_op.into_inherited()
+        };
+
+        return Ok(Closure {
+            body: merged.with_pos(pos),
+            env: merge_environments(env1, &env2),
+        });
+    }
+
     let RichTerm {
         term: t1,
         pos: pos1,
@@ -83,6 +827,67 @@ pub fn merge<C: Cache>(
     } = t2;
 
     match (t1.into_owned(), t2.into_owned()) {
+        // When merging leniently for tooling, a partially written program shouldn't prevent the
+        // analysis of the rest of the merge.
+        (Term::ParseError(_), t2_) if field_errors.is_some() => Ok(Closure {
+            body: RichTerm::new(t2_, pos2),
+            env: env2,
+        }),
+        (t1_, Term::ParseError(_)) if field_errors.is_some() => Ok(Closure {
+            body: RichTerm::new(t1_, pos1),
+            env: env1,
+        }),
+        // An annotated record is merged as a bare record, and the annotation is put back on the
+        // result such that its contracts are applied to the merged record. The evaluator strips
+        // annotations before merging, but callers of the merge API may not.
+        (Term::Annotated(annot, inner), t2_)
+            if is_annotated_record(inner.as_ref()) && is_annotated_record(&t2_) =>
+        {
+            let merged = merge_impl(
+                cache,
+                inner,
+                env1.clone(),
+                RichTerm::new(t2_, pos2),
+                env2,
+                pos_op,
+                mode,
+                ctx,
+                call_stack,
+                field_errors,
+            )?;
+
+            Ok(annotate(cache, merged, annot, env1, pos_op))
+        }
+        (t1_, Term::Annotated(annot, inner))
+            if is_annotated_record(&t1_) && is_annotated_record(inner.as_ref()) =>
+        {
+            let merged = merge_impl(
+                cache,
+                RichTerm::new(t1_, pos1),
+                env1,
+                inner,
+                env2.clone(),
+                pos_op,
+                mode,
+                ctx,
+                call_stack,
+                field_errors,
+            )?;
+
+            Ok(annotate(cache, merged, annot, env2, pos_op))
+        }
+        // When merging left-biased, the values defined on both sides which aren't both records
+        // are leaves: the left one wins without conflicting. The top-level operands, whose path
+        // is empty, go through the usual checks instead.
+        (t1_, t2_)
+            if matches!(&mode, MergeMode::LeftBiased(label) if !label.path.is_empty())
+                && !matches!((&t1_, &t2_), (Term::Record(_), Term::Record(_))) =>
+        {
+            Ok(Closure {
+                body: RichTerm::new(t1_, pos1),
+                env: env1,
+            })
+        }
         // Merge is idempotent on basic terms
         (Term::Null, Term::Null) => Ok(Closure::atomic_closure(RichTerm::new(
             Term::Null,
@@ -95,13 +900,34 @@ pub fn merge<C: Cache>(
                     pos_op.into_inherited(),
                 )))
             } else {
-                Err(EvalError::MergeIncompatibleArgs {
-                    left_arg: RichTerm::new(Term::Bool(b1), pos1),
-                    right_arg: RichTerm::new(Term::Bool(b2), pos2),
-                    merge_label: mode.into(),
-                })
+                resolve_conflict(
+                    ctx.conflict_policy_for(&mode),
+                    mode,
+                    RichTerm::new(Term::Bool(b1), pos1),
+                    RichTerm::new(Term::Bool(b2), pos2),
+                    pos_op,
+                )
             }
         }
+        // Numeric combination, when selected by a `merge sum`, `merge max` or `merge min`
+        // annotation. Numbers are arbitrary precision rationals, so summing them is exact.
+        (Term::Num(n1), Term::Num(n2))
+            if matches!(
+                mode.strategy(),
+                MergeStrategy::Sum | MergeStrategy::Max | MergeStrategy::Min
+            ) =>
+        {
+            let result = match mode.strategy() {
+                MergeStrategy::Sum => n1 + n2,
+                MergeStrategy::Max => std::cmp::max(n1, n2),
+                _ => std::cmp::min(n1, n2),
+            };
+
+            Ok(Closure::atomic_closure(RichTerm::new(
+                Term::Num(result),
+                pos_op.into_inherited(),
+            )))
+        }
         (Term::Num(n1), Term::Num(n2)) => {
             if n1 == n2 {
                 Ok(Closure::atomic_closure(RichTerm::new(
@@ -109,12 +935,32 @@ pub fn merge<C: Cache>(
                     pos_op.into_inherited(),
                 )))
             } else {
-                Err(EvalError::MergeIncompatibleArgs {
-                    left_arg: RichTerm::new(Term::Num(n1), pos1),
-                    right_arg: RichTerm::new(Term::Num(n2), pos2),
-                    merge_label: mode.into(),
-                })
+                resolve_conflict(
+                    ctx.conflict_policy_for(&mode),
+                    mode,
+                    RichTerm::new(Term::Num(n1), pos1),
+                    RichTerm::new(Term::Num(n2), pos2),
+                    pos_op,
+                )
+            }
+        }
+        // Concatenation of strings, when selected by a `merge concat` annotation. Both operands
+        // have already been evaluated, including their interpolated chunks.
+        (Term::Str(mut s1), Term::Str(s2))
+            if matches!(mode.strategy(), MergeStrategy::Concat { .. }) =>
+        {
+            if let MergeStrategy::Concat {
+                separator: Some(separator),
+            } = mode.strategy()
+            {
+                s1.push_str(&separator);
             }
+            s1.push_str(&s2);
+
+            Ok(Closure::atomic_closure(RichTerm::new(
+                Term::Str(s1),
+                pos_op.into_inherited(),
+            )))
         }
         (Term::Str(s1), Term::Str(s2)) => {
             if s1 == s2 {
@@ -123,11 +969,13 @@ pub fn merge<C: Cache>(
                     pos_op.into_inherited(),
                 )))
             } else {
-                Err(EvalError::MergeIncompatibleArgs {
-                    left_arg: RichTerm::new(Term::Str(s1), pos1),
-                    right_arg: RichTerm::new(Term::Str(s2), pos2),
-                    merge_label: mode.into(),
-                })
+                resolve_conflict(
+                    ctx.conflict_policy_for(&mode),
+                    mode,
+                    RichTerm::new(Term::Str(s1), pos1),
+                    RichTerm::new(Term::Str(s2), pos2),
+                    pos_op,
+                )
             }
         }
         (Term::Lbl(l1), Term::Lbl(l2)) => {
@@ -137,13 +985,16 @@ pub fn merge<C: Cache>(
                     pos_op.into_inherited(),
                 )))
             } else {
-                Err(EvalError::MergeIncompatibleArgs {
+                Err(MergeError::IncompatibleArgs {
                     left_arg: RichTerm::new(Term::Lbl(l1), pos1),
                     right_arg: RichTerm::new(Term::Lbl(l2), pos2),
                     merge_label: mode.into(),
                 })
             }
         }
+        // Enum tags don't carry payloads (yet): merging two tags is idempotent, as for other
+        // basic values. If variants with payloads are introduced, equal tags should recursively
+        // merge their payloads.
         (Term::Enum(i1), Term::Enum(i2)) => {
             if i1 == i2 {
                 Ok(Closure::atomic_closure(RichTerm::new(
@@ -151,12 +1002,117 @@ pub fn merge<C: Cache>(
                     pos_op.into_inherited(),
                 )))
             } else {
-                Err(EvalError::MergeIncompatibleArgs {
-                    left_arg: RichTerm::new(Term::Enum(i1), pos1),
-                    right_arg: RichTerm::new(Term::Enum(i2), pos2),
-                    merge_label: mode.into(),
-                })
+                resolve_conflict(
+                    ctx.conflict_policy_for(&mode),
+                    mode,
+                    RichTerm::new(Term::Enum(i1), pos1),
+                    RichTerm::new(Term::Enum(i2), pos2),
+                    pos_op,
+                )
+            }
+        }
+        // Element-wise merging of arrays, when selected by a `merge element-wise` annotation: the
+        // elements of both arrays are merged pairwise, such that e.g. the priorities and the
+        // contracts of records inside arrays are taken into account.
+        (Term::Array(arr1, attrs1), Term::Array(arr2, attrs2))
+            if mode.strategy() == MergeStrategy::ElementWise =>
+        {
+            if arr1.len() != arr2.len() {
+                return Err(MergeError::ArrayLengthMismatch {
+                    left_len: arr1.len(),
+                    right_len: arr2.len(),
+                    pos_op,
+                });
             }
+
+            // The strategy only applies to the annotated field: the elements themselves are
+            // merged using the standard strategy.
+            let elem_label = MergeLabel {
+                strategy: MergeStrategy::Standard,
+                ..MergeLabel::from(mode)
+            };
+            ctx.fuel.consume(arr1.len(), &elem_label)?;
+            let mut env = Environment::new();
+
+            let merged: Array = arr1
+                .into_iter()
+                .zip(arr2)
+                .map(|(elem1, elem2)| {
+                    let elem1 = RuntimeContract::apply_all(
+                        elem1,
+                        attrs1.pending_contracts.iter().cloned(),
+                        pos1,
+                    );
+                    let elem2 = RuntimeContract::apply_all(
+                        elem2,
+                        attrs2.pending_contracts.iter().cloned(),
+                        pos2,
+                    );
+
+                    let mut local_env = Environment::new();
+                    let elem1 = elem1.closurize(cache, &mut local_env, env1.clone());
+                    let elem2 = elem2.closurize(cache, &mut local_env, env2.clone());
+
+                    mk_term::op2(BinaryOp::Merge(elem_label.clone()), elem1, elem2)
+                        .closurize(cache, &mut env, local_env)
+                })
+                .collect();
+
+            Ok(Closure {
+                body: RichTerm::new(
+                    Term::Array(merged, ArrayAttrs::new().closurized()),
+                    pos_op.into_inherited(),
+                ),
+                env,
+            })
+        }
+        // Set merging of arrays, when selected by a `merge set` annotation: the arrays are
+        // concatenated and duplicates are removed, the first occurrence of an element winning.
+        (t1 @ Term::Array(..), t2 @ Term::Array(..)) if mode.strategy() == MergeStrategy::Set => {
+            use crate::{mk_app, stdlib};
+
+            let mut env = Environment::new();
+            let t1 = RichTerm::new(t1, pos1).closurize(cache, &mut env, env1);
+            let t2 = RichTerm::new(t2, pos2).closurize(cache, &mut env, env2);
+
+            // As for `contract.Equal` below, we use an internal function which can't be shadowed.
+            let result = mk_app!(stdlib::internals::array_set_merge(), t1, t2).with_pos(pos_op);
+
+            Ok(Closure { body: result, env })
+        }
+        // Broadcasting of a record into an array of records, when selected by a `merge broadcast`
+        // annotation.
+        (Term::Record(r), Term::Array(arr, attrs))
+            if mode.strategy() == MergeStrategy::Broadcast =>
+        {
+            merge_broadcast(
+                cache,
+                RichTerm::new(Term::Record(r), pos1),
+                env1,
+                arr,
+                attrs,
+                env2,
+                pos2,
+                pos_op,
+                mode.into(),
+                ctx,
+            )
+        }
+        (Term::Array(arr, attrs), Term::Record(r))
+            if mode.strategy() == MergeStrategy::Broadcast =>
+        {
+            merge_broadcast(
+                cache,
+                RichTerm::new(Term::Record(r), pos2),
+                env2,
+                arr,
+                attrs,
+                env1,
+                pos1,
+                pos_op,
+                mode.into(),
+                ctx,
+            )
         }
         // There are several different (and valid) ways of merging arrays. We don't want to choose
         // for the user, so future custom merge functions will provide a way to overload the native
@@ -165,12 +1121,22 @@ pub fn merge<C: Cache>(
         // minimum way such that it is idempotent.
         (t1 @ Term::Array(..), t2 @ Term::Array(..)) => {
             use crate::{mk_app, stdlib, types::TypeF};
-            use std::rc::Rc;
 
+            let strategy = mode.strategy();
             let mut env = Environment::new();
             let t1 = RichTerm::new(t1, pos1).closurize(cache, &mut env, env1);
             let t2 = RichTerm::new(t2, pos2).closurize(cache, &mut env, env2);
 
+            // We would need to substitute variables inside `t1` to make it useful to print, but by
+            // default we don't want to do it preventively at each array merging, so we just print
+            // `contract.Equal some_array`. Substituting them is opt-in, as it might have a
+            // noticeable impact on performance.
+            let array_for_display = if ctx.display_merged_arrays {
+                display_array(cache, &t1, &env)
+            } else {
+                mk_term::var("some_array")
+            };
+
             // We reconstruct the contract we apply later on just to fill the label. This will be
             // printed out when reporting the error.
             let contract_for_display = mk_app!(
@@ -178,32 +1144,31 @@ pub fn merge<C: Cache>(
                     UnaryOp::StaticAccess("Equal".into()),
                     Term::Var("contract".into()),
                 ),
-                // We would need to substitute variables inside `t1` to make it useful to print,
-                // but currently we don't want to do it preventively at each array merging, so we
-                // just print `contract.Equal some_array`.
-                //
-                // If the error reporting proves to be insufficient, consider substituting the
-                // variables inside `t1`, but be aware that it might (or might not) have a
-                // noticeable impact on performance.
-                mk_term::var("some_array")
+                array_for_display
             );
 
-            let label = Label {
+            let mut label = Label {
                 types: Rc::new(TypeF::Flat(contract_for_display).into()),
                 span: MergeLabel::from(mode).span,
                 ..Default::default()
             }
-            .with_diagnostic_message("cannot merge unequal arrays")
-            .append_diagnostic_note(
-                "\
-                This equality contract was auto-generated from a merge operation on two arrays. \
-                Arrays can only be merged if they are equal.",
-            );
+            .with_diagnostic_message("cannot merge unequal arrays");
+
+            if let Some(note) = ctx.array_merge_note.text() {
+                label = label.append_diagnostic_note(note);
+            }
 
             // We don't actually use `contract.Equal` directly, because contract could have been
-            // locally redefined. We rather use the internal `$stdlib_contract_equal`, which is
-            // exactly the same, but can't be shadowed.
-            let eq_contract = mk_app!(stdlib::internals::stdlib_contract_equal(), t1);
+            // locally redefined. We rather use the internal `$array_merge_equal`, which behaves
+            // the same, but can't be shadowed and reports where the two arrays differ.
+            // The `compare` strategy replaces structural equality with the given comparator, which
+            // doesn't depend on the environment.
+            let eq_contract = match strategy {
+                MergeStrategy::Compare { comparator } => {
+                    mk_app!(stdlib::internals::array_merge_equal_with(), comparator, t1)
+                }
+                _ => mk_app!(stdlib::internals::array_merge_equal(), t1),
+            };
             let result = mk_app!(
                 mk_term::op2(BinaryOp::Assume(), eq_contract, Term::Lbl(label)),
                 t2
@@ -214,43 +1179,269 @@ pub fn merge<C: Cache>(
         }
         // Merge put together the fields of records, and recursively merge
         // fields that are present in both terms
+        (Term::Record(r1), Term::Record(r2))
+            if mode.strategy() == MergeStrategy::Unsealed
+                && (r1.sealed_tail.is_some() || r2.sealed_tail.is_some()) =>
+        {
+            Ok(merge_unsealed(
+                cache,
+                MergeLabel::from(mode),
+                r1,
+                env1,
+                r2,
+                env2,
+                pos_op,
+            ))
+        }
         (Term::Record(r1), Term::Record(r2)) => {
             // While it wouldn't be impossible to merge records with sealed tails,
             // working out how to do so in a "sane" way that preserves parametricity
             // is non-trivial. It's also not entirely clear that this is something
             // users will generally have reason to do, so in the meantime we've
             // decided to just prevent this entirely
-            if let Some(record::SealedTail { label, .. }) = r1.sealed_tail.or(r2.sealed_tail) {
-                return Err(EvalError::IllegalPolymorphicTailAccess {
-                    action: IllegalPolymorphicTailAction::Merge,
+            let sealed_tail = match (r1.sealed_tail, r2.sealed_tail) {
+                (Some(tail), _) => Some((tail, MergeSide::Left)),
+                (None, Some(tail)) => Some((tail, MergeSide::Right)),
+                (None, None) => None,
+            };
+
+            if let Some((record::SealedTail { label, .. }, side)) = sealed_tail {
+                return Err(MergeError::IllegalPolymorphicTailAccess {
+                    side,
+                    merge_label: MergeLabel::from(mode),
                     evaluated_arg: label.get_evaluated_arg(cache),
                     label,
                     call_stack: std::mem::take(call_stack),
                 });
             }
 
+            // Merging with an empty record doesn't override anything, so we can return the other
+            // operand as it is instead of reverting each of its fields. This doesn't hold when
+            // applying a contract, which may reject fields, nor for the `intersect` strategy, which
+            // drops them. Key normalization must check the fields for collisions, so it takes the
+            // general path as well, and so does merging with a frozen record, which can't be
+            // extended.
+            if matches!(mode, MergeMode::Standard(_))
+                && mode.strategy() != MergeStrategy::Intersect
+                && ctx.key_normalization.is_none()
+                && !(r1.attrs.frozen || r2.attrs.frozen)
+                && (r1.fields.is_empty() || r2.fields.is_empty())
+            {
+                let merge_label = MergeLabel::from(mode);
+                let attrs = RecordAttrs::merge_counted(r1.attrs, r2.attrs);
+                // The fields of the right operand are all introduced by this merge, while the ones
+                // of the left operand aren't new anymore.
+                let (mut record, env, introduced) = if r1.fields.is_empty() {
+                    (r2, env2, true)
+                } else {
+                    (r1, env1, false)
+                };
+                let fields_count = record.fields.len();
+
+                // The merge is accounted for as in the general case below. No field is merged nor
+                // reverted, so there's nothing to report to the observer or to time.
+                ctx.check_cancelled()?;
+                ctx.fuel.consume(fields_count, &merge_label)?;
+
+                match ctx.max_fields {
+                    Some(max_fields) if fields_count > max_fields => {
+                        return Err(MergeError::RecordTooLarge {
+                            fields: fields_count,
+                            max_fields,
+                            merge_label,
+                        });
+                    }
+                    _ => (),
+                }
+
+                if let Some(stats) = &mut ctx.stats {
+                    stats.record_merges += 1;
+                    stats.fields_split += fields_count;
+                }
+
+                if ctx.track_introduced_fields {
+                    for field in record.fields.values_mut() {
+                        field.metadata.introduced = introduced;
+                    }
+                }
+
+                return Ok(Closure {
+                    body: RichTerm::new(
+                        Term::Record(RecordData { attrs, ..record }),
+                        pos_op.into_inherited(),
+                    ),
+                    env,
+                });
+            }
+
+            // When record contracts are matched case-insensitively, the fields of the checked
+            // value take the casing of the contract fields they match before being split.
+            let fields1 =
+                if ctx.case_insensitive_contracts && matches!(mode, MergeMode::Contract(..)) {
+                    match_contract_casing(r1.fields, &r2.fields).map_err(|(first, second)| {
+                        MergeError::KeyCollision {
+                            first,
+                            second,
+                            merge_label: MergeLabel::from(mode.clone()),
+                        }
+                    })?
+                } else {
+                    r1.fields
+                };
+
+            // The fields of the merged record are ordered as the fields of `r1`, followed by the
+            // fields only defined in `r2` (see the Ordering section of the module documentation).
+            let mut field_names: Vec<_> = fields1.keys().cloned().collect();
+
+            // Splitting the fields only keeps the identifiers of `r1`. We keep the ones of `r2` as
+            // well to report where both sides declare a required field without a value.
+            let right_ids: Option<HashSet<Ident>> = ctx
+                .strict_required_fields
+                .then(|| r2.fields.keys().cloned().collect());
+
             let split::SplitResult {
                 left,
                 center,
                 right,
-            } = split::split(r1.fields, r2.fields);
+            } = match &ctx.key_normalization {
+                Some(normalization) => split::split_normalized(fields1, r2.fields, |id| {
+                    normalization.normalize(id.label())
+                })
+                .map_err(|(first, second)| MergeError::KeyCollision {
+                    first,
+                    second,
+                    merge_label: MergeLabel::from(mode.clone()),
+                })?,
+                None => split::split(fields1, r2.fields),
+            };
+
+            // With the `disjoint` strategy, overlapping fields are an error instead of being
+            // merged.
+            if mode.strategy() == MergeStrategy::Disjoint && !center.is_empty() {
+                return Err(MergeError::OverlappingFields {
+                    fields: center.keys().cloned().collect(),
+                    merge_label: MergeLabel::from(mode),
+                });
+            }
+
+            // A frozen record can't be extended with the fields only defined on the other side.
+            // Empty optional fields don't add anything to the record, so they don't count. Record
+            // contracts aren't concerned: they are checked for extra fields below instead.
+            if !matches!(mode, MergeMode::Contract(..)) && (r1.attrs.frozen || r2.attrs.frozen) {
+                let added = |fields: &IndexMap<Ident, Field>| -> Vec<Ident> {
+                    fields
+                        .iter()
+                        .filter(|(_, field)| !field.is_empty_optional())
+                        .map(|(id, _)| *id)
+                        .collect()
+                };
+                let (added_to_left, added_to_right) = (added(&right), added(&left));
+
+                if let Err(side) = RecordAttrs::check_extension(
+                    r1.attrs,
+                    r2.attrs,
+                    !added_to_left.is_empty(),
+                    !added_to_right.is_empty(),
+                ) {
+                    return Err(MergeError::CannotExtendFrozenRecord {
+                        side,
+                        fields: match side {
+                            MergeSide::Left => added_to_left,
+                            MergeSide::Right => added_to_right,
+                        },
+                        merge_label: MergeLabel::from(mode),
+                    });
+                }
+            }
+
+            // Fields without a value don't populate the record, so they don't count as extra
+            // fields when checking a closed record contract. Such fields are typically the
+            // requirements of another contract already applied to the value, which makes applying
+            // two contracts one after the other behave as applying their merge (see
+            // [RecordAttrs::merge]). Neither do `not_exported` fields count, which are internal
+            // helpers that aren't part of the public interface of the record.
+            let extra_fields: Vec<_> = left
+                .iter()
+                .filter(|(_, field)| field.value.is_some() && !field.metadata.not_exported)
+                .map(|(id, _)| id)
+                .collect();
+
+            // The fields declared by a record contract which the value must define, but doesn't.
+            let missing = match &mode {
+                MergeMode::Contract(_, absent_fields) => missing_fields(&right, *absent_fields),
+                _ => Vec::new(),
+            };
 
             match mode {
-                MergeMode::Contract(label) if !r2.attrs.open && !left.is_empty() => {
-                    let fields: Vec<String> =
-                        left.keys().map(|field| format!("`{field}`")).collect();
+                MergeMode::Contract(label, _) if !r2.attrs.open && !extra_fields.is_empty() => {
+                    let fields: Vec<_> = extra_fields.iter().map(|id| id.label()).collect();
                     let plural = if fields.len() == 1 { "" } else { "s" };
-                    let fields_list = fields.join(",");
+                    // A value can have a lot of extra fields, for example when a large record is
+                    // checked against the wrong contract. We only list a few of them.
+                    let fields_list = truncated_list(&fields, MAX_MESSAGE_FIELDS);
+
+                    // Extra fields are often misspelled required fields, so we report the
+                    // required fields that are missing from the value at the same time.
+                    let message = if missing.is_empty() {
+                        format!("extra field{plural} {fields_list}")
+                    } else {
+                        let missing_plural = if missing.len() == 1 { "" } else { "s" };
+                        format!(
+                            "extra field{plural} {fields_list}; missing field{missing_plural} {}",
+                            truncated_list(&missing, MAX_MESSAGE_FIELDS)
+                        )
+                    };
+
+                    let mut notes = vec![String::from("Have you misspelled a field?")];
+
+                    let mut accepted: Vec<_> = center
+                        .keys()
+                        .chain(right.keys())
+                        .map(Ident::label)
+                        .collect();
+
+                    if !accepted.is_empty() {
+                        accepted.sort_unstable();
+                        notes.push(format!(
+                            "The record contract accepts the following fields: {}.",
+                            truncated_list(&accepted, MAX_LISTED_FIELDS)
+                        ));
+                    }
+
+                    notes.push(String::from("The record contract might also be too strict. By default, record contracts exclude any field which is not listed.
+Append `, ..` at the end of the record contract, as in `{some_field | SomeContract, ..}`, to make it accept extra fields."));
+
+                    let contract_id = ContractId::of(&label);
+                    let label = label
+                        .with_contract_id(contract_id)
+                        .with_diagnostic_message(message)
+                        .with_diagnostic_notes(notes);
+
+                    return Err(MergeError::BlameError {
+                        evaluated_arg: label.get_evaluated_arg(cache),
+                        label,
+                        call_stack: CallStack::new(),
+                    });
+                }
+                MergeMode::Contract(label, AbsentFields::Strict) if !missing.is_empty() => {
+                    let plural = if missing.len() == 1 { "" } else { "s" };
+                    let message = format!(
+                        "missing field{plural} {}",
+                        truncated_list(&missing, MAX_MESSAGE_FIELDS)
+                    );
+                    let note = String::from(
+                        "The record contract is applied in strict mode, where the value must \
+                        define every field of the contract which doesn't have a default value, \
+                        including optional fields.",
+                    );
 
+                    let contract_id = ContractId::of(&label);
                     let label = label
-                        .with_diagnostic_message(format!("extra field{plural} {fields_list}"))
-                        .with_diagnostic_notes(vec![
-                            String::from("Have you misspelled a field?"),
-                            String::from("The record contract might also be too strict. By default, record contracts exclude any field which is not listed.
-Append `, ..` at the end of the record contract, as in `{some_field | SomeContract, ..}`, to make it accept extra fields."),
-                        ]);
-
-                    return Err(EvalError::BlameError {
+                        .with_contract_id(contract_id)
+                        .with_diagnostic_message(message)
+                        .with_diagnostic_notes(vec![note]);
+
+                    return Err(MergeError::BlameError {
                         evaluated_arg: label.get_evaluated_arg(cache),
                         label,
                         call_stack: CallStack::new(),
@@ -259,23 +1450,162 @@ Append `, ..` at the end of the record contract, as in `{some_field | SomeContra
                 _ => (),
             };
 
-            let final_pos = if let MergeMode::Standard(_) = mode {
-                pos_op.into_inherited()
+            // With the `intersect` strategy, the fields defined on only one side are dropped.
+            // Since they aren't part of the final record anymore, the remaining fields can't
+            // refer to them recursively.
+            let (left, right) = if mode.strategy() == MergeStrategy::Intersect {
+                field_names.retain(|id| center.contains_key(id));
+                (IndexMap::new(), IndexMap::new())
             } else {
+                (left, right)
+            };
+
+            let final_pos = if let MergeMode::Contract(..) = mode {
                 pos1.into_inherited()
+            } else {
+                pos_op.into_inherited()
             };
 
+            let contract_id = match &mode {
+                MergeMode::Contract(label, _) => Some(ContractId::of(label)),
+                _ => None,
+            };
+            let contract = contract_id.is_some();
+            let fill_defaults = matches!(mode, MergeMode::FillDefaults(_));
+            let left_biased = matches!(mode, MergeMode::LeftBiased(_));
+            // Only standard merges count in the number of records a record is merged from.
+            let attrs = if matches!(mode, MergeMode::Standard(_)) {
+                RecordAttrs::merge_counted(r1.attrs, r2.attrs)
+            } else {
+                RecordAttrs::merge(r1.attrs, r2.attrs)
+            };
             let merge_label = MergeLabel::from(mode);
+            ctx.check_cancelled()?;
+            ctx.fuel.consume(left.len() + right.len(), &merge_label)?;
 
-            let field_names: Vec<_> = left
-                .keys()
-                .chain(center.keys())
-                .chain(right.keys())
-                .cloned()
+            // The fields added by the right operand, whose flag is set once the record is merged.
+            // The other fields aren't new anymore, even if a previous merge had introduced them.
+            let introduced: Option<HashSet<Ident>> =
+                (ctx.track_introduced_fields && !contract).then(|| right.keys().cloned().collect());
+
+            // When applying a contract, the fields of the value that aren't mentioned by the
+            // contract can't be overridden. Those which aren't recursive are passed through as
+            // they are, which avoids copying each cache element of a large value checked against
+            // a small contract.
+            //
+            // If reverting non-recursive fields is disabled, the same goes for the fields defined
+            // on one side only in any merge. Reverting a field which doesn't depend on any other
+            // field gives back the very same expression, such that the fields of a flat record
+            // are all re-closurized as they are.
+            let passthrough: HashSet<Ident> = {
+                let mut passthrough = HashSet::new();
+
+                if contract || ctx.skip_flat_reverts {
+                    passthrough.extend(
+                        left.iter()
+                            .filter(|(_, field)| !is_revertible(cache, field, &env1))
+                            .map(|(id, _)| *id),
+                    );
+                }
+
+                if ctx.skip_flat_reverts {
+                    passthrough.extend(
+                        right
+                            .iter()
+                            .filter(|(_, field)| !is_revertible(cache, field, &env2))
+                            .map(|(id, _)| *id),
+                    );
+                }
+
+                passthrough
+            };
+
+            // When applying a contract, a default value provided by the contract is overridden by
+            // a default value of the checked record instead of being merged with it.
+            let center = if contract {
+                drop_tied_contract_defaults(center)
+            } else {
+                center
+            };
+
+            // The contracts of the fields of a record contract are tagged with the identifier of
+            // the record contract, unless they already belong to a nested one, such that their
+            // blame errors can be grouped by record contract.
+            let (center, right) = match &contract_id {
+                Some(contract_id) => (
+                    center
+                        .into_iter()
+                        .map(|(id, (field1, field2))| {
+                            (id, (field1, tag_contracts(field2, contract_id)))
+                        })
+                        .collect(),
+                    right
+                        .into_iter()
+                        .map(|(id, field)| (id, tag_contracts(field, contract_id)))
+                        .collect(),
+                ),
+                None => (center, right),
+            };
+
+            // `left`, `center` and `right` are disjoint, so this is exactly the number of fields
+            // of the merged record. Each part is already allocated, so the sum can't overflow in
+            // practice, but we'd rather saturate than wrap around.
+            let fields_count = left
+                .len()
+                .saturating_add(center.len())
+                .saturating_add(right.len());
+
+            match ctx.max_fields {
+                Some(max_fields) if fields_count > max_fields => {
+                    return Err(MergeError::RecordTooLarge {
+                        fields: fields_count,
+                        max_fields,
+                        merge_label,
+                    });
+                }
+                _ => (),
+            }
+
+            if let Some(stats) = &mut ctx.stats {
+                stats.record_merges += 1;
+                stats.fields_split += fields_count;
+                stats.reverts += left.len() + right.len() - passthrough.len();
+            }
+
+            field_names.extend(right.keys().cloned());
+            // Inserting a key which is already present keeps its original position. We reserve
+            // the position of each field upfront, such that the fields can then be processed part
+            // by part while the merged record still follows the order of the operands.
+            let mut m: IndexMap<Ident, Field> = field_names
+                .iter()
+                .map(|id| (*id, Field::default()))
                 .collect();
-            let mut m = IndexMap::with_capacity(left.len() + center.len() + right.len());
             let mut env = Environment::new();
 
+            // Overriding fields may create a cycle of fields which are defined as each other,
+            // such as in `{a | default = 1, b = a} & {a = b, b | default = 2}`. Evaluating them
+            // would only report an infinite recursion, so we report the cycle instead. Only the
+            // fields defined on both sides can be overridden, such that there's no new cycle
+            // without them.
+            let cycles = if center.is_empty() {
+                Vec::new()
+            } else {
+                let aliases = merge_aliases(
+                    cache,
+                    (&left, &env1),
+                    &center,
+                    (&right, &env2),
+                    &passthrough,
+                    fill_defaults,
+                );
+                alias_cycles(&field_names, &aliases)
+            };
+
+            // When timings are collected, the time spent on each field is attributed to the
+            // top-level field it belongs to. Nested records are merged lazily, possibly long after
+            // this merge, but their path still starts with the top-level field.
+            let top_level = merge_label.path.outermost();
+
             // Merging recursive records is the one operation that may override recursive fields. To
             // have the recursive fields depend on the updated values, we need to revert the
             // corresponding elements in the cache to their original expression.
@@ -285,31 +1615,140 @@ Append `, ..` at the end of the record contract, as in `{some_field | SomeContra
             // The fields in the intersection (center) need a slightly more general treatment to
             // correctly propagate the recursive values down each field: saturation. See
             // [crate::eval::cache::Cache::saturate()].
-            m.extend(
-                left.into_iter()
-                    .map(|(id, field)| (id, field.revert_closurize(cache, &mut env, env1.clone()))),
-            );
+            m.extend(left.into_iter().map(|(id, field)| {
+                let start = ctx.start_timing();
+                let field = if passthrough.contains(&id) {
+                    field.closurize(cache, &mut env, env1.clone())
+                } else {
+                    field.revert_closurize(cache, &mut env, env1.clone())
+                };
+                ctx.record_timing(top_level.unwrap_or(id), start);
 
-            m.extend(
-                right
-                    .into_iter()
-                    .map(|(id, field)| (id, field.revert_closurize(cache, &mut env, env2.clone()))),
-            );
+                (id, field)
+            }));
+
+            m.extend(right.into_iter().map(|(id, field)| {
+                let start = ctx.start_timing();
+                let field = if passthrough.contains(&id) {
+                    field.closurize(cache, &mut env, env2.clone())
+                } else {
+                    field.revert_closurize(cache, &mut env, env2.clone())
+                };
+                ctx.record_timing(top_level.unwrap_or(id), start);
+
+                (id, field)
+            }));
 
             for (id, (field1, field2)) in center.into_iter() {
-                m.insert(
-                    id,
-                    merge_fields(
-                        cache,
-                        merge_label,
-                        field1,
-                        env1.clone(),
-                        field2,
-                        env2.clone(),
-                        &mut env,
-                        field_names.iter(),
-                    )?,
+                let start = ctx.start_timing();
+
+                // When filling defaults, the left value always wins and the right one is ignored.
+                if fill_defaults {
+                    m.insert(id, field1.revert_closurize(cache, &mut env, env1.clone()));
+                    ctx.record_timing(top_level.unwrap_or(id), start);
+                    continue;
+                }
+
+                let (field1, field2) = if field_errors.is_some() {
+                    ignore_parse_errors(cache, field1, &env1, field2, &env2)
+                } else {
+                    (field1, field2)
+                };
+
+                let field_label = MergeLabel {
+                    path: merge_label.path.push(id),
+                    ..merge_label.clone()
+                };
+
+                let result = merge_fields(
+                    cache,
+                    field_label,
+                    ctx,
+                    left_biased,
+                    field1,
+                    env1.clone(),
+                    field2,
+                    env2.clone(),
+                    &mut env,
+                    field_names.iter(),
                 );
+                ctx.record_timing(top_level.unwrap_or(id), start);
+
+                // In strict mode, a required field which neither side gives a value to is reported
+                // right away, instead of when the field is used.
+                let result = result.and_then(|field| match &right_ids {
+                    Some(right_ids) if field.value.is_none() && !field.metadata.opt => {
+                        Err(MergeError::MissingFieldDef {
+                            left_pos: id.pos,
+                            right_pos: right_ids.get(&id).map_or(TermPos::None, |id| id.pos),
+                            merge_label: MergeLabel {
+                                path: merge_label.path.push(id),
+                                ..merge_label.clone()
+                            },
+                        })
+                    }
+                    _ => Ok(field),
+                });
+
+                // Two contracts which can't both hold are reported right away when requested,
+                // unless the field is optional and doesn't have a value, as its contracts are
+                // then never applied.
+                let result = result.and_then(|field| {
+                    if !ctx.check_contradictory_contracts || field.is_empty_optional() {
+                        return Ok(field);
+                    }
+
+                    match contradictory_contracts(&field.metadata.annotation) {
+                        Some((ctr1, ctr2)) => Err(MergeError::ContradictoryContracts {
+                            contract1: ctr1.types.clone(),
+                            span1: ctr1.label.span,
+                            contract2: ctr2.types.clone(),
+                            span2: ctr2.label.span,
+                            merge_label: MergeLabel {
+                                path: merge_label.path.push(id),
+                                ..merge_label.clone()
+                            },
+                        }),
+                        None => Ok(field),
+                    }
+                });
+
+                let field = match (result, field_errors.as_deref_mut()) {
+                    (Ok(field), _) => field,
+                    // A cancellation aborts the merge, even when errors are collected.
+                    (Err(MergeError::Cancelled), _) => return Err(MergeError::Cancelled),
+                    (Err(err), Some(errors)) => {
+                        let err = EvalError::from(err);
+                        errors.push(err.clone());
+                        Field::from(RichTerm::from(Term::RuntimeError(err)))
+                    }
+                    (Err(err), None) => return Err(err),
+                };
+
+                m.insert(id, field);
+            }
+
+            for fields in cycles {
+                let err = EvalError::MergeCycle {
+                    fields: fields.clone(),
+                    merge_label: merge_label.clone(),
+                };
+
+                for id in &fields {
+                    if let Some(field) = m.get_mut(id) {
+                        field.value = Some(RichTerm::from(Term::RuntimeError(err.clone())));
+                    }
+                }
+
+                if let Some(errors) = field_errors.as_deref_mut() {
+                    errors.push(err);
+                }
+            }
+
+            if let Some(introduced) = &introduced {
+                for (id, field) in m.iter_mut() {
+                    field.metadata.introduced = introduced.contains(id);
+                }
             }
 
             Ok(Closure {
@@ -318,25 +1757,48 @@ Append `, ..` at the end of the record contract, as in `{some_field | SomeContra
                     // of program transformations. At this point, the interpreter doesn't care
                     // about them anymore, and dependencies are stored at the level of revertible
                     // cache elements directly.
-                    Term::RecRecord(
-                        RecordData::new(m, RecordAttrs::merge(r1.attrs, r2.attrs), None),
-                        Vec::new(),
-                        None,
-                    ),
+                    Term::RecRecord(RecordData::new(m, attrs, None), Vec::new(), None),
                     final_pos,
                 ),
                 env,
             })
         }
-        (t1_, t2_) => match (mode, &t2_) {
+        (t1_, t2_) => match (mode.identify_contract(), &t2_) {
+            // Passing a function where a record is expected is a common mistake, for example when
+            // a function hasn't been applied to all of its arguments: we report it specifically.
+            (MergeMode::Contract(label, _), Term::Record(..))
+                if matches!(t1_, Term::Fun(..) | Term::Match { .. }) =>
+            {
+                Err(MergeError::BlameError {
+                    evaluated_arg: Some(RichTerm::new(t1_, pos1)),
+                    label: label
+                        .with_diagnostic_message("expected a record, but value is a function")
+                        .append_diagnostic_note(
+                            "Have you forgotten to apply a function to all of its arguments?",
+                        ),
+                    call_stack: call_stack.clone(),
+                })
+            }
+            // Applying a record contract to `null` usually means that a record value hasn't been
+            // provided, for example when an optional value is left unset: we report it
+            // specifically as well.
+            (MergeMode::Contract(label, _), Term::Record(..)) if matches!(t1_, Term::Null) => {
+                Err(MergeError::BlameError {
+                    evaluated_arg: Some(RichTerm::new(t1_, pos1)),
+                    label: label
+                        .with_diagnostic_message("expected a record, but the value is null")
+                        .append_diagnostic_note("Did you forget to provide this field?"),
+                    call_stack: call_stack.clone(),
+                })
+            }
             // We want to merge a non-record term with a record contract
-            (MergeMode::Contract(label), Term::Record(..)) => Err(EvalError::BlameError {
+            (MergeMode::Contract(label, _), Term::Record(..)) => Err(MergeError::BlameError {
                 evaluated_arg: label.get_evaluated_arg(cache),
                 label,
                 call_stack: call_stack.clone(),
             }),
             // The following cases are either errors or not yet implemented
-            (mode, _) => Err(EvalError::MergeIncompatibleArgs {
+            (mode, _) => Err(MergeError::IncompatibleArgs {
                 left_arg: RichTerm::new(t1_, pos1),
                 right_arg: RichTerm::new(t2_, pos2),
                 merge_label: mode.into(),
@@ -345,44 +1807,402 @@ Append `, ..` at the end of the record contract, as in `{some_field | SomeContra
     }
 }
 
+/// The maximum number of fields listed in the notes of an extra field error.
+const MAX_LISTED_FIELDS: usize = 20;
+
+/// The maximum number of fields listed in the message of an extra field error, which is shown
+/// first and should stay short.
+const MAX_MESSAGE_FIELDS: usize = 10;
+
+/// Render a list of fields as a comma-separated list, showing at most `max` of them.
+fn truncated_list(fields: &[&str], max: usize) -> String {
+    let listed: Vec<String> = fields
+        .iter()
+        .take(max)
+        .map(|field| format!("`{field}`"))
+        .collect();
+    let listed = listed.join(", ");
+
+    if fields.len() > max {
+        format!("{listed} and {} more", fields.len() - max)
+    } else {
+        listed
+    }
+}
+
+/// Return the names of the fields only declared by a record contract, as given by `right`, which
+/// the checked value should define but doesn't. Fields with a default value are always filled.
+/// Optional fields are only missing in strict mode. See [AbsentFields].
+fn missing_fields(right: &IndexMap<Ident, Field>, absent_fields: AbsentFields) -> Vec<&str> {
+    right
+        .iter()
+        .filter(|(_, field)| {
+            field.value.is_none() && (absent_fields == AbsentFields::Strict || !field.metadata.opt)
+        })
+        .map(|(id, _)| id.label())
+        .collect()
+}
+
+/// Rename the fields of a value checked by a record contract, as given by `value`, to the casing
+/// of the fields of the contract, as given by `contract`, which they match case-insensitively. The
+/// other fields are left untouched, and the order of the fields is preserved. See
+/// [EvalConfig::case_insensitive_contracts].
+///
+/// If two fields of the same record match each other case-insensitively, it's ambiguous which
+/// one is checked: the two names are returned as an error.
+fn match_contract_casing(
+    value: IndexMap<Ident, Field>,
+    contract: &IndexMap<Ident, Field>,
+) -> Result<IndexMap<Ident, Field>, (Ident, Ident)> {
+    let mut canonical = HashMap::with_capacity(contract.len());
+
+    for id in contract.keys() {
+        if let Some(prev) = canonical.insert(id.label().to_lowercase(), *id) {
+            return Err((prev, *id));
+        }
+    }
+
+    // The original name of each field of the result, to report collisions.
+    let mut originals = HashMap::with_capacity(value.len());
+    let mut renamed = IndexMap::with_capacity(value.len());
+
+    for (id, field) in value {
+        let new_id = canonical
+            .get(&id.label().to_lowercase())
+            .copied()
+            .unwrap_or(id);
+
+        if let Some(prev) = originals.insert(new_id, id) {
+            return Err((prev, id));
+        }
+
+        renamed.insert(new_id, field);
+    }
+
+    Ok(renamed)
+}
+
+/// Merge a record with each element of an array, as done by the `broadcast` merge strategy. The
+/// elements are checked to be records beforehand, in order to report a clear error otherwise.
+#[allow(clippy::too_many_arguments)]
+fn merge_broadcast<C: Cache>(
+    cache: &mut C,
+    record: RichTerm,
+    env_record: Environment,
+    array: Array,
+    attrs: ArrayAttrs,
+    env_array: Environment,
+    pos_array: TermPos,
+    pos_op: TermPos,
+    merge_label: MergeLabel,
+    ctx: &mut MergeContext,
+) -> Result<Closure, MergeError> {
+    use crate::{
+        mk_app, stdlib,
+        types::{DictTypeFlavour, TypeF, Types},
+    };
+
+    // The strategy only applies to the annotated field: the elements themselves are merged using
+    // the standard strategy.
+    let elem_label = MergeLabel {
+        strategy: MergeStrategy::Standard,
+        ..merge_label
+    };
+    ctx.fuel.consume(array.len(), &elem_label)?;
+
+    // The elements are checked against `{_ | Dyn}`, which only accepts records.
+    let is_record = mk_app!(
+        stdlib::internals::dict_contract(),
+        stdlib::internals::dynamic()
+    );
+    let label = Label {
+        types: Rc::new(Types::from(TypeF::Dict {
+            type_fields: Box::new(Types::from(TypeF::Dyn)),
+            flavour: DictTypeFlavour::Contract,
+        })),
+        span: elem_label.span,
+        ..Default::default()
+    }
+    .append_diagnostic_note(
+        "\
+        This contract was auto-generated from a merge operation using the `broadcast` strategy. \
+        A record can only be broadcast into an array of records.",
+    );
+
+    let mut record_env = Environment::new();
+    let record = record.closurize(cache, &mut record_env, env_record);
+    let mut env = Environment::new();
+
+    let merged: Array = array
+        .into_iter()
+        .map(|elem| {
+            let elem = RuntimeContract::apply_all(
+                elem,
+                attrs.pending_contracts.iter().cloned(),
+                pos_array,
+            );
+            let elem =
+                RuntimeContract::new(is_record.clone(), label.clone()).apply(elem, pos_array);
+
+            let mut local_env = record_env.clone();
+            let elem = elem.closurize(cache, &mut local_env, env_array.clone());
+
+            mk_term::op2(BinaryOp::Merge(elem_label.clone()), elem, record.clone())
+                .closurize(cache, &mut env, local_env)
+        })
+        .collect();
+
+    Ok(Closure {
+        body: RichTerm::new(
+            Term::Array(merged, ArrayAttrs::new().closurized()),
+            pos_op.into_inherited(),
+        ),
+        env,
+    })
+}
+
+/// The maximum number of elements shown when displaying an array in the contract of an array
+/// merge. See [EvalConfig::display_merged_arrays].
+const MAX_DISPLAYED_ELEMENTS: usize = 20;
+
+/// Substitute the variables of an array bound in `env`, such that it can be shown in the contract
+/// of an array merge. Only the first [MAX_DISPLAYED_ELEMENTS] elements are kept, followed by `...`
+/// if the array is longer. The elements aren't evaluated.
+fn display_array<C: Cache>(cache: &C, array: &RichTerm, env: &Environment) -> RichTerm {
+    let array = subst(cache, array.clone(), &Environment::new(), env);
+
+    match array.as_ref() {
+        Term::Array(elts, attrs) if elts.len() > MAX_DISPLAYED_ELEMENTS => {
+            let elts = elts
+                .iter()
+                .take(MAX_DISPLAYED_ELEMENTS)
+                .cloned()
+                .chain(std::iter::once(mk_term::var("...")))
+                .collect();
+
+            RichTerm::new(Term::Array(elts, attrs.clone()), array.pos)
+        }
+        _ => array,
+    }
+}
+
+/// Merge two records, at least one of which has a sealed polymorphic tail, as requested by the
+/// `unsealed` merge strategy. Each sealed tail is unsealed and merged back into the record it was
+/// sealed from, and the two resulting records are then merged with the standard strategy.
+///
+/// **This breaks parametricity**. A function with a polymorphic contract such as `forall r. {a :
+/// Number; r} -> ...` is supposed to be oblivious to the fields of its argument hiding in `r`, but
+/// an unsealed merge exposes them, and may even fail because of them. The tails are unsealed
+/// without the sealing key, so this must only ever happen when explicitly asked for.
+fn merge_unsealed<C: Cache>(
+    cache: &mut C,
+    merge_label: MergeLabel,
+    r1: RecordData,
+    env1: Environment,
+    r2: RecordData,
+    env2: Environment,
+    pos_op: TermPos,
+) -> Closure {
+    // The strategy only applies to the records being merged: the merges introduced below use the
+    // standard strategy, and can't unseal anything anymore.
+    let merge_label = MergeLabel {
+        strategy: MergeStrategy::Standard,
+        ..merge_label
+    };
+    let mut env = Environment::new();
+
+    let mut unseal = |record: RecordData, record_env: Environment| {
+        let visible = RecordData::new(record.fields, record.attrs, None);
+        let visible =
+            RichTerm::from(Term::Record(visible)).closurize(cache, &mut env, record_env.clone());
+
+        // The tail is bound in the environment of the sealed record, and its fields are disjoint
+        // from the visible ones.
+        match record.sealed_tail {
+            Some(tail) => mk_term::op2(
+                BinaryOp::Merge(merge_label.clone()),
+                visible,
+                tail.into_unsealed().closurize(cache, &mut env, record_env),
+            ),
+            None => visible,
+        }
+    };
+
+    let left = unseal(r1, env1);
+    let right = unseal(r2, env2);
+
+    Closure {
+        body: mk_term::op2(BinaryOp::Merge(merge_label), left, right).with_pos(pos_op),
+        env,
+    }
+}
+
 /// Take two record fields in their respective environment and combine both their metadata and
 /// values. Apply the required saturate, revert or closurize operation, including on the final
 /// field returned.
+///
+/// If `left_biased` is `true`, the values are merged in the [MergeMode::LeftBiased] mode instead
+/// of the standard one.
 #[allow(clippy::too_many_arguments)]
 fn merge_fields<'a, C: Cache, I: DoubleEndedIterator<Item = &'a Ident> + Clone>(
     cache: &mut C,
     merge_label: MergeLabel,
+    ctx: &mut MergeContext,
+    left_biased: bool,
     field1: Field,
     env1: Environment,
     field2: Field,
     env2: Environment,
     env_final: &mut Environment,
     fields: I,
-) -> Result<Field, EvalError> {
+) -> Result<Field, MergeError> {
+    ctx.check_cancelled()?;
+    ctx.fuel.consume(1, &merge_label)?;
+
+    if let Some(stats) = &mut ctx.stats {
+        stats.field_merges += 1;
+    }
+
     // For now, we blindly closurize things and copy environments in this function. A
     // careful analysis would make it possible to spare a few closurize operations and more
     // generally environment cloning.
+    // Accumulate the positions of all the definitions of this field, including the ones that are
+    // overridden because of their lower priority.
+    let mut definition_sites = field1.definition_sites();
+    let definition_sites2 = field2.definition_sites();
+    let kind = FieldMergeKind::of(&field1, &field2);
+    // Same for the documentations, which are kept together with the position of their definition.
+    let mut doc_sources = field1.doc_sources();
+    doc_sources.extend(field2.doc_sources());
+    let contributions = field1.contributions() + field2.contributions();
+
+    if let Some(observer) = &ctx.observer {
+        observer.borrow_mut().field_merged(FieldMergeEvent {
+            path: &merge_label.path,
+            left_sites: &definition_sites,
+            right_sites: &definition_sites2,
+            kind,
+        });
+    }
+
+    // The side whose value is overridden by a value of higher priority, if any.
+    let overridden = match kind {
+        FieldMergeKind::Left if field2.value.is_some() => Some(MergeSide::Right),
+        FieldMergeKind::Right if field1.value.is_some() => Some(MergeSide::Left),
+        _ => None,
+    };
+
+    let priority_resolution = match (kind, overridden) {
+        (FieldMergeKind::Merge, _) => PriorityResolution::MergedEqual,
+        (_, Some(MergeSide::Left)) => PriorityResolution::OverrodeLeft,
+        (_, Some(MergeSide::Right)) => PriorityResolution::OverrodeRight,
+        (_, None) => PriorityResolution::DefaultOnly,
+    };
+
+    definition_sites.extend(definition_sites2);
+
     let Field {
         metadata: metadata1,
         value: value1,
-        pending_contracts: pending_contracts1,
+        pending_contracts: mut pending_contracts1,
     } = field1;
     let Field {
         metadata: metadata2,
         value: value2,
-        pending_contracts: pending_contracts2,
+        pending_contracts: mut pending_contracts2,
     } = field2;
 
+    // When one definition overrides the other, users may expect the documentation to follow the
+    // value, while [merge_doc] always keeps the documentation of the left definition.
+    if let (Some(warnings), Some(side), Some(doc1), Some(doc2)) = (
+        &mut ctx.doc_override_warnings,
+        overridden,
+        &metadata1.doc,
+        &metadata2.doc,
+    ) {
+        if doc1 != doc2 {
+            warnings.push(DocOverrideWarning {
+                path: merge_label.path.clone(),
+                doc: doc2.clone(),
+                pos: value2.as_ref().map_or(TermPos::None, |value| value.pos),
+                overridden: side,
+            });
+        }
+    }
+
+    let opt = merge_opt(&metadata1, &metadata2);
+
+    // When both definitions select a strategy, the one of the definition with the highest
+    // priority is used, as is its value. Definitions of the same priority must agree, as it would
+    // otherwise be ambiguous how to merge their values.
+    let merge_strategy = if metadata2.priority > metadata1.priority {
+        metadata2.merge_strategy.or(metadata1.merge_strategy)
+    } else {
+        match (metadata1.merge_strategy, metadata2.merge_strategy) {
+            (Some(left), Some(right))
+                if metadata1.priority == metadata2.priority
+                    && strategies_conflict(&left, &right) =>
+            {
+                return Err(MergeError::ConflictingStrategies {
+                    left,
+                    right,
+                    merge_label,
+                });
+            }
+            (strategy1, strategy2) => strategy1.or(strategy2),
+        }
+    };
+
+    // A merge annotation only applies to the values of the annotated field, so we don't inherit
+    // the strategy of the enclosing merge.
+    let merge_label = MergeLabel {
+        strategy: merge_strategy.clone().unwrap_or_default(),
+        optional: metadata1.opt && metadata2.opt,
+        ..merge_label
+    };
+
+    let merged = value1.is_some() && value2.is_some() && metadata1.priority == metadata2.priority;
+
+    // A misspelled merge function is reported as soon as the values are set to be merged, even if
+    // the field is never used.
+    if let MergeStrategy::Custom { name } = &merge_label.strategy {
+        if merged && !ctx.merge_fns.contains_key(name) {
+            return Err(MergeError::UnknownMergeFn {
+                name: name.clone(),
+                merge_label,
+            });
+        }
+    }
+
     // Selecting either meta1's value, meta2's value, or the merge of the two values,
     // depending on which is defined and respective priorities.
     let (value, priority) = match (value1, value2) {
-        (Some(t1), Some(t2)) if metadata1.priority == metadata2.priority => (
-            Some(
-                fields_merge_closurize(cache, merge_label, env_final, t1, &env1, t2, &env2, fields)
+        (Some(t1), Some(t2)) if metadata1.priority == metadata2.priority => {
+            // When exporting, the value of a field that isn't exported is only ever evaluated if
+            // another field depends on it. We can skip some of the work upfront.
+            let shallow = ctx.for_export && (metadata1.not_exported || metadata2.not_exported);
+
+            (
+                Some(
+                    fields_merge_closurize(
+                        cache,
+                        merge_label,
+                        env_final,
+                        t1,
+                        &env1,
+                        t2,
+                        &env2,
+                        fields,
+                        shallow,
+                        ctx.terminal,
+                        left_biased,
+                        ctx.stats.as_mut(),
+                    )
                     .unwrap(),
-            ),
-            metadata1.priority,
-        ),
+                ),
+                metadata1.priority,
+            )
+        }
         (Some(t1), _) if metadata1.priority > metadata2.priority => (
             Some(t1.revert_closurize(cache, env_final, env1.clone())),
             metadata1.priority,
@@ -403,18 +2223,49 @@ fn merge_fields<'a, C: Cache, I: DoubleEndedIterator<Item = &'a Ident> + Clone>(
         _ => unreachable!(),
     };
 
+    // The value which takes precedence, if it's not merged recursively, has been reverted.
+    if let (Some(stats), Some(_), false) = (&mut ctx.stats, &value, merged) {
+        stats.reverts += 1;
+    }
+
+    let enum_union = if ctx.union_enum_contracts {
+        union_enum_contracts(&mut pending_contracts1, &mut pending_contracts2)
+    } else {
+        None
+    };
+
+    // The order of the pending contracts is deterministic, and determines which contract is blamed
+    // first when several ones fail (see [RuntimeContract::apply_all]): the contracts of the left
+    // operand come first, then the ones of the right operand, and finally the union of enum
+    // contracts. Type annotations are checked before all of them, because their contract is
+    // directly applied to the value they're attached to (see
+    // [crate::transform::gen_pending_contracts]).
     let mut pending_contracts = pending_contracts1.revert_closurize(cache, env_final, env1.clone());
     pending_contracts.extend(
         pending_contracts2
             .revert_closurize(cache, env_final, env2.clone())
             .into_iter(),
     );
+    // The generated contract only refers to the standard library, hence the empty environment.
+    pending_contracts
+        .extend(enum_union.map(|ctr| ctr.closurize(cache, env_final, Environment::new())));
 
     // Annotation aren't used anymore at runtime. We still accumulate them to answer metadata
     // queries, but we don't need to e.g. closurize or revert them.
     let mut annot1 = metadata1.annotation;
     let mut annot2 = metadata2.annotation;
 
+    // As opposed to contract annotations, a type annotation only constrains the value it's
+    // attached to: the corresponding contract is applied once and for all to this value, and
+    // doesn't propagate through merging (see [crate::transform::gen_pending_contracts]). When this
+    // value is overridden, the type annotation doesn't tell anything about the final value, so we
+    // drop it instead of accumulating it with the contracts of the result.
+    match overridden {
+        Some(MergeSide::Left) => annot1.types = None,
+        Some(MergeSide::Right) => annot2.types = None,
+        None => (),
+    }
+
     // If both have type annotations, we arbitrarily choose the first one as the type annotation
     // for the resulting field. This doesn't make any difference operationally.
     let types = match (annot1.types.take(), annot2.types.take()) {
@@ -428,223 +2279,1620 @@ fn merge_fields<'a, C: Cache, I: DoubleEndedIterator<Item = &'a Ident> + Clone>(
     let contracts: Vec<_> = annot1
         .contracts
         .into_iter()
-        .chain(annot2.contracts.into_iter())
+        .chain(annot2.contracts.into_iter())
+        .collect();
+
+    let metadata = FieldMetadata {
+        doc: merge_doc(metadata1.doc, metadata2.doc),
+        annotation: TypeAnnotation { types, contracts },
+        opt,
+        relax_opt: metadata1.relax_opt || metadata2.relax_opt,
+        frozen: metadata1.frozen || metadata2.frozen,
+        // The resulting field will be suppressed from serialization if either of the fields to be merged is.
+        not_exported: metadata1.not_exported || metadata2.not_exported,
+        priority,
+        merge_strategy,
+        definition_sites,
+        doc_sources,
+        contributions,
+        priority_resolution: Some(priority_resolution),
+        // In contract mode, the value is on the left, and keeps track of the fields introduced by
+        // previous merges. Other merges update the flag afterwards, if requested.
+        introduced: metadata1.introduced,
+    };
+
+    Ok(Field {
+        metadata,
+        value,
+        pending_contracts,
+    })
+}
+
+/// Return `true` if two merge strategies selected by the definitions of the same field differ.
+/// Comparators are compared regardless of their position, such that the same comparator written
+/// in two places doesn't conflict with itself.
+fn strategies_conflict(strategy1: &MergeStrategy, strategy2: &MergeStrategy) -> bool {
+    match (strategy1, strategy2) {
+        (
+            MergeStrategy::Compare {
+                comparator: comparator1,
+            },
+            MergeStrategy::Compare {
+                comparator: comparator2,
+            },
+        ) => comparator1.clone().without_pos() != comparator2.clone().without_pos(),
+        _ => strategy1 != strategy2,
+    }
+}
+
+/// Return two contracts of a field annotation which can never both hold, if any. The check is
+/// conservative, to avoid reporting contracts which are actually compatible: only the contracts of
+/// different primitive types, such as `Number` and `String`, are considered contradictory. User
+/// contracts, and composite types such as arrays or records, are never reported.
+fn contradictory_contracts(annot: &TypeAnnotation) -> Option<(&LabeledType, &LabeledType)> {
+    use crate::types::TypeF;
+
+    let primitives: Vec<&LabeledType> = annot
+        .types
+        .iter()
+        .chain(annot.contracts.iter())
+        .filter(|ctr| {
+            matches!(
+                ctr.types.types,
+                TypeF::Number | TypeF::Bool | TypeF::String | TypeF::Symbol
+            )
+        })
+        .collect();
+
+    let first = primitives.first()?;
+    let other = primitives
+        .iter()
+        .find(|ctr| ctr.types.types != first.types.types)?;
+
+    Some((first, other))
+}
+
+/// Return the tags accepted by a contract if it's the contract of an enum type without a tail, as
+/// `[| 'A, 'B |]`.
+fn closed_enum_tags(contract: &RuntimeContract) -> Option<Vec<Ident>> {
+    use crate::types::{EnumRowsIteratorItem, TypeF};
+
+    if !contract.label.path.is_empty() {
+        return None;
+    }
+
+    let TypeF::Enum(erows) = &contract.label.types.types else {
+        return None;
+    };
+
+    erows
+        .iter()
+        .map(|item| match item {
+            EnumRowsIteratorItem::Row(id) => Some(*id),
+            EnumRowsIteratorItem::TailVar(_) => None,
+        })
+        .collect()
+}
+
+/// Remove the first enum contract without a tail from each list of contracts, and return a
+/// contract accepting the tags of both. The lists are left untouched if one of them doesn't have
+/// such a contract. The resulting contract reuses the label of the left contract, with the type
+/// of the union.
+fn union_enum_contracts(
+    contracts1: &mut Vec<RuntimeContract>,
+    contracts2: &mut Vec<RuntimeContract>,
+) -> Option<RuntimeContract> {
+    use crate::types::{EnumRows, EnumRowsF, TypeF, Types};
+
+    let (index1, mut tags) = contracts1
+        .iter()
+        .enumerate()
+        .find_map(|(index, ctr)| Some((index, closed_enum_tags(ctr)?)))?;
+    let (index2, tags2) = contracts2
+        .iter()
+        .enumerate()
+        .find_map(|(index, ctr)| Some((index, closed_enum_tags(ctr)?)))?;
+
+    let label = contracts1.remove(index1).label;
+    contracts2.remove(index2);
+
+    for tag in tags2 {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let erows = tags
+        .into_iter()
+        .rev()
+        .fold(EnumRows(EnumRowsF::Empty), |tail, row| {
+            EnumRows(EnumRowsF::Extend {
+                row,
+                tail: Box::new(tail),
+            })
+        });
+    let types = Types::from(TypeF::Enum(erows));
+    let contract = types
+        .contract()
+        .expect("an enum type without a tail doesn't have free type variables");
+
+    Some(RuntimeContract::new(
+        contract,
+        Label {
+            types: Rc::new(types),
+            ..label
+        },
+    ))
+}
+
+/// The context of [Field::merge]: the environments of the fields being merged, and where to put
+/// the result.
+pub struct FieldMergeContext<'a, C: Cache> {
+    /// The evaluation cache.
+    pub cache: &'a mut C,
+    /// The label of the merge, which is used for error reporting.
+    pub merge_label: MergeLabel,
+    /// The environment of the left field.
+    pub env1: Environment,
+    /// The environment of the right field.
+    pub env2: Environment,
+    /// The environment in which the values and the contracts of the resulting field are
+    /// closurized.
+    pub env_final: &'a mut Environment,
+    /// The fields of the record the resulting field belongs to, which the recursive values of
+    /// the merged fields may depend on.
+    pub fields: &'a [Ident],
+}
+
+impl Field {
+    /// Combine two fields, with the same semantics as when two records defining this field are
+    /// merged: the value with the highest priority wins, values of the same priority are merged
+    /// lazily, and the metadata are combined.
+    ///
+    /// ```
+    /// # use codespan::{ByteIndex, Files};
+    /// # use nickel_lang_core::{
+    /// #     eval::{cache::{Cache, CacheImpl}, merge::FieldMergeContext, Environment},
+    /// #     label::MergeLabel,
+    /// #     position::RawSpan,
+    /// #     term::{make as mk_term, record::{Field, FieldMetadata}, MergePriority},
+    /// # };
+    /// # let mut files = Files::new();
+    /// # let src_id = files.add("<generated>", "base & patch");
+    /// # let span = RawSpan { src_id, start: ByteIndex(0), end: ByteIndex(12) };
+    /// let mut cache = CacheImpl::new();
+    /// let mut env_final = Environment::new();
+    ///
+    /// let base = Field {
+    ///     value: Some(mk_term::integer(1)),
+    ///     metadata: FieldMetadata {
+    ///         priority: MergePriority::Bottom,
+    ///         ..Default::default()
+    ///     },
+    ///     ..Default::default()
+    /// };
+    /// let patch = Field::from(mk_term::integer(2));
+    ///
+    /// let merged = base
+    ///     .merge(
+    ///         patch,
+    ///         FieldMergeContext {
+    ///             cache: &mut cache,
+    ///             merge_label: MergeLabel::new(span),
+    ///             env1: Environment::new(),
+    ///             env2: Environment::new(),
+    ///             env_final: &mut env_final,
+    ///             fields: &[],
+    ///         },
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // The value of the patch wins over the default value.
+    /// assert_eq!(merged.metadata.priority, MergePriority::Neutral);
+    /// assert!(merged.value.is_some());
+    /// ```
+    pub fn merge<C: Cache>(
+        self,
+        other: Field,
+        ctx: FieldMergeContext<'_, C>,
+    ) -> Result<Field, EvalError> {
+        let FieldMergeContext {
+            cache,
+            merge_label,
+            env1,
+            env2,
+            env_final,
+            fields,
+        } = ctx;
+
+        merge_fields(
+            cache,
+            merge_label,
+            &mut MergeContext::default(),
+            false,
+            self,
+            env1,
+            other,
+            env2,
+            env_final,
+            fields.iter(),
+        )
+        .map_err(EvalError::from)
+    }
+}
+
+/// Determine if the result of merging two fields is optional. If one of the records requires this
+/// field, then it musn't be optional: the resulting field is optional iff both are. The exception
+/// is a field relaxed by a `relax_optional` annotation, which stays optional when merged with a
+/// definition of lower or equal priority.
+fn merge_opt(metadata1: &FieldMetadata, metadata2: &FieldMetadata) -> bool {
+    let relaxes = |metadata: &FieldMetadata, other: &FieldMetadata| {
+        metadata.relax_opt && metadata.priority >= other.priority
+    };
+
+    (metadata1.opt && metadata2.opt)
+        || relaxes(metadata1, metadata2)
+        || relaxes(metadata2, metadata1)
+}
+
+/// Merge two optional documentations.
+fn merge_doc(doc1: Option<String>, doc2: Option<String>) -> Option<String> {
+    //FIXME: how to merge documentation? Just concatenate?
+    doc1.or(doc2)
+}
+
+/// See [crate::eval::cache::Cache::saturate]. Saturation is a transformation on recursive cache elements
+/// that is used when we must combine different values with different recursive dependencies (say,
+/// the two values of fields being merged) into one expression.
+///
+/// Saturation is first and foremost a transformation of terms, but like
+/// [crate::transform::Closurizable], it can be applied to other types that contain terms, hence
+/// the trait.
+trait Saturate: Sized {
+    /// Take the content of a record field, and saturate the potential revertible element with the given
+    /// fields. See [crate::eval::cache::Cache::saturate].
+    ///
+    /// If the expression is not a variable referring to an element in the cache
+    ///  (this can happen e.g. for numeric constants), we just return the term as it is, which
+    /// falls into the zero dependencies special case.
+    fn saturate<'a, I: DoubleEndedIterator<Item = &'a Ident> + Clone, C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        local_env: &Environment,
+        fields: I,
+    ) -> Result<Self, EvalError>;
+}
+
+impl Saturate for RichTerm {
+    fn saturate<'a, I: DoubleEndedIterator<Item = &'a Ident> + Clone, C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        local_env: &Environment,
+        fields: I,
+    ) -> Result<RichTerm, EvalError> {
+        if let Term::Var(var_id) = &*self.term {
+            let idx = local_env
+                .get(var_id)
+                .cloned()
+                .ok_or(EvalError::UnboundIdentifier(*var_id, self.pos))?;
+
+            Ok(cache.saturate(idx, env, fields).with_pos(self.pos))
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+/// Return the dependencies of a field when represented as a `RichTerm`.
+fn field_deps<C: Cache>(
+    cache: &C,
+    rt: &RichTerm,
+    local_env: &Environment,
+) -> Result<FieldDeps, EvalError> {
+    if let Term::Var(var_id) = &*rt.term {
+        local_env
+            .get(var_id)
+            .map(|idx| cache.deps(idx).unwrap_or_else(FieldDeps::empty))
+            .ok_or(EvalError::UnboundIdentifier(*var_id, rt.pos))
+    } else {
+        Ok(FieldDeps::empty())
+    }
+}
+
+/// The fields of a merged record whose value is an alias of other fields, in that evaluating the
+/// field immediately evaluates them. Each alias is tagged with the side of the merge which defines
+/// it.
+type Aliases = IndexMap<Ident, Vec<(Ident, MergeSide)>>;
+
+/// Return the field that the value of `field` is an alias of, if any. We only consider the values
+/// which depend on exactly one other field, such as `b` in `{a = b, b = 1}`, and check that their
+/// expression is a variable. Other values may refer to other fields as well, but the references are
+/// usually behind a lazy constructor, as in `{a = {c = b}, b = 1}`, and aren't worth analyzing.
+fn field_alias<C: Cache>(cache: &mut C, field: &Field, env: &Environment) -> Option<Ident> {
+    let Term::Var(var_id) = field.value.as_ref()?.as_ref() else {
+        return None;
+    };
+    let idx = env.get(var_id)?;
+
+    match cache.deps(idx) {
+        Some(FieldDeps::Known(deps)) if deps.len() == 1 => {
+            // The element may have been evaluated already. We look at the original expression,
+            // which is the one used by the merged record.
+            let original = cache.revert(idx);
+
+            match cache.get(original).body.as_ref() {
+                Term::Var(id) if deps.contains(id) => Some(*id),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Collect the aliases of a merged record, given the parts of the operands computed by
+/// [split::split]. For fields defined on both sides, only the definitions which take part in the
+/// merged value are considered. The fields passed through without being reverted aren't part of
+/// the merged record's recursive environment, and are ignored.
+fn merge_aliases<C: Cache>(
+    cache: &mut C,
+    (left, env1): (&IndexMap<Ident, Field>, &Environment),
+    center: &IndexMap<Ident, (Field, Field)>,
+    (right, env2): (&IndexMap<Ident, Field>, &Environment),
+    passthrough: &HashSet<Ident>,
+    fill_defaults: bool,
+) -> Aliases {
+    let mut aliases = Aliases::new();
+    let mut add_alias = |id: Ident, field: &Field, env: &Environment, side: MergeSide| {
+        if let Some(target) = field_alias(cache, field, env) {
+            aliases.entry(id).or_default().push((target, side));
+        }
+    };
+
+    for (id, field) in left.iter().filter(|(id, _)| !passthrough.contains(*id)) {
+        add_alias(*id, field, env1, MergeSide::Left);
+    }
+
+    for (id, (field1, field2)) in center {
+        let kind = if fill_defaults {
+            FieldMergeKind::Left
+        } else {
+            FieldMergeKind::of(field1, field2)
+        };
+
+        if matches!(kind, FieldMergeKind::Merge | FieldMergeKind::Left) {
+            add_alias(*id, field1, env1, MergeSide::Left);
+        }
+
+        if matches!(kind, FieldMergeKind::Merge | FieldMergeKind::Right) {
+            add_alias(*id, field2, env2, MergeSide::Right);
+        }
+    }
+
+    for (id, field) in right.iter().filter(|(id, _)| !passthrough.contains(*id)) {
+        add_alias(*id, field, env2, MergeSide::Right);
+    }
+
+    aliases
+}
+
+/// Find the cycles of aliases of a merged record which don't exist in either operand. Those are
+/// the strongly connected components of the graph of aliases which contain aliases from both sides
+/// of the merge. Each cycle is returned as the list of its fields, in the order of `field_names`.
+fn alias_cycles(field_names: &[Ident], aliases: &Aliases) -> Vec<Vec<Ident>> {
+    /// The state of Tarjan's strongly connected components algorithm.
+    #[derive(Default)]
+    struct Search {
+        next_index: usize,
+        /// The index and the lowlink of each visited field.
+        indices: HashMap<Ident, (usize, usize)>,
+        stack: Vec<Ident>,
+        on_stack: HashSet<Ident>,
+        components: Vec<HashSet<Ident>>,
+    }
+
+    impl Search {
+        fn enter(&mut self, id: Ident) {
+            let index = self.next_index;
+            self.next_index += 1;
+            self.indices.insert(id, (index, index));
+            self.stack.push(id);
+            self.on_stack.insert(id);
+        }
+
+        fn lower(&mut self, id: Ident, lowlink: usize) {
+            let entry = self.indices.get_mut(&id).unwrap();
+            entry.1 = entry.1.min(lowlink);
+        }
+
+        /// Once all the aliases of `id` have been followed, pop its component if it's the root
+        /// of one.
+        fn leave(&mut self, id: Ident) {
+            let (index, lowlink) = self.indices[&id];
+
+            if index == lowlink {
+                let mut component = HashSet::new();
+
+                while let Some(top) = self.stack.pop() {
+                    self.on_stack.remove(&top);
+                    component.insert(top);
+
+                    if top == id {
+                        break;
+                    }
+                }
+
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut search = Search::default();
+    // The fields being visited, each with the position of its next alias to follow. Aliases can
+    // be chained over as many fields as a record has, so we don't recurse.
+    let mut work: Vec<(Ident, usize)> = Vec::new();
+
+    for root in aliases.keys() {
+        if search.indices.contains_key(root) {
+            continue;
+        }
+
+        search.enter(*root);
+        work.push((*root, 0));
+
+        while let Some((id, next)) = work.last_mut() {
+            let id = *id;
+
+            match aliases.get(&id).and_then(|targets| targets.get(*next)) {
+                Some((target, _)) => {
+                    *next += 1;
+
+                    if !search.indices.contains_key(target) {
+                        search.enter(*target);
+                        work.push((*target, 0));
+                    } else if search.on_stack.contains(target) {
+                        let index = search.indices[target].0;
+                        search.lower(id, index);
+                    }
+                }
+                None => {
+                    work.pop();
+                    search.leave(id);
+
+                    if let Some((parent, _)) = work.last() {
+                        let lowlink = search.indices[&id].1;
+                        search.lower(*parent, lowlink);
+                    }
+                }
+            }
+        }
+    }
+
+    search
+        .components
+        .into_iter()
+        .filter(|component| {
+            let mut sides = component
+                .iter()
+                .flat_map(|id| aliases.get(id).into_iter().flatten())
+                .filter(|(target, _)| component.contains(target))
+                .map(|(_, side)| *side);
+
+            match sides.next() {
+                Some(first) => sides.any(|side| side != first),
+                None => false,
+            }
+        })
+        .map(|component| {
+            field_names
+                .iter()
+                .filter(|id| component.contains(*id))
+                .cloned()
+                .collect()
+        })
+        .collect()
+}
+
+/// Set the identifier of the record contract `contract_id` on the pending contracts of a field
+/// coming from this record contract, unless they already come from a nested record contract.
+fn tag_contracts(mut field: Field, contract_id: &ContractId) -> Field {
+    for ctr in field.pending_contracts.iter_mut() {
+        ctr.label
+            .contract_id
+            .get_or_insert_with(|| contract_id.clone());
+    }
+
+    field
+}
+
+/// Drop the value of the contract side of the fields defined on both sides of a contract
+/// application (see [MergeMode::Contract]) when both sides are default values. Both values would
+/// otherwise be merged as values of equal priority, which fails for most values which aren't
+/// records. A default provided by a contract is rather seen as a fallback, and is considered of
+/// strictly lower priority than a default provided by the checked value. The metadata of the
+/// contract side, including its contracts, are still merged with the value.
+fn drop_tied_contract_defaults(
+    center: IndexMap<Ident, (Field, Field)>,
+) -> IndexMap<Ident, (Field, Field)> {
+    center
+        .into_iter()
+        .map(|(id, (value_field, mut contract_field))| {
+            let tie = matches!(
+                (
+                    &value_field.metadata.priority,
+                    &contract_field.metadata.priority
+                ),
+                (MergePriority::Bottom, MergePriority::Bottom)
+            );
+
+            if tie && value_field.value.is_some() {
+                contract_field.value = None;
+            }
+
+            (id, (value_field, contract_field))
+        })
+        .collect()
+}
+
+/// Determine if the value or one of the pending contracts of a field is stored in a revertible
+/// cache element, that is if the field may depend recursively on other fields.
+fn is_revertible<C: Cache>(cache: &C, field: &Field, local_env: &Environment) -> bool {
+    field
+        .value
+        .iter()
+        .chain(field.pending_contracts.iter().map(|ctr| &ctr.contract))
+        .any(|rt| match &*rt.term {
+            Term::Var(var_id) => local_env
+                .get(var_id)
+                .map_or(true, |idx| cache.deps(idx).is_some()),
+            _ => false,
+        })
+}
+
+/// Return `true` if the value of a field is a placeholder left by the error recovery of the parser,
+/// possibly behind a variable.
+fn is_parse_error<C: Cache>(cache: &C, field: &Field, local_env: &Environment) -> bool {
+    match field.value.as_ref().map(|value| value.as_ref()) {
+        Some(Term::ParseError(_)) => true,
+        Some(Term::Var(var_id)) => local_env.get(var_id).map_or(false, |idx| {
+            matches!(cache.get(idx.clone()).body.as_ref(), Term::ParseError(_))
+        }),
+        _ => false,
+    }
+}
+
+/// Drop the value of one of two fields being merged leniently if it's a placeholder left by the
+/// error recovery of the parser, such that the value of the other field is kept as it is instead of
+/// being merged with the placeholder. If both values are placeholders, the left one is kept.
+fn ignore_parse_errors<C: Cache>(
+    cache: &C,
+    mut field1: Field,
+    env1: &Environment,
+    mut field2: Field,
+    env2: &Environment,
+) -> (Field, Field) {
+    if field1.value.is_some() && is_parse_error(cache, &field2, env2) {
+        field2.value = None;
+    } else if field2.value.is_some() && is_parse_error(cache, &field1, env1) {
+        field1.value = None;
+    }
+
+    (field1, field2)
+}
+
+/// Take the current environment, two fields with their local environment, and return a term which
+/// is the merge of the two fields, closurized in the provided final environment.
+///
+/// The element in the cache allocated for the result is revertible if and only if at least one of
+/// the original elements is (if one of the original values is overridable, then so is the merge of
+/// the two). In this case, the field dependencies are the union of the dependencies of each field.
+///
+/// The fields are saturated (see [saturate]) to properly propagate recursive dependencies down to
+/// `t1` and `t2` in the final, merged record.
+///
+/// If `shallow` is `true` and neither value depends on other fields, saturation is skipped
+/// altogether and the values are just closurized, which gives the same result without iterating
+/// over all the fields of the record.
+///
+/// If `terminal` is `true`, the merged record is the final result of the evaluation and is never
+/// reverted: the result is then allocated in a standard element, and the dependencies are only
+/// computed if they're needed for a shallow merge. See [MergeContext::terminal].
+///
+/// If `left_biased` is `true`, the values are merged with [BinaryOp::MergeLeftBiased] instead of
+/// the standard merge operator.
+///
+/// The saturations performed are recorded in `stats`, if provided.
+#[allow(clippy::too_many_arguments)]
+fn fields_merge_closurize<'a, I: DoubleEndedIterator<Item = &'a Ident> + Clone, C: Cache>(
+    cache: &mut C,
+    merge_label: MergeLabel,
+    env: &mut Environment,
+    t1: RichTerm,
+    env1: &Environment,
+    t2: RichTerm,
+    env2: &Environment,
+    fields: I,
+    shallow: bool,
+    terminal: bool,
+    left_biased: bool,
+    stats: Option<&mut MergeStats>,
+) -> Result<RichTerm, EvalError> {
+    let mut local_env = Environment::new();
+
+    let combined_deps = if terminal && !shallow {
+        FieldDeps::empty()
+    } else {
+        field_deps(cache, &t1, env1)?.union(field_deps(cache, &t2, env2)?)
+    };
+    // The merge is synthesized and has no position of its own. We use the position of the first
+    // value which has one, so that the errors raised when evaluating the merged field still point
+    // to the source.
+    let pos = if t1.pos.is_def() { t1.pos } else { t2.pos };
+
+    let (t1, t2) = if shallow && combined_deps.is_empty() {
+        (
+            t1.closurize(cache, &mut local_env, env1.clone()),
+            t2.closurize(cache, &mut local_env, env2.clone()),
+        )
+    } else {
+        if let Some(stats) = stats {
+            stats.saturations += 2;
+        }
+
+        (
+            t1.saturate(cache, &mut local_env, env1, fields.clone())?,
+            t2.saturate(cache, &mut local_env, env2, fields)?,
+        )
+    };
+
+    let op = if left_biased {
+        BinaryOp::MergeLeftBiased(merge_label)
+    } else {
+        BinaryOp::Merge(merge_label)
+    };
+    let body = RichTerm::from(Term::Op2(op, t1, t2)).with_pos(pos);
+
+    // We closurize the final result in an element with appropriate dependencies
+    let closure = Closure {
+        body,
+        env: local_env,
+    };
+    let fresh_var = Ident::fresh();
+
+    let binding_type = if terminal {
+        BindingType::Normal
+    } else {
+        // new_rev takes care of not creating a revertible element in the cache if the dependencies
+        // are empty.
+        BindingType::Revertible(combined_deps)
+    };
+
+    env.insert(
+        fresh_var,
+        cache.add(closure, IdentKind::Record, binding_type),
+    );
+
+    Ok(RichTerm::from(Term::Var(fresh_var)))
+}
+
+/// Same as [Closurizable], but also revert the element if the term is a variable.
+pub(super) trait RevertClosurize {
+    /// Revert the element at the index inside the term (if any), and closurize the result inside `env`.
+    fn revert_closurize<C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        with_env: Environment,
+    ) -> Self;
+}
+
+impl RevertClosurize for RichTerm {
+    fn revert_closurize<C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        with_env: Environment,
+    ) -> RichTerm {
+        if let Term::Var(id) = self.as_ref() {
+            // This create a fresh variable which is bound to a reverted copy of the original element
+            let reverted = cache.revert(with_env.get(id).unwrap());
+            let fresh_id = Ident::fresh();
+            env.insert(fresh_id, reverted);
+            RichTerm::new(Term::Var(fresh_id), self.pos)
+        } else {
+            // Otherwise, if it is not a variable after the share normal form transformations, it
+            // should be a constant and we don't need to revert anything
+            self
+        }
+    }
+}
+
+impl RevertClosurize for Field {
+    fn revert_closurize<C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        with_env: Environment,
+    ) -> Field {
+        let value = self
+            .value
+            .map(|value| value.revert_closurize(cache, env, with_env.clone()));
+
+        let pending_contracts = self
+            .pending_contracts
+            .revert_closurize(cache, env, with_env);
+
+        Field {
+            metadata: self.metadata,
+            value,
+            pending_contracts,
+        }
+    }
+}
+
+impl RevertClosurize for RuntimeContract {
+    fn revert_closurize<C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        with_env: Environment,
+    ) -> RuntimeContract {
+        self.map_contract(|ctr| ctr.revert_closurize(cache, env, with_env))
+    }
+}
+
+impl RevertClosurize for Vec<RuntimeContract> {
+    fn revert_closurize<C: Cache>(
+        self,
+        cache: &mut C,
+        env: &mut Environment,
+        with_env: Environment,
+    ) -> Vec<RuntimeContract> {
+        let mut result = Vec::with_capacity(self.len());
+        let mut pending_contracts = self.into_iter();
+        // The last contract can take ownership of `with_env`, which saves one clone.
+        let last = pending_contracts.next_back();
+
+        result.extend(pending_contracts.map(|pending_contract| {
+            pending_contract.revert_closurize(cache, env, with_env.clone())
+        }));
+
+        if let Some(last) = last {
+            result.push(last.revert_closurize(cache, env, with_env));
+        }
+
+        result
+    }
+}
+
+/// How a field defined in both operands of a merge is combined. See [analyze_merge].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FieldMergeKind {
+    /// Both fields have a value with the same priority: the values are recursively merged.
+    Merge,
+    /// The value of the left field takes precedence, either because it has a higher priority or
+    /// because the right field has no value.
+    Left,
+    /// The value of the right field takes precedence, either because it has a higher priority or
+    /// because the left field has no value.
+    Right,
+    /// None of the fields have a value: only their metadata are merged.
+    Empty,
+}
+
+impl FieldMergeKind {
+    /// Determine how two fields are combined, following the same rules as [merge_fields].
+    pub fn of(field1: &Field, field2: &Field) -> Self {
+        let (prio1, prio2) = (&field1.metadata.priority, &field2.metadata.priority);
+
+        match (&field1.value, &field2.value) {
+            (Some(_), Some(_)) if prio1 == prio2 => FieldMergeKind::Merge,
+            (Some(_), _) if prio1 > prio2 => FieldMergeKind::Left,
+            (Some(_), None) => FieldMergeKind::Left,
+            (_, Some(_)) => FieldMergeKind::Right,
+            (None, None) => FieldMergeKind::Empty,
+        }
+    }
+}
+
+/// The result of [analyze_merge].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergeAnalysis {
+    /// The fields which are only defined in the left operand.
+    pub left: Vec<Ident>,
+    /// The fields which are defined in both operands, and how they would be combined.
+    pub center: IndexMap<Ident, FieldMergeKind>,
+    /// The fields which are only defined in the right operand.
+    pub right: Vec<Ident>,
+}
+
+/// Compute how two records would be merged, without performing any evaluation. The fields are
+/// partitioned as by [split::split], and each field defined on both sides is classified according
+/// to the metadata of both definitions.
+///
+/// The analysis is shallow: it doesn't look into the values of the fields, which may be
+/// unevaluated, nor into dynamic fields.
+pub fn analyze_merge(r1: &RecordData, r2: &RecordData) -> MergeAnalysis {
+    let split::SplitResult {
+        left,
+        center,
+        right,
+    } = split::split(
+        r1.fields.iter().collect::<IndexMap<_, _>>(),
+        r2.fields.iter().collect::<IndexMap<_, _>>(),
+    );
+
+    MergeAnalysis {
+        left: left.into_keys().copied().collect(),
+        center: center
+            .into_iter()
+            .map(|(id, (field1, field2))| (*id, FieldMergeKind::of(field1, field2)))
+            .collect(),
+        right: right.into_keys().copied().collect(),
+    }
+}
+
+/// Whether two records would merge without conflict, as determined by
+/// [VirtualMachine::merge_compatibility].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MergeCompatibility {
+    /// No conflict has been found.
+    Clean,
+    /// Some fields are defined on both sides by different values of the same priority, which
+    /// can't be merged. The paths of the conflicting fields are listed in the order of the merged
+    /// record.
+    Conflicts(Vec<MergePath>),
+    /// A record to merge has a sealed tail, which makes the merge fail (see the Sealed tails
+    /// section of the module documentation). The path is the one of the merged field, or is empty
+    /// for the operands themselves.
+    SealedTail(MergePath),
+}
+
+/// Determine if merging two evaluated values which aren't both records would fail, according to
+/// the conflict policy. Values which can't be compared without evaluating them further, such as
+/// two arrays, are assumed to merge.
+pub(crate) fn values_conflict(policy: ConflictPolicy, value1: &Term, value2: &Term) -> bool {
+    match (value1, value2) {
+        _ if policy != ConflictPolicy::Error => false,
+        (Term::Fun(..) | Term::FunPattern(..), _) | (_, Term::Fun(..) | Term::FunPattern(..)) => {
+            true
+        }
+        _ if value1.is_constant() && value2.is_constant() => {
+            RichTerm::from(value1.clone()).without_pos()
+                != RichTerm::from(value2.clone()).without_pos()
+        }
+        (Term::Record(..) | Term::Array(..), other)
+        | (other, Term::Record(..) | Term::Array(..)) => other.is_constant(),
+        _ => false,
+    }
+}
+
+/// How a field is affected by merging an overlay into a base record. See [merge_with_delta].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeltaKind {
+    /// The field is only defined in the overlay.
+    Added,
+    /// The field is defined on both sides, and the overlay overrides or changes its value.
+    Changed,
+    /// The field is defined on both sides, but the overlay leaves its value as it is.
+    Unchanged,
+    /// The field is only defined in the base.
+    Untouched,
+}
+
+/// A field of a [MergeDelta].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDelta {
+    /// The path of the field, from the root of the merged records.
+    pub path: MergePath,
+    /// How the field is affected by the overlay.
+    pub kind: DeltaKind,
+    /// The value of the field in the base, if any.
+    pub old: Option<RichTerm>,
+    /// The value of the field in the overlay, if any.
+    pub new: Option<RichTerm>,
+    /// The position of the definition which determines the value of the merged field: the one of
+    /// the overlay for added and changed fields, and the one of the base otherwise.
+    pub pos: TermPos,
+}
+
+/// The changes made by an overlay to a base record, as computed by [merge_with_delta].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeDelta {
+    /// The fields of the merged record, in the same order.
+    pub fields: Vec<FieldDelta>,
+}
+
+impl MergeDelta {
+    /// Return the fields affected by the overlay in the given way.
+    pub fn fields_of_kind(&self, kind: DeltaKind) -> impl Iterator<Item = &FieldDelta> {
+        self.fields.iter().filter(move |field| field.kind == kind)
+    }
+}
+
+/// Same as [merge], but also compute a machine-readable delta describing what `overlay` changes in
+/// `base`, for example to review the effect of an overlay in a deployment pipeline.
+///
+/// The fields are partitioned as by [split::split]: the fields only defined in the overlay are
+/// added, and the fields only defined in the base are untouched. A field defined on both sides is
+/// changed when the value of the overlay takes precedence (see [FieldMergeKind]), or when both
+/// values are merged but differ, and is unchanged otherwise. When both values are records, their
+/// fields are classified in turn, and the delta lists the nested fields instead.
+///
+/// Merging is lazy: the values of the delta are reported as they currently are, and may not be
+/// evaluated yet. Values which can't be compared without evaluating them, such as two function
+/// applications, are considered to be different. If any operand isn't a record, the delta is
+/// empty.
+#[allow(clippy::too_many_arguments)]
+pub fn merge_with_delta<C: Cache>(
+    cache: &mut C,
+    base: RichTerm,
+    env1: Environment,
+    overlay: RichTerm,
+    env2: Environment,
+    pos_op: TermPos,
+    mode: MergeMode,
+    ctx: &mut MergeContext,
+    call_stack: &mut CallStack,
+) -> Result<(Closure, MergeDelta), MergeError> {
+    let mut delta = MergeDelta::default();
+
+    if let (Term::Record(r1), Term::Record(r2)) = (base.as_ref(), overlay.as_ref()) {
+        record_delta(
+            cache,
+            (r1, &env1),
+            (r2, &env2),
+            &MergePath::default(),
+            &mut delta.fields,
+        );
+    }
+
+    let closure = merge(
+        cache, base, env1, overlay, env2, pos_op, mode, ctx, call_stack,
+    )?;
+
+    Ok((closure, delta))
+}
+
+/// Implementation of [merge_with_delta] for two records located at `path`.
+fn record_delta<C: Cache>(
+    cache: &C,
+    (r1, env1): (&RecordData, &Environment),
+    (r2, env2): (&RecordData, &Environment),
+    path: &MergePath,
+    delta: &mut Vec<FieldDelta>,
+) {
+    let split::SplitResult {
+        left,
+        center,
+        right,
+    } = split::split(
+        r1.fields.iter().collect::<IndexMap<_, _>>(),
+        r2.fields.iter().collect::<IndexMap<_, _>>(),
+    );
+
+    // Follow the order of the merged record: the fields of `r1`, followed by the fields only
+    // defined in `r2`.
+    for id in r1.fields.keys() {
+        let path = path.push(*id);
+
+        if let Some(field) = left.get(id) {
+            delta.push(FieldDelta {
+                path,
+                kind: DeltaKind::Untouched,
+                old: cached_value(cache, field, env1).map(|closure| closure.body),
+                new: None,
+                pos: value_pos(field),
+            });
+        } else if let Some(&(field1, field2)) = center.get(id) {
+            field_delta(cache, (field1, env1), (field2, env2), path, delta);
+        }
+    }
+
+    for (id, field) in right {
+        delta.push(FieldDelta {
+            path: path.push(*id),
+            kind: DeltaKind::Added,
+            old: None,
+            new: cached_value(cache, field, env2).map(|closure| closure.body),
+            pos: value_pos(field),
+        });
+    }
+}
+
+/// Classify a field defined on both sides of a merge for [merge_with_delta].
+fn field_delta<C: Cache>(
+    cache: &C,
+    (field1, env1): (&Field, &Environment),
+    (field2, env2): (&Field, &Environment),
+    path: MergePath,
+    delta: &mut Vec<FieldDelta>,
+) {
+    let old = cached_value(cache, field1, env1);
+    let new = cached_value(cache, field2, env2);
+
+    let kind = match (FieldMergeKind::of(field1, field2), &old, &new) {
+        (FieldMergeKind::Right, ..) => DeltaKind::Changed,
+        (FieldMergeKind::Merge, Some(old), Some(new)) => {
+            if let (Term::Record(r1), Term::Record(r2)) = (old.body.as_ref(), new.body.as_ref()) {
+                record_delta(cache, (r1, &old.env), (r2, &new.env), &path, delta);
+                return;
+            }
+
+            if is_same_constant(&old.body, &new.body) {
+                DeltaKind::Unchanged
+            } else {
+                DeltaKind::Changed
+            }
+        }
+        _ => DeltaKind::Unchanged,
+    };
+
+    let pos = if kind == DeltaKind::Changed {
+        value_pos(field2)
+    } else {
+        value_pos(field1)
+    };
+
+    delta.push(FieldDelta {
+        path,
+        kind,
+        old: old.map(|closure| closure.body),
+        new: new.map(|closure| closure.body),
+        pos,
+    });
+}
+
+/// Return the current value of a field together with its environment. A closurized value is
+/// looked up in the cache, where it may have been evaluated since.
+fn cached_value<C: Cache>(cache: &C, field: &Field, env: &Environment) -> Option<Closure> {
+    let value = field.value.as_ref()?;
+
+    let cached = match value.as_ref() {
+        Term::Var(id) => env.get(id).map(|idx| cache.get(idx.clone())),
+        _ => None,
+    };
+
+    Some(cached.unwrap_or_else(|| Closure {
+        body: value.clone(),
+        env: env.clone(),
+    }))
+}
+
+/// Return the position of the value of a field, if any.
+fn value_pos(field: &Field) -> TermPos {
+    field
+        .value
+        .as_ref()
+        .map_or(TermPos::None, |value| value.pos)
+}
+
+/// Determine if two values are the same constant, which can be decided without evaluating them.
+fn is_same_constant(value1: &RichTerm, value2: &RichTerm) -> bool {
+    value1.as_ref().is_constant() && value1.clone().without_pos() == value2.clone().without_pos()
+}
+
+/// A conflict between two leaf values found by [merge_report].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// The path of the conflicting field, from the root of the merged records.
+    pub path: MergePath,
+    /// The value of the field in the left record.
+    pub left: RichTerm,
+    /// The value of the field in the right record.
+    pub right: RichTerm,
+}
+
+/// Merge two records, reporting all the conflicts between leaf values instead of failing on the
+/// first one. This is meant for validation pipelines, which want to list every conflict of a
+/// configuration at once.
+///
+/// Fields are combined following the same rules as [merge]: when both definitions have a value of
+/// the same priority, records are merged recursively and other values must be equal, while
+/// otherwise the value of highest priority wins. Two leaf values of the same priority which aren't
+/// equal are recorded as a [Conflict], and the field is replaced with a placeholder which raises a
+/// merge error if it's ever evaluated, as done by [merge_lenient].
+///
+/// Contrary to [merge_lenient], this doesn't evaluate anything: the records must be fully
+/// evaluated, for example by [crate::program::Program::eval_full], and values which aren't records
+/// are compared structurally. Merge strategies and contracts aren't taken into account.
+pub fn merge_report(r1: RecordData, r2: RecordData) -> (RecordData, Vec<Conflict>) {
+    let mut conflicts = Vec::new();
+    let record = merge_report_records(r1, r2, &MergePath::default(), &mut conflicts);
+
+    (record, conflicts)
+}
+
+/// Implementation of [merge_report] for two records located at `path`.
+fn merge_report_records(
+    r1: RecordData,
+    r2: RecordData,
+    path: &MergePath,
+    conflicts: &mut Vec<Conflict>,
+) -> RecordData {
+    // Reserve the position of each field to follow the order of the standard merge: inserting a
+    // key which is already present doesn't move it.
+    let mut fields: IndexMap<Ident, Field> = r1
+        .fields
+        .keys()
+        .chain(r2.fields.keys())
+        .map(|id| (*id, Field::default()))
+        .collect();
+
+    let split::SplitResult {
+        left,
+        center,
+        right,
+    } = split::split(r1.fields, r2.fields);
+
+    fields.extend(left);
+    fields.extend(right);
+
+    for (id, (field1, field2)) in center {
+        let field = merge_report_fields(field1, field2, &path.push(id), conflicts);
+        fields.insert(id, field);
+    }
+
+    RecordData::new(fields, RecordAttrs::merge_counted(r1.attrs, r2.attrs), None)
+}
+
+/// Combine two definitions of the field located at `path` for [merge_report].
+fn merge_report_fields(
+    field1: Field,
+    field2: Field,
+    path: &MergePath,
+    conflicts: &mut Vec<Conflict>,
+) -> Field {
+    match FieldMergeKind::of(&field1, &field2) {
+        FieldMergeKind::Left => field1,
+        FieldMergeKind::Right => field2,
+        FieldMergeKind::Merge | FieldMergeKind::Empty => {
+            let value = match (field1.value, field2.value) {
+                (Some(value1), Some(value2)) => {
+                    Some(merge_report_values(value1, value2, path, conflicts))
+                }
+                (value1, value2) => value1.or(value2),
+            };
+
+            let contributions = field1.contributions() + field2.contributions();
+
+            Field {
+                value,
+                metadata: FieldMetadata {
+                    contributions,
+                    ..FieldMetadata::flatten(field1.metadata, field2.metadata)
+                },
+                pending_contracts: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Combine two leaf values of the same priority for [merge_report], recursing into records.
+fn merge_report_values(
+    value1: RichTerm,
+    value2: RichTerm,
+    path: &MergePath,
+    conflicts: &mut Vec<Conflict>,
+) -> RichTerm {
+    if let (Term::Record(r1), Term::Record(r2)) = (value1.as_ref(), value2.as_ref()) {
+        let record = merge_report_records(r1.clone(), r2.clone(), path, conflicts);
+        return RichTerm::new(Term::Record(record), value1.pos);
+    }
+
+    if value1.clone().without_pos() == value2.clone().without_pos() {
+        return value1;
+    }
+
+    let merge_label = match value1.pos.into_opt().or_else(|| value2.pos.into_opt()) {
+        Some(span) => MergeLabel::new(span),
+        None => MergeLabel::from(Label::default()),
+    };
+
+    conflicts.push(Conflict {
+        path: path.clone(),
+        left: value1.clone(),
+        right: value2.clone(),
+    });
+
+    RichTerm::from(Term::RuntimeError(EvalError::MergeIncompatibleArgs {
+        left_arg: value1,
+        right_arg: value2,
+        merge_label: MergeLabel {
+            path: path.clone(),
+            ..merge_label
+        },
+    }))
+}
+
+/// Return the number of definitions merged into each leaf field of a record, that is each field
+/// whose value isn't a record, together with the path of the field. A field which hasn't been
+/// merged counts as one definition. This lets configuration platforms spot a field which is
+/// defined by an unusually large number of layers.
+///
+/// As for [merge_report], the record must be fully evaluated, for example by
+/// [crate::program::Program::eval_full]. The leaves are listed in the order of the record.
+pub fn field_contributions(record: &RecordData) -> Vec<(Vec<Ident>, usize)> {
+    fn collect(record: &RecordData, path: &MergePath, result: &mut Vec<(Vec<Ident>, usize)>) {
+        for (id, field) in record.fields.iter() {
+            let path = path.push(*id);
+
+            match field.value.as_ref().map(|value| value.as_ref()) {
+                Some(Term::Record(nested)) => collect(nested, &path, result),
+                _ => result.push((path.to_vec(), field.contributions())),
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    collect(record, &MergePath::default(), &mut result);
+    result
+}
+
+/// Expand the fields of a record whose name is a dotted path, such as `"a.b.c" = 1`, into nested
+/// records, as in `a = {b = {c = 1}}`. Records produced from flat key-value data can then be
+/// merged with nested ones, or with each other, by the standard recursive merge.
+///
+/// Fields sharing a prefix are grouped in the same nested record. When a dotted field collides
+/// with a field which is defined directly, as `"a.b" = 1` and `a = {c = 2}`, both definitions are
+/// combined: record literals are combined field by field, and other values are combined by a merge
+/// expression, which is evaluated as any other merge. Record literals nested in the values of the
+/// fields are expanded as well. Names with an empty segment, such as `"a..b"` or `".a"`, aren't
+/// considered as paths and are kept as they are.
+///
+/// The values of the fields are moved around but not modified: they must be evaluated in the
+/// environment of the original record.
+pub fn nest_dotted_fields(record: RecordData) -> RecordData {
+    let RecordData {
+        fields,
+        attrs,
+        sealed_tail,
+    } = record;
+    let mut nested = IndexMap::with_capacity(fields.len());
+
+    for (id, field) in fields {
+        let field = field.map_value(|value| match value.as_ref() {
+            Term::Record(record) => {
+                RichTerm::new(Term::Record(nest_dotted_fields(record.clone())), value.pos)
+            }
+            _ => value,
+        });
+
+        let segments: Vec<&str> = id.label().split('.').collect();
+
+        if segments.len() == 1 || segments.iter().any(|segment| segment.is_empty()) {
+            insert_nested_field(&mut nested, id, field);
+            continue;
+        }
+
+        // Wrap the field in one record for each segment of the path, from the innermost one.
+        let field = segments[1..].iter().rev().fold(field, |field, segment| {
+            let fields = [(Ident::from(*segment), field)].into_iter().collect();
+            Field::from(RichTerm::from(Term::Record(RecordData::new(
+                fields,
+                RecordAttrs::default(),
+                None,
+            ))))
+        });
+
+        insert_nested_field(&mut nested, Ident::from(segments[0]), field);
+    }
+
+    RecordData::new(nested, attrs, sealed_tail)
+}
+
+/// Insert a field in a record being built by [nest_dotted_fields], combining it with the existing
+/// definition of the same field, if any.
+fn insert_nested_field(fields: &mut IndexMap<Ident, Field>, id: Ident, field: Field) {
+    let Some(existing) = fields.get_mut(&id) else {
+        fields.insert(id, field);
+        return;
+    };
+
+    let previous = std::mem::take(existing);
+    let metadata = FieldMetadata::flatten(previous.metadata, field.metadata);
+    let pending_contracts = previous
+        .pending_contracts
+        .into_iter()
+        .chain(field.pending_contracts)
         .collect();
 
-    let metadata = FieldMetadata {
-        doc: merge_doc(metadata1.doc, metadata2.doc),
-        annotation: TypeAnnotation { types, contracts },
-        // If one of the record requires this field, then it musn't be optional. The
-        // resulting field is optional iff both are.
-        opt: metadata1.opt && metadata2.opt,
-        // The resulting field will be suppressed from serialization if either of the fields to be merged is.
-        not_exported: metadata1.not_exported || metadata2.not_exported,
-        priority,
+    let value = match (previous.value, field.value) {
+        (Some(value1), Some(value2)) => Some(match (value1.as_ref(), value2.as_ref()) {
+            (Term::Record(r1), Term::Record(r2)) => {
+                let mut combined = r1.clone();
+
+                for (id, field) in r2.fields.clone() {
+                    insert_nested_field(&mut combined.fields, id, field);
+                }
+
+                RichTerm::new(Term::Record(combined), value1.pos)
+            }
+            _ => {
+                let merge_label = match value1.pos.into_opt().or_else(|| value2.pos.into_opt()) {
+                    Some(span) => MergeLabel::new(span),
+                    None => MergeLabel::from(Label::default()),
+                };
+
+                RichTerm::from(Term::Op2(BinaryOp::Merge(merge_label), value1, value2))
+            }
+        }),
+        (value1, value2) => value1.or(value2),
     };
 
-    Ok(Field {
-        metadata,
+    *existing = Field {
         value,
+        metadata,
         pending_contracts,
-    })
-}
-
-/// Merge two optional documentations.
-fn merge_doc(doc1: Option<String>, doc2: Option<String>) -> Option<String> {
-    //FIXME: how to merge documentation? Just concatenate?
-    doc1.or(doc2)
+    };
 }
 
-/// See [crate::eval::cache::Cache::saturate]. Saturation is a transformation on recursive cache elements
-/// that is used when we must combine different values with different recursive dependencies (say,
-/// the two values of fields being merged) into one expression.
+/// The merge of two evaluated records which can be updated incrementally when one of them changes,
+/// for tooling which re-evaluates a configuration after each edit.
 ///
-/// Saturation is first and foremost a transformation of terms, but like
-/// [crate::transform::Closurizable], it can be applied to other types that contain terms, hence
-/// the trait.
-trait Saturate: Sized {
-    /// Take the content of a record field, and saturate the potential revertible element with the given
-    /// fields. See [crate::eval::cache::Cache::saturate].
-    ///
-    /// If the expression is not a variable referring to an element in the cache
-    ///  (this can happen e.g. for numeric constants), we just return the term as it is, which
-    /// falls into the zero dependencies special case.
-    fn saturate<'a, I: DoubleEndedIterator<Item = &'a Ident> + Clone, C: Cache>(
-        self,
-        cache: &mut C,
-        env: &mut Environment,
-        local_env: &Environment,
-        fields: I,
-    ) -> Result<Self, EvalError>;
+/// Records are merged as done by [merge_report], and the result of merging each top-level field
+/// is kept together with its conflicts. When one of the records is updated, only the fields which
+/// have changed are merged again, while the result of the other fields is reused.
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use nickel_lang_core::{
+/// #     error::MergeSide,
+/// #     eval::merge::IncrementalMerge,
+/// #     identifier::Ident,
+/// #     term::{make as mk_term, record::RecordData, IndexMap},
+/// # };
+/// let record = |fields: &[(&str, i64)]| {
+///     RecordData::with_field_values(
+///         fields
+///             .iter()
+///             .map(|(id, value)| (Ident::from(*id), mk_term::integer(*value)))
+///             .collect::<IndexMap<_, _>>(),
+///     )
+/// };
+///
+/// let mut merge = IncrementalMerge::new(record(&[("a", 1), ("b", 2)]), record(&[("b", 2)]));
+/// assert!(merge.conflicts().is_empty());
+///
+/// // Only `b` is merged again.
+/// merge.update(
+///     MergeSide::Right,
+///     record(&[("b", 3)]),
+///     &HashSet::from([Ident::from("b")]),
+/// );
+/// assert_eq!(merge.conflicts().len(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct IncrementalMerge {
+    /// The left record.
+    left: RecordData,
+    /// The right record.
+    right: RecordData,
+    /// The result of merging each top-level field, together with the conflicts found in this
+    /// field.
+    fields: IndexMap<Ident, (Field, Vec<Conflict>)>,
 }
 
-impl Saturate for RichTerm {
-    fn saturate<'a, I: DoubleEndedIterator<Item = &'a Ident> + Clone, C: Cache>(
-        self,
-        cache: &mut C,
-        env: &mut Environment,
-        local_env: &Environment,
-        fields: I,
-    ) -> Result<RichTerm, EvalError> {
-        if let Term::Var(var_id) = &*self.term {
-            let idx = local_env
-                .get(var_id)
-                .cloned()
-                .ok_or(EvalError::UnboundIdentifier(*var_id, self.pos))?;
+impl IncrementalMerge {
+    /// Merge two evaluated records.
+    pub fn new(left: RecordData, right: RecordData) -> Self {
+        let mut merge = IncrementalMerge {
+            fields: IndexMap::with_capacity(left.fields.len().max(right.fields.len())),
+            left,
+            right,
+        };
 
-            Ok(cache.saturate(idx, env, fields).with_pos(self.pos))
-        } else {
-            Ok(self)
-        }
-    }
-}
+        let all: HashSet<Ident> = merge
+            .left
+            .fields
+            .keys()
+            .chain(merge.right.fields.keys())
+            .copied()
+            .collect();
+        merge.merge_fields(&all);
 
-/// Return the dependencies of a field when represented as a `RichTerm`.
-fn field_deps<C: Cache>(
-    cache: &C,
-    rt: &RichTerm,
-    local_env: &Environment,
-) -> Result<FieldDeps, EvalError> {
-    if let Term::Var(var_id) = &*rt.term {
-        local_env
-            .get(var_id)
-            .map(|idx| cache.deps(idx).unwrap_or_else(FieldDeps::empty))
-            .ok_or(EvalError::UnboundIdentifier(*var_id, rt.pos))
-    } else {
-        Ok(FieldDeps::empty())
+        merge
     }
-}
 
-/// Take the current environment, two fields with their local environment, and return a term which
-/// is the merge of the two fields, closurized in the provided final environment.
-///
-/// The element in the cache allocated for the result is revertible if and only if at least one of
-/// the original elements is (if one of the original values is overridable, then so is the merge of
-/// the two). In this case, the field dependencies are the union of the dependencies of each field.
-///
-/// The fields are saturated (see [saturate]) to properly propagate recursive dependencies down to
-/// `t1` and `t2` in the final, merged record.
-#[allow(clippy::too_many_arguments)]
-fn fields_merge_closurize<'a, I: DoubleEndedIterator<Item = &'a Ident> + Clone, C: Cache>(
-    cache: &mut C,
-    merge_label: MergeLabel,
-    env: &mut Environment,
-    t1: RichTerm,
-    env1: &Environment,
-    t2: RichTerm,
-    env2: &Environment,
-    fields: I,
-) -> Result<RichTerm, EvalError> {
-    let mut local_env = Environment::new();
+    /// Replace one of the records with a new version, and merge again the given fields.
+    ///
+    /// `changed` must contain every top-level field whose definition is different in the new
+    /// record, including the fields which have been added or removed. The other fields are assumed
+    /// to be unchanged, and their previous result is reused as it is.
+    pub fn update(&mut self, side: MergeSide, record: RecordData, changed: &HashSet<Ident>) {
+        match side {
+            MergeSide::Left => self.left = record,
+            MergeSide::Right => self.right = record,
+        }
 
-    let combined_deps = field_deps(cache, &t1, env1)?.union(field_deps(cache, &t2, env2)?);
-    let body = RichTerm::from(Term::Op2(
-        BinaryOp::Merge(merge_label),
-        t1.saturate(cache, &mut local_env, env1, fields.clone())?,
-        t2.saturate(cache, &mut local_env, env2, fields)?,
-    ));
+        self.merge_fields(changed);
+    }
 
-    // We closurize the final result in an element with appropriate dependencies
-    let closure = Closure {
-        body,
-        env: local_env,
-    };
-    let fresh_var = Ident::fresh();
+    /// Merge again the given fields of the current records, and store the results.
+    fn merge_fields(&mut self, ids: &HashSet<Ident>) {
+        let restrict = |record: &RecordData| -> IndexMap<Ident, Field> {
+            ids.iter()
+                .filter_map(|id| Some((*id, record.fields.get(id)?.clone())))
+                .collect()
+        };
 
-    // new_rev takes care of not creating a revertible element in the cache if the dependencies are empty.
-    env.insert(
-        fresh_var,
-        cache.add(
-            closure,
-            IdentKind::Record,
-            BindingType::Revertible(combined_deps),
-        ),
-    );
+        let split::SplitResult {
+            left,
+            center,
+            right,
+        } = split::split(restrict(&self.left), restrict(&self.right));
 
-    Ok(RichTerm::from(Term::Var(fresh_var)))
-}
+        for id in ids {
+            self.fields.remove(id);
+        }
 
-/// Same as [Closurizable], but also revert the element if the term is a variable.
-pub(super) trait RevertClosurize {
-    /// Revert the element at the index inside the term (if any), and closurize the result inside `env`.
-    fn revert_closurize<C: Cache>(
-        self,
-        cache: &mut C,
-        env: &mut Environment,
-        with_env: Environment,
-    ) -> Self;
-}
+        self.fields.extend(
+            left.into_iter()
+                .chain(right)
+                .map(|(id, field)| (id, (field, Vec::new()))),
+        );
 
-impl RevertClosurize for RichTerm {
-    fn revert_closurize<C: Cache>(
-        self,
-        cache: &mut C,
-        env: &mut Environment,
-        with_env: Environment,
-    ) -> RichTerm {
-        if let Term::Var(id) = self.as_ref() {
-            // This create a fresh variable which is bound to a reverted copy of the original element
-            let reverted = cache.revert(with_env.get(id).unwrap());
-            let fresh_id = Ident::fresh();
-            env.insert(fresh_id, reverted);
-            RichTerm::new(Term::Var(fresh_id), self.pos)
-        } else {
-            // Otherwise, if it is not a variable after the share normal form transformations, it
-            // should be a constant and we don't need to revert anything
-            self
+        for (id, (field1, field2)) in center {
+            let mut conflicts = Vec::new();
+            let field = merge_report_fields(
+                field1,
+                field2,
+                &MergePath::default().push(id),
+                &mut conflicts,
+            );
+            self.fields.insert(id, (field, conflicts));
         }
     }
+
+    /// Return the merged record. The fields are in the same order as if the current records were
+    /// merged by [merge_report].
+    pub fn result(&self) -> RecordData {
+        let fields = self
+            .ordered_ids()
+            .map(|id| (id, self.fields[&id].0.clone()))
+            .collect();
+
+        RecordData::new(
+            fields,
+            RecordAttrs::merge_counted(self.left.attrs, self.right.attrs),
+            None,
+        )
+    }
+
+    /// Return the conflicts of the merge, in the same order as [merge_report].
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        self.ordered_ids()
+            .flat_map(|id| self.fields[&id].1.iter().cloned())
+            .collect()
+    }
+
+    /// Iterate over the names of the merged fields, in the order of [merge_report].
+    fn ordered_ids(&self) -> impl Iterator<Item = Ident> {
+        let ids: IndexMap<Ident, ()> = self
+            .left
+            .fields
+            .keys()
+            .chain(self.right.fields.keys())
+            .map(|id| (*id, ()))
+            .collect();
+
+        ids.into_keys()
+    }
 }
 
-impl RevertClosurize for Field {
-    fn revert_closurize<C: Cache>(
-        self,
-        cache: &mut C,
-        env: &mut Environment,
-        with_env: Environment,
-    ) -> Field {
-        let value = self
-            .value
-            .map(|value| value.revert_closurize(cache, env, with_env.clone()));
+/// Debug-only check that standard merging is commutative, enabled by the
+/// `check-merge-commutativity` feature. Each standard merge is also performed with swapped
+/// operands, and the shapes of both results are compared.
+///
+/// Merging is lazy, so the results can't be compared structurally in general: the values of the
+/// fields of a merged record, or of the elements of an array, are fresh cache elements. We thus
+/// compare basic values, the field names of records and the length of arrays, which is enough to
+/// catch a merge arm or a strategy which depends on the order of its operands. The `concat`,
+/// `set`, `left` and `right` strategies are order-dependent by design and are skipped.
+#[cfg(all(debug_assertions, feature = "check-merge-commutativity"))]
+mod commutativity {
+    use super::*;
 
-        let pending_contracts = self
-            .pending_contracts
-            .revert_closurize(cache, env, with_env);
+    /// The shape of the result of a merge.
+    #[derive(Debug, PartialEq)]
+    enum Shape {
+        Error,
+        Value(Term),
+        Record(Vec<Ident>),
+        Array(usize),
+        Other,
+    }
 
-        Field {
-            metadata: self.metadata,
-            value,
-            pending_contracts,
+    impl Shape {
+        fn of(result: &Result<Closure, MergeError>) -> Self {
+            let Ok(closure) = result else {
+                return Shape::Error;
+            };
+
+            match closure.body.as_ref() {
+                t @ (Term::Null | Term::Bool(_) | Term::Num(_) | Term::Str(_) | Term::Enum(_)) => {
+                    Shape::Value(t.clone())
+                }
+                Term::Record(record) | Term::RecRecord(record, ..) => {
+                    let mut fields: Vec<_> = record.fields.keys().cloned().collect();
+                    fields.sort();
+                    Shape::Record(fields)
+                }
+                Term::Array(array, _) => Shape::Array(array.len()),
+                _ => Shape::Other,
+            }
         }
     }
-}
 
-impl RevertClosurize for RuntimeContract {
-    fn revert_closurize<C: Cache>(
-        self,
+    /// Perform the merge with swapped operands, if it's a standard merge whose strategy is meant
+    /// to be commutative. The swapped merge is performed in a copy of the merge context, such
+    /// that it doesn't consume fuel, update the statistics or notify the observer.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn swapped_merge<C: Cache>(
         cache: &mut C,
-        env: &mut Environment,
-        with_env: Environment,
-    ) -> RuntimeContract {
-        self.map_contract(|ctr| ctr.revert_closurize(cache, env, with_env))
+        t1: &RichTerm,
+        env1: &Environment,
+        t2: &RichTerm,
+        env2: &Environment,
+        pos_op: TermPos,
+        mode: &MergeMode,
+        ctx: &MergeContext,
+        call_stack: &CallStack,
+    ) -> Option<Result<Closure, MergeError>> {
+        match mode {
+            MergeMode::Standard(_)
+                if !matches!(
+                    mode.strategy(),
+                    MergeStrategy::Concat { .. }
+                        | MergeStrategy::Set
+                        | MergeStrategy::Left
+                        | MergeStrategy::Right
+                ) =>
+            {
+                let mut ctx = MergeContext {
+                    observer: None,
+                    stats: None,
+                    field_timings: None,
+                    doc_override_warnings: None,
+                    ..ctx.clone()
+                };
+
+                Some(merge_impl(
+                    cache,
+                    t2.clone(),
+                    env2.clone(),
+                    t1.clone(),
+                    env1.clone(),
+                    pos_op,
+                    mode.clone(),
+                    &mut ctx,
+                    &mut call_stack.clone(),
+                    None,
+                ))
+            }
+            _ => None,
+        }
     }
-}
 
-impl RevertClosurize for Vec<RuntimeContract> {
-    fn revert_closurize<C: Cache>(
-        self,
-        cache: &mut C,
-        env: &mut Environment,
-        with_env: Environment,
-    ) -> Vec<RuntimeContract> {
-        self.into_iter()
-            .map(|pending_contract| pending_contract.revert_closurize(cache, env, with_env.clone()))
-            .collect()
+    /// Panic if the results of a merge and of the merge with swapped operands have different
+    /// shapes.
+    pub(super) fn assert_same_shape(
+        result: &Result<Closure, MergeError>,
+        swapped: &Result<Closure, MergeError>,
+    ) {
+        let (shape, swapped_shape) = (Shape::of(result), Shape::of(swapped));
+
+        assert_eq!(
+            shape, swapped_shape,
+            "merge isn't commutative: swapping the operands changed the result"
+        );
     }
 }
 
 pub mod split {
     use crate::term::IndexMap;
+    use std::collections::HashMap;
 
     pub struct SplitResult<K, V1, V2> {
         pub left: IndexMap<K, V1>,
@@ -655,16 +3903,31 @@ pub mod split {
     /// Split two maps m1 and m2 in three parts (left,center,right), where left holds bindings
     /// `(key,value)` where key is not in `m2.keys()`, right is the dual (keys of m2 that are not
     /// in m1), and center holds bindings for keys that are both in m1 and m2.
+    ///
+    /// `left` and `center` follow the order of `m1`, and `right` follows the order of `m2`.
     pub fn split<K, V1, V2>(m1: IndexMap<K, V1>, m2: IndexMap<K, V2>) -> SplitResult<K, V1, V2>
     where
         K: std::hash::Hash + Eq,
     {
-        let mut left = IndexMap::new();
-        let mut center = IndexMap::new();
-        let mut right = m2;
+        // `left` holds at most all the keys of `m1`, and `center` at most the keys common to both
+        // maps. Pre-sizing them avoids repeated reallocations when splitting large records.
+        let mut left = IndexMap::with_capacity(m1.len());
+        let mut center = IndexMap::with_capacity(m1.len().min(m2.len()));
+        let mut right = IndexMap::with_capacity(m2.len());
+        // Removing the common keys from `m2` in place would either shuffle it or be quadratic, so
+        // we set their values aside instead.
+        let mut common = HashMap::with_capacity(m1.len().min(m2.len()));
+
+        for (key, value) in m2 {
+            if m1.contains_key(&key) {
+                common.insert(key, value);
+            } else {
+                right.insert(key, value);
+            }
+        }
 
         for (key, value) in m1 {
-            if let Some(v2) = right.remove(&key) {
+            if let Some(v2) = common.remove(&key) {
                 center.insert(key, (value, v2));
             } else {
                 left.insert(key, value);
@@ -678,6 +3941,69 @@ pub mod split {
         }
     }
 
+    /// Same as [split], but keys are compared after being normalized by `normalize`. A key of `m2`
+    /// which has the same normalized form as a key of `m1` ends up in the center under the key of
+    /// `m1`. The other keys are left untouched.
+    ///
+    /// If two keys of the same map have the same normalized form, it's ambiguous which one should
+    /// be matched: the two keys are returned as an error.
+    pub fn split_normalized<K, N, V1, V2>(
+        m1: IndexMap<K, V1>,
+        m2: IndexMap<K, V2>,
+        normalize: impl Fn(&K) -> N,
+    ) -> Result<SplitResult<K, V1, V2>, (K, K)>
+    where
+        K: std::hash::Hash + Eq + Clone,
+        N: std::hash::Hash + Eq,
+    {
+        let mut normalized2 = HashMap::with_capacity(m2.len());
+
+        for key in m2.keys() {
+            if let Some(prev) = normalized2.insert(normalize(key), key.clone()) {
+                return Err((prev, key.clone()));
+            }
+        }
+
+        let mut normalized1 = HashMap::with_capacity(m1.len());
+
+        for key in m1.keys() {
+            if let Some(prev) = normalized1.insert(normalize(key), key.clone()) {
+                return Err((prev, key.clone()));
+            }
+        }
+
+        let mut left = IndexMap::with_capacity(m1.len());
+        let mut center = IndexMap::with_capacity(m1.len().min(m2.len()));
+        let mut right = IndexMap::with_capacity(m2.len());
+        // The values of `m2` which match a key of `m1`, indexed by the key of `m1`.
+        let mut common = HashMap::with_capacity(m1.len().min(m2.len()));
+
+        for (key, value) in m2 {
+            match normalized1.get(&normalize(&key)) {
+                Some(key1) => {
+                    common.insert(key1.clone(), value);
+                }
+                None => {
+                    right.insert(key, value);
+                }
+            }
+        }
+
+        for (key, value) in m1 {
+            if let Some(v2) = common.remove(&key) {
+                center.insert(key, (value, v2));
+            } else {
+                left.insert(key, value);
+            }
+        }
+
+        Ok(SplitResult {
+            left,
+            center,
+            right,
+        })
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -785,5 +4111,33 @@ pub mod split {
                 ))
             }
         }
+
+        #[test]
+        fn normalized() {
+            let m1: IndexMap<_, _> = [("fooBar", 1), ("left", 1)].into_iter().collect();
+            let m2: IndexMap<_, _> = [("foo_bar", 2), ("right", 2)].into_iter().collect();
+
+            let SplitResult {
+                left,
+                center,
+                right,
+            } = split_normalized(m1, m2, |key| key.to_lowercase().replace('_', "")).unwrap();
+
+            assert_eq!(left.into_iter().collect::<Vec<_>>(), vec![("left", 1)]);
+            assert_eq!(
+                center.into_iter().collect::<Vec<_>>(),
+                vec![("fooBar", (1, 2))]
+            );
+            assert_eq!(right.into_iter().collect::<Vec<_>>(), vec![("right", 2)]);
+        }
+
+        #[test]
+        fn normalized_collision() {
+            let m1: IndexMap<_, _> = [("fooBar", 1), ("foo_bar", 1)].into_iter().collect();
+            let m2 = IndexMap::<&str, isize>::new();
+
+            let result = split_normalized(m1, m2, |key| key.to_lowercase().replace('_', ""));
+            assert!(matches!(result, Err(("fooBar", "foo_bar"))));
+        }
     }
 }
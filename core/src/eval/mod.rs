@@ -30,9 +30,10 @@
 //! - **Index on stack**: If the evaluation of the current term is done, and there is one (or
 //!   several) index on the stack, this means we have to perform an update. Consecutive indices are
 //!   popped from the stack and are updated to point to the current evaluated term.
-//! - **Import**: Import must have been resolved before the evaluation starts. An unresolved import
-//!   causes an [`crate::error::EvalError::InternalError`]. A resolved import, identified by a
-//!   `FileId`, is retrieved from the import resolver and evaluation proceeds.
+//! - **Import**: A resolved import, identified by a `FileId`, is retrieved from the import
+//!   resolver and evaluation proceeds. Imports are usually resolved before the evaluation starts,
+//!   but lazy imports (see [`crate::cache::Cache::set_lazy_imports`]) are only resolved when they
+//!   are evaluated. A failure to do so causes an [`crate::error::EvalError::ImportFailed`].
 //!
 //! ## Contracts
 //!
@@ -80,6 +81,7 @@ use crate::{
     environment::Environment as GenericEnvironment,
     error::{Error, EvalError},
     identifier::Ident,
+    label::MergePath,
     match_sharedterm,
     position::TermPos,
     program::QueryPath,
@@ -87,12 +89,13 @@ use crate::{
         array::ArrayAttrs,
         make as mk_term,
         record::{Field, RecordData},
-        BinaryOp, BindingType, LetAttrs, RichTerm, RuntimeContract, StrChunk, Term, UnaryOp,
+        BinaryOp, BindingType, IndexMap, LetAttrs, RichTerm, RuntimeContract, StrChunk, Term,
+        UnaryOp,
     },
     transform::Closurizable,
 };
 
-use std::io::Write;
+use std::{collections::HashMap, io::Write, path::PathBuf};
 
 pub mod cache;
 pub mod callstack;
@@ -114,6 +117,118 @@ impl AsRef<Vec<StackElem>> for CallStack {
     }
 }
 
+/// The configuration of the evaluator.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EvalConfig {
+    /// The maximum number of record fields that merging is allowed to process during one
+    /// evaluation, or `None` if merging is unbounded (the default). Exhausting this budget aborts
+    /// the evaluation with [`EvalError::MergeFuelExhausted`]. This is useful to bound the work
+    /// done when evaluating untrusted programs.
+    pub merge_fuel: Option<usize>,
+    /// The note appended to the error reported when merging two unequal arrays. Defaults to a
+    /// note explaining that the equality contract was generated by the merge. Embedders rendering
+    /// their own error messages may replace or disable it.
+    pub array_merge_note: merge::ArrayMergeNote,
+    /// Whether to collect statistics about the merges performed during evaluation (disabled by
+    /// default). See [`merge::MergeStats`] and [`VirtualMachine::merge_stats`].
+    pub collect_merge_stats: bool,
+    /// Whether to measure the time spent merging each top-level field (disabled by default). The
+    /// time spent on nested fields, including the merges of nested records performed lazily
+    /// later on, is attributed to the top-level field they belong to. This is meant to find the
+    /// expensive parts of a slow merge. See [`VirtualMachine::field_timings`].
+    pub collect_field_timings: bool,
+    /// A token which can be set to cancel the evaluation, checked by merging. Cancelling the
+    /// evaluation aborts it with [`EvalError::Cancelled`].
+    pub cancellation: Option<merge::CancellationToken>,
+    /// Whether the error reported when merging two unequal arrays shows the content of the left
+    /// array (disabled by default). The array has to be substituted at each array merge, which
+    /// trades a bit of performance for better diagnostics. Long arrays are truncated.
+    pub display_merged_arrays: bool,
+    /// A normalization of field names under which the fields of merged records are matched, for
+    /// example to merge `fooBar` with `foo_bar` (disabled by default). See
+    /// [`merge::KeyNormalization`].
+    pub key_normalization: Option<merge::KeyNormalization>,
+    /// Whether merging two fields which both have a contract of an enum type without a tail, such
+    /// as `[| 'A, 'B |]`, combines them into one contract accepting the tags of either side
+    /// (disabled by default). Otherwise, the merged value must satisfy both contracts.
+    pub union_enum_contracts: bool,
+    /// How to merge two different numbers, strings, booleans or enum tags of the same priority
+    /// (by default, the merge fails). See [`merge::ConflictPolicy`].
+    pub conflict_policy: merge::ConflictPolicy,
+    /// Whether merging forces the value of each field of the merged record, with its pending
+    /// contracts applied (disabled by default). Contract failures are then reported as soon as
+    /// records are merged, instead of when the failing fields are used. This is meant for
+    /// validation, which wants to check every contract without forcing the whole configuration
+    /// by hand.
+    pub strict_contracts: bool,
+    /// Whether to collect a warning each time the documentation of a field is dropped when
+    /// merging definitions of different priorities (disabled by default). See
+    /// [`merge::DocOverrideWarning`] and [`VirtualMachine::doc_override_warnings`].
+    pub warn_overridden_docs: bool,
+    /// Whether two different values of the same priority which can't be combined are tolerated
+    /// when the field is optional on both sides, the right value winning (disabled by default).
+    /// This only makes a difference when [`EvalConfig::conflict_policy`] is
+    /// [`merge::ConflictPolicy::Error`]: other conflicts are still reported.
+    pub lenient_optional_conflicts: bool,
+    /// The merge functions defined by the host application, by name. See
+    /// [`EvalConfig::register_merge_fn`].
+    pub merge_fns: HashMap<String, merge::MergeFn>,
+    /// Whether merging two records which both declare a required field, without either of them
+    /// giving it a value, fails right away with [`EvalError::MergeMissingFieldDef`] (disabled by
+    /// default). Otherwise, the missing value is only reported when the field is used, which may
+    /// be far from the merge. This is meant for merging complete configurations, where each
+    /// required field should have been given a value by one of the layers.
+    pub strict_required_fields: bool,
+    /// Whether merging skips reverting the fields which don't depend on any other field of their
+    /// record (disabled by default). Such fields can't be affected by an override, so reverting
+    /// them is pure overhead, which is significant when merging large flat records such as
+    /// generated data. They are re-closurized as they are instead.
+    pub skip_flat_reverts: bool,
+    /// Whether merging checks that the contracts accumulated on each field merged from both sides
+    /// can hold together (disabled by default). Two contracts which can never both hold, such as
+    /// `Number` and `String`, are then reported right away with
+    /// [`EvalError::MergeContradictoryContracts`], instead of when the field is used. The check is
+    /// conservative: only contracts of different primitive types are considered contradictory.
+    pub check_contradictory_contracts: bool,
+    /// Whether merging flags the fields only defined in the right operand (disabled by default).
+    /// Each field of a merged record then records whether it was added by the right operand, as
+    /// [`crate::term::record::FieldMetadata::introduced`], which is reported by metadata queries.
+    /// This makes it cheap to find the fields added by the latest overlay of a configuration.
+    /// Applying a record contract doesn't add any field, and leaves the flags untouched.
+    pub track_introduced_fields: bool,
+    /// How record contracts handle the fields they declare but which the checked value doesn't
+    /// define (lenient by default). In strict mode, every declared field without a default value,
+    /// including optional fields, must be defined by the value. See [`merge::AbsentFields`].
+    pub absent_fields: merge::AbsentFields,
+    /// The maximum number of fields of a record produced by merging, or `None` if the size of
+    /// merged records is unbounded (the default). Each record merge, including the merges of
+    /// nested records, aborts with [`EvalError::RecordTooLarge`] before building a record with
+    /// more fields. Unlike [`EvalConfig::merge_fuel`], which bounds the total work of an
+    /// evaluation, this bounds the size of each record.
+    pub max_fields: Option<usize>,
+    /// Whether record contracts match the fields of the checked value case-insensitively
+    /// (disabled by default). A field such as `username` is then checked by the contract field
+    /// `userName`, and is renamed to `userName` in the result. Two fields of the value matching
+    /// the same contract field abort the evaluation with [`EvalError::MergeKeyCollision`]. This
+    /// is meant for data coming from case-insensitive sources.
+    pub case_insensitive_contracts: bool,
+}
+
+impl EvalConfig {
+    /// Register a merge function under the given name, replacing any function previously
+    /// registered under the same name. The values of the fields annotated with `merge "name"` are
+    /// then merged by this function instead of the standard merge, which lets host applications
+    /// define domain-specific merges. See [`merge::MergeFn`].
+    pub fn register_merge_fn(
+        &mut self,
+        name: impl Into<String>,
+        merge_fn: impl Fn(&RichTerm, &RichTerm) -> Result<RichTerm, String> + Send + Sync + 'static,
+    ) {
+        self.merge_fns
+            .insert(name.into(), merge::MergeFn::new(merge_fn));
+    }
+}
+
 // The current state of the Nickel virtual machine.
 pub struct VirtualMachine<R: ImportResolver, C: Cache> {
     // The main stack, storing arguments, cache indices and pending computations.
@@ -126,6 +241,10 @@ pub struct VirtualMachine<R: ImportResolver, C: Cache> {
     pub cache: C,
     // The stream for writing trace output.
     trace: Box<dyn Write>,
+    // The configuration of the evaluator.
+    config: EvalConfig,
+    // The state of merging for the current evaluation.
+    merge_ctx: merge::MergeContext,
 }
 
 impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
@@ -136,6 +255,8 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
             stack: Stack::new(),
             cache: Cache::new(),
             trace: Box::new(trace),
+            config: Default::default(),
+            merge_ctx: Default::default(),
         }
     }
 
@@ -146,6 +267,8 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
             stack: Stack::new(),
             cache,
             trace: Box::new(trace),
+            config: Default::default(),
+            merge_ctx: Default::default(),
         }
     }
 
@@ -154,6 +277,54 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
     pub fn reset(&mut self) {
         self.call_stack.0.clear();
         self.stack.reset(&mut self.cache);
+        self.merge_ctx.reset(&self.config);
+    }
+
+    pub fn config(&self) -> &EvalConfig {
+        &self.config
+    }
+
+    /// Set the configuration of the evaluator. The new configuration takes effect immediately,
+    /// resetting the merge budget.
+    pub fn set_config(&mut self, config: EvalConfig) {
+        self.merge_ctx.reset(&config);
+        self.config = config;
+    }
+
+    /// Set an observer notified of each field merged during evaluation, or remove it if `None`.
+    /// See [merge::MergeObserver].
+    pub fn set_merge_observer(
+        &mut self,
+        observer: Option<std::rc::Rc<std::cell::RefCell<dyn merge::MergeObserver>>>,
+    ) {
+        self.merge_ctx.observer = observer;
+    }
+
+    /// Return the statistics about the merges performed since the last reset, or `None` if they
+    /// aren't collected. See [EvalConfig::collect_merge_stats].
+    pub fn merge_stats(&self) -> Option<merge::MergeStats> {
+        self.merge_ctx.stats
+    }
+
+    /// Return the time spent merging each top-level field since the last reset, in the order in
+    /// which the fields were first merged. Empty if it isn't collected. See
+    /// [EvalConfig::collect_field_timings].
+    pub fn field_timings(&self) -> Vec<(Ident, std::time::Duration)> {
+        self.merge_ctx
+            .field_timings
+            .iter()
+            .flatten()
+            .map(|(id, duration)| (*id, *duration))
+            .collect()
+    }
+
+    /// Return the documentations dropped by the merges performed since the last reset. Empty if
+    /// they aren't collected. See [EvalConfig::warn_overridden_docs].
+    pub fn doc_override_warnings(&self) -> &[merge::DocOverrideWarning] {
+        self.merge_ctx
+            .doc_override_warnings
+            .as_deref()
+            .unwrap_or_default()
     }
 
     pub fn import_resolver(&self) -> &R {
@@ -213,7 +384,28 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
             },
             rt,
         );
-        self.eval_closure(Closure::atomic_closure(wrapper), initial_env)
+
+        self.merge_ctx.for_export = for_export;
+        let result = self.eval_closure(Closure::atomic_closure(wrapper), initial_env);
+        self.merge_ctx.for_export = false;
+
+        result
+    }
+
+    /// Evaluate a closure to a weak head normal form like [VirtualMachine::eval_closure], as part
+    /// of an export: the fields which aren't exported are then merged in a cheaper way (see
+    /// [merge::MergeContext::for_export]). This is used to evaluate the result of a program piece
+    /// by piece while serializing it, see [crate::serialize::stream].
+    pub fn eval_closure_for_export(
+        &mut self,
+        closure: Closure,
+        initial_env: &Environment,
+    ) -> Result<(RichTerm, Environment), EvalError> {
+        self.merge_ctx.for_export = true;
+        let result = self.eval_closure(closure, initial_env);
+        self.merge_ctx.for_export = false;
+
+        result
     }
 
     /// Query the value and the metadata of a record field in an expression.
@@ -311,6 +503,156 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
         Ok(field)
     }
 
+    /// Compute the value of the field at `path` in an expression, typically a merge, for display,
+    /// without committing anything to the state of the machine. This is meant for interactive
+    /// tooling, such as an editor showing the merged value of a field on hover.
+    ///
+    /// The value is fully evaluated and its variables are substituted, as by
+    /// [VirtualMachine::eval_full]. The thunks of `initial_env`, and the ones reachable from them,
+    /// are shared with the other evaluations of the machine: the preview evaluates independent
+    /// copies instead (see [cache::lazy::ThunkCopier]), such that the thunks it forces, and the
+    /// ones it leaves black-holed when failing, are dropped afterwards. The evaluation also runs
+    /// on a scratch copy of the cache, with a fresh stack and merge context, which are dropped
+    /// together with the merge budget and statistics used by the preview. The merge observer
+    /// isn't notified either. The state of the machine is left untouched, even if the evaluation
+    /// fails.
+    ///
+    /// Copying the environment takes time proportional to the number of thunks reachable from
+    /// it, such as the ones of the standard library, which is paid by each preview.
+    pub fn merge_preview(
+        &mut self,
+        t: RichTerm,
+        path: QueryPath,
+        initial_env: &Environment,
+    ) -> Result<RichTerm, EvalError> {
+        let t = path
+            .0
+            .into_iter()
+            .fold(t, |t, id| mk_term::op1(UnaryOp::StaticAccess(id), t));
+
+        let initial_env = cache::lazy::ThunkCopier::new().copy_env(initial_env);
+
+        let scratch = self.cache.clone();
+        let cache = std::mem::replace(&mut self.cache, scratch);
+        let merge_ctx =
+            std::mem::replace(&mut self.merge_ctx, merge::MergeContext::new(&self.config));
+        let stack = std::mem::replace(&mut self.stack, Stack::new());
+        let call_stack = std::mem::take(&mut self.call_stack);
+
+        let result = self.eval_full(t, &initial_env);
+
+        self.cache = cache;
+        self.merge_ctx = merge_ctx;
+        self.stack = stack;
+        self.call_stack = call_stack;
+
+        result
+    }
+
+    /// Determine if two expressions evaluating to records would merge without conflict, without
+    /// performing the merge. This is meant for tooling, which wants to warn that two layers of a
+    /// configuration conflict before running a full evaluation.
+    ///
+    /// The fields are partitioned as by [merge::split::split], and only the fields defined on
+    /// both sides by values of the same priority are evaluated, to the extent needed to compare
+    /// them: records are compared field by field, and other values are compared if they're
+    /// constants, while functions never merge. The values are evaluated in the environment of their original
+    /// record, without taking the overrides of the merge into account, and merge strategies and
+    /// contracts are ignored. The result is thus an approximation, which is exact for
+    /// configurations without recursive references between overridden fields.
+    pub fn merge_compatibility(
+        &mut self,
+        t1: RichTerm,
+        t2: RichTerm,
+        initial_env: &Environment,
+    ) -> Result<merge::MergeCompatibility, EvalError> {
+        let r1 = self.eval_closure(Closure::atomic_closure(t1), initial_env)?;
+        let r2 = self.eval_closure(Closure::atomic_closure(t2), initial_env)?;
+        let mut conflicts = Vec::new();
+
+        if let Some(path) =
+            self.values_compatibility(r1, r2, &MergePath::default(), initial_env, &mut conflicts)?
+        {
+            return Ok(merge::MergeCompatibility::SealedTail(path));
+        }
+
+        if conflicts.is_empty() {
+            Ok(merge::MergeCompatibility::Clean)
+        } else {
+            Ok(merge::MergeCompatibility::Conflicts(conflicts))
+        }
+    }
+
+    /// Compare two evaluated values located at `path` for [Self::merge_compatibility], recording
+    /// the paths of the conflicting fields in `conflicts`. Return the path of the first record
+    /// with a sealed tail, if any.
+    fn values_compatibility(
+        &mut self,
+        (value1, env1): (RichTerm, Environment),
+        (value2, env2): (RichTerm, Environment),
+        path: &MergePath,
+        initial_env: &Environment,
+        conflicts: &mut Vec<MergePath>,
+    ) -> Result<Option<MergePath>, EvalError> {
+        let (Term::Record(r1), Term::Record(r2)) = (value1.as_ref(), value2.as_ref()) else {
+            if merge::values_conflict(
+                self.merge_ctx.conflict_policy,
+                value1.as_ref(),
+                value2.as_ref(),
+            ) {
+                conflicts.push(path.clone());
+            }
+
+            return Ok(None);
+        };
+
+        if r1.sealed_tail.is_some() || r2.sealed_tail.is_some() {
+            return Ok(Some(path.clone()));
+        }
+
+        let merge::split::SplitResult { center, .. } = merge::split::split(
+            r1.fields.iter().collect::<IndexMap<_, _>>(),
+            r2.fields.iter().collect::<IndexMap<_, _>>(),
+        );
+
+        for (id, (field1, field2)) in center {
+            let (Some(value1), Some(value2)) = (&field1.value, &field2.value) else {
+                continue;
+            };
+
+            if merge::FieldMergeKind::of(field1, field2) != merge::FieldMergeKind::Merge {
+                continue;
+            }
+
+            let evaluated1 = self.eval_closure(
+                Closure {
+                    body: value1.clone(),
+                    env: env1.clone(),
+                },
+                initial_env,
+            )?;
+            let evaluated2 = self.eval_closure(
+                Closure {
+                    body: value2.clone(),
+                    env: env2.clone(),
+                },
+                initial_env,
+            )?;
+
+            if let Some(sealed) = self.values_compatibility(
+                evaluated1,
+                evaluated2,
+                &path.push(*id),
+                initial_env,
+                conflicts,
+            )? {
+                return Ok(Some(sealed));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// The main loop of evaluation.
     ///
     /// Implement the evaluation loop of the core language. The specific implementations of
@@ -638,11 +980,25 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
                         ));
                     }
                 }
+                // Imports are only left unresolved until evaluation when they are lazy. See
+                // [crate::cache::Cache::set_lazy_imports].
                 Term::Import(path) => {
-                    return Err(EvalError::InternalError(
-                        format!("Unresolved import ({})", path.to_string_lossy()),
-                        pos,
-                    ))
+                    let parent = pos
+                        .as_opt_ref()
+                        .map(|span| PathBuf::from(self.import_resolver.get_path(span.src_id)));
+                    let file_id = self
+                        .import_resolver
+                        .resolve_lazily(path, parent, &pos)
+                        .map_err(|err| EvalError::ImportFailed(Box::new(err)))?;
+
+                    if let Some(t) = self.import_resolver.get(file_id) {
+                        Closure::atomic_closure(t)
+                    } else {
+                        return Err(EvalError::InternalError(
+                            format!("Resolved import not found ({file_id:?})"),
+                            pos,
+                        ));
+                    }
                 }
                 // Closurize the array if it's not already done.
                 // This *should* make it unnecessary to call closurize in [operation].
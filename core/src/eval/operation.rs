@@ -1102,6 +1102,19 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
                     Err(mk_type_error!("record_empty_with_tail", "Record"))
                 }
             },
+            UnaryOp::RecordFreeze() => match_sharedterm! { t,
+                with {
+                    Term::Record(mut r) => {
+                        r.attrs.frozen = true;
+                        Ok(Closure {
+                            body: RichTerm::new(Term::Record(r), pos),
+                            env
+                        })
+                    },
+                } else {
+                    Err(mk_type_error!("record_freeze", "Record"))
+                }
+            },
             UnaryOp::Trace() => {
                 if let Term::Str(s) = &*t {
                     let _ = writeln!(self.trace, "std.trace: {s}");
@@ -1707,6 +1720,44 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
                     Err(mk_type_error!("has_field", "String", 1, t1, pos1))
                 }
             },
+            BinaryOp::RecordCommonFields() => match (&*t1, &*t2) {
+                (Term::Record(r1), Term::Record(r2)) => {
+                    // Ignore optional fields without definitions.
+                    let defined = |record: &RecordData| {
+                        record
+                            .fields
+                            .iter()
+                            .filter(|(_, field)| !field.is_empty_optional())
+                            .collect::<IndexMap<_, _>>()
+                    };
+
+                    let merge::split::SplitResult { center, .. } =
+                        merge::split::split(defined(r1), defined(r2));
+                    let mut fields: Vec<String> =
+                        center.into_keys().map(Ident::to_string).collect();
+                    fields.sort();
+                    let terms = fields.into_iter().map(mk_term::string).collect();
+
+                    Ok(Closure::atomic_closure(RichTerm::new(
+                        Term::Array(terms, ArrayAttrs::new().closurized()),
+                        pos_op_inh,
+                    )))
+                }
+                (Term::Record(_), _) => Err(mk_type_error!(
+                    "record_common_fields",
+                    "Record",
+                    2,
+                    t2,
+                    pos2
+                )),
+                (_, _) => Err(mk_type_error!(
+                    "record_common_fields",
+                    "Record",
+                    1,
+                    t1,
+                    pos1
+                )),
+            },
             BinaryOp::ArrayConcat() => match_sharedterm! {t1,
                 with {
                     Term::Array(ts1, attrs1) => match_sharedterm! {t2,
@@ -1803,7 +1854,34 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
                 (Term::Array(..), _) => Err(mk_type_error!("elem_at", "Number", 2, t2, pos2)),
                 (_, _) => Err(mk_type_error!("elem_at", "Array", 1, t1, pos1)),
             },
-            BinaryOp::Merge(merge_label) => merge::merge(
+            BinaryOp::Merge(merge_label) => {
+                // The result of a merge which is directly forced by a full evaluation is the final
+                // result, which can't be merged again.
+                self.merge_ctx.terminal = self.stack.is_top_level_force();
+
+                let result = merge::merge(
+                    &mut self.cache,
+                    RichTerm {
+                        term: t1,
+                        pos: pos1,
+                    },
+                    env1,
+                    RichTerm {
+                        term: t2,
+                        pos: pos2,
+                    },
+                    env2,
+                    pos_op,
+                    MergeMode::Standard(merge_label),
+                    &mut self.merge_ctx,
+                    &mut self.call_stack,
+                )
+                .map_err(EvalError::from);
+
+                self.merge_ctx.terminal = false;
+                result
+            }
+            BinaryOp::MergeFillDefaults(merge_label) => merge::merge(
                 &mut self.cache,
                 RichTerm {
                     term: t1,
@@ -1816,9 +1894,29 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
                 },
                 env2,
                 pos_op,
-                MergeMode::Standard(merge_label),
+                MergeMode::FillDefaults(merge_label),
+                &mut self.merge_ctx,
                 &mut self.call_stack,
-            ),
+            )
+            .map_err(EvalError::from),
+            BinaryOp::MergeLeftBiased(merge_label) => merge::merge(
+                &mut self.cache,
+                RichTerm {
+                    term: t1,
+                    pos: pos1,
+                },
+                env1,
+                RichTerm {
+                    term: t2,
+                    pos: pos2,
+                },
+                env2,
+                pos_op,
+                MergeMode::LeftBiased(merge_label),
+                &mut self.merge_ctx,
+                &mut self.call_stack,
+            )
+            .map_err(EvalError::from),
             BinaryOp::Hash() => {
                 let mk_err_fst = |t1| {
                     Err(mk_type_error!(
@@ -2347,9 +2445,11 @@ impl<R: ImportResolver, C: Cache> VirtualMachine<R, C> {
                                 },
                                 env3,
                                 pos_op,
-                                MergeMode::Contract(lbl),
+                                MergeMode::Contract(lbl, self.merge_ctx.absent_fields),
+                                &mut self.merge_ctx,
                                 &mut self.call_stack
                             )
+                            .map_err(EvalError::from)
                         }
                     } else {
                         Err(EvalError::InternalError(format!("The MergeContract() operator was expecting a first argument of type Label, got {}", t1.type_of().unwrap_or_else(|| String::from("<unevaluated>"))), pos_op))
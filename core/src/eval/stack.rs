@@ -5,7 +5,7 @@ use super::cache::{Cache, CacheIndex};
 use super::operation::OperationCont;
 use crate::eval::{Closure, Environment, IdentKind};
 use crate::position::TermPos;
-use crate::term::{BindingType, RichTerm, StrChunk};
+use crate::term::{BindingType, RichTerm, StrChunk, UnaryOp};
 
 pub struct StrAccData {
     /// The accumulator.
@@ -241,6 +241,20 @@ impl<C: Cache> Stack<C> {
         }
     }
 
+    /// Determine if the stack only holds the continuation of a [UnaryOp::Force], as when evaluating
+    /// the argument of the top-level `Force` introduced by a full evaluation (see
+    /// [super::VirtualMachine::eval_full]). The value being evaluated is then the final result of
+    /// the evaluation, and isn't shared with any other term.
+    pub fn is_top_level_force(&self) -> bool {
+        matches!(
+            self.0.as_slice(),
+            [Marker::Cont(
+                OperationCont::Op1(UnaryOp::Force { .. }, _),
+                ..
+            )]
+        )
+    }
+
     pub fn peek_op_cont(&self) -> Option<OperationCont> {
         let mut it = self
             .0
@@ -441,4 +455,30 @@ mod tests {
         );
         assert_eq!(1, s.count_conts());
     }
+
+    #[test]
+    fn top_level_force() {
+        let force = || {
+            OperationCont::Op1(
+                UnaryOp::Force {
+                    ignore_not_exported: false,
+                },
+                TermPos::None,
+            )
+        };
+
+        let mut s = Stack::<CacheImpl>::new();
+        assert!(!s.is_top_level_force());
+
+        s.push_op_cont(force(), 0, TermPos::None);
+        assert!(s.is_top_level_force());
+
+        s.push_op_cont(some_cont(), 0, TermPos::None);
+        assert!(!s.is_top_level_force());
+
+        let mut s = Stack::<CacheImpl>::new();
+        s.push_op_cont(force(), 0, TermPos::None);
+        s.push_arg(some_closure(), TermPos::None);
+        assert!(!s.is_top_level_force());
+    }
 }
@@ -338,3 +338,726 @@ fn substitution() {
         parse("match {'x => [1, 1], 'y => (if false then 1 else \"Glob2\"), 'z => {id = true, other = false}} true").unwrap()
     );
 }
+
+#[test]
+fn analyze_merge() {
+    use merge::{FieldMergeKind, MergeAnalysis};
+
+    fn record(s: &str) -> RecordData {
+        match parse(s).unwrap().term.into_owned() {
+            Term::Record(data) | Term::RecRecord(data, ..) => data,
+            t => panic!("expected a record, got {t:?}"),
+        }
+    }
+
+    let r1 = record("{a = 1, b = 2, c | default = 3, d = 4, e | Number}");
+    let r2 = record("{b = 2, c = 3, d | default = 4, e | optional, f = 5}");
+
+    assert_eq!(
+        merge::analyze_merge(&r1, &r2),
+        MergeAnalysis {
+            left: vec![Ident::from("a")],
+            center: [
+                ("b", FieldMergeKind::Merge),
+                ("c", FieldMergeKind::Right),
+                ("d", FieldMergeKind::Left),
+                ("e", FieldMergeKind::Empty),
+            ]
+            .into_iter()
+            .map(|(id, kind)| (Ident::from(id), kind))
+            .collect(),
+            right: vec![Ident::from("f")],
+        }
+    );
+}
+
+#[test]
+fn prefix_fields() {
+    use crate::term::MergePriority;
+    use assert_matches::assert_matches;
+    use merge::MergeAnalysis;
+
+    fn record(s: &str) -> RecordData {
+        match parse(s).unwrap().term.into_owned() {
+            Term::Record(data) | Term::RecRecord(data, ..) => data,
+            t => panic!("expected a record, got {t:?}"),
+        }
+    }
+
+    let r1 = record("{a = 1, b = 2}");
+    let r2 = record("{a | doc \"left alone\" | default = 3, b | Number | priority 5 = 4}")
+        .prefix_fields("ns_");
+
+    // Prefixed fields don't collide with the original ones
+    assert_eq!(
+        merge::analyze_merge(&r1, &r2),
+        MergeAnalysis {
+            left: vec![Ident::from("a"), Ident::from("b")],
+            center: Default::default(),
+            right: vec![Ident::from("ns_a"), Ident::from("ns_b")],
+        }
+    );
+
+    // Metadata and values are preserved
+    let ns_a = &r2.fields[&Ident::from("ns_a")];
+    assert_eq!(ns_a.metadata.doc.as_deref(), Some("left alone"));
+    assert_eq!(ns_a.metadata.priority, MergePriority::Bottom);
+
+    let ns_b = &r2.fields[&Ident::from("ns_b")];
+    assert_eq!(
+        ns_b.metadata.priority,
+        MergePriority::Numeral(Number::from(5))
+    );
+    assert_eq!(ns_b.metadata.annotation.contracts.len(), 1);
+    assert_matches!(
+        ns_b.value.as_ref().map(|value| value.as_ref()),
+        Some(Term::Num(n)) if *n == Number::from(4)
+    );
+}
+
+#[test]
+fn merge_lenient_records_field_errors() {
+    use crate::label::MergeLabel;
+    use crate::term::IndexMap;
+    use assert_matches::assert_matches;
+    use merge::{MergeContext, MergeMode};
+
+    fn record(fields: &[(&str, i64)]) -> RichTerm {
+        let fields: IndexMap<Ident, RichTerm> = fields
+            .iter()
+            .map(|(id, value)| (Ident::from(*id), mk_term::integer(*value)))
+            .collect();
+        Term::Record(RecordData::with_field_values(fields)).into()
+    }
+
+    // Each field merged consumes one unit of fuel: with a budget of one, merging the second field
+    // fails.
+    let config = EvalConfig {
+        merge_fuel: Some(1),
+        ..Default::default()
+    };
+    let merge_lenient = |cache: &mut CacheImpl| {
+        merge::merge_lenient(
+            cache,
+            record(&[("a", 1), ("b", 2)]),
+            Environment::new(),
+            record(&[("a", 1), ("b", 2)]),
+            Environment::new(),
+            TermPos::None,
+            MergeMode::Standard(MergeLabel::from(Label::dummy())),
+            &mut MergeContext::new(&config),
+            &mut CallStack::new(),
+        )
+    };
+
+    let (closure, errors) = merge_lenient(&mut CacheImpl::new()).unwrap();
+    assert_matches!(errors.as_slice(), [EvalError::MergeFuelExhausted { .. }]);
+
+    match closure.body.as_ref() {
+        Term::RecRecord(data, ..) => {
+            assert!(data.fields[&Ident::from("a")].value.is_some());
+            assert_matches!(
+                data.fields[&Ident::from("b")]
+                    .value
+                    .as_ref()
+                    .map(|value| &*value.term),
+                Some(Term::RuntimeError(EvalError::MergeFuelExhausted { .. }))
+            );
+        }
+        t => panic!("expected a record, got {t:?}"),
+    }
+
+    // The standard merge aborts on the first error.
+    assert!(merge::merge(
+        &mut CacheImpl::new(),
+        record(&[("a", 1), ("b", 2)]),
+        Environment::new(),
+        record(&[("a", 1), ("b", 2)]),
+        Environment::new(),
+        TermPos::None,
+        MergeMode::Standard(MergeLabel::from(Label::dummy())),
+        &mut MergeContext::new(&config),
+        &mut CallStack::new(),
+    )
+    .is_err());
+}
+
+#[test]
+fn merge_lenient_ignores_parse_errors() {
+    use crate::error::ParseError;
+    use crate::label::MergeLabel;
+    use crate::term::IndexMap;
+    use assert_matches::assert_matches;
+    use merge::{MergeContext, MergeMode};
+
+    fn parse_error() -> RichTerm {
+        Term::ParseError(ParseError::ExternalFormatError(
+            String::from("nickel"),
+            String::from("unexpected end of input"),
+            None,
+        ))
+        .into()
+    }
+
+    fn record(fields: Vec<(&str, RichTerm)>) -> RichTerm {
+        let fields: IndexMap<Ident, RichTerm> = fields
+            .into_iter()
+            .map(|(id, value)| (Ident::from(id), value))
+            .collect();
+        Term::Record(RecordData::with_field_values(fields)).into()
+    }
+
+    let merge_lenient = |cache: &mut CacheImpl, t1: RichTerm, t2: RichTerm| {
+        merge::merge_lenient(
+            cache,
+            t1,
+            Environment::new(),
+            t2,
+            Environment::new(),
+            TermPos::None,
+            MergeMode::Standard(MergeLabel::from(Label::dummy())),
+            &mut MergeContext::default(),
+            &mut CallStack::new(),
+        )
+    };
+
+    let mut cache = CacheImpl::new();
+    let (closure, errors) = merge_lenient(
+        &mut cache,
+        record(vec![("a", parse_error()), ("b", mk_term::integer(1))]),
+        record(vec![("a", mk_term::integer(2)), ("b", parse_error())]),
+    )
+    .unwrap();
+    assert!(errors.is_empty());
+
+    match closure.body.as_ref() {
+        Term::RecRecord(data, ..) => {
+            // The fields which aren't placeholders are kept as they are, and aren't merged.
+            for (field, value) in [("a", 2), ("b", 1)] {
+                let body = match data.fields[&Ident::from(field)]
+                    .value
+                    .as_ref()
+                    .map(|value| &*value.term)
+                {
+                    Some(Term::Var(id)) => cache.get(closure.env.get(id).unwrap().clone()).body,
+                    t => panic!("expected a closurized value, got {t:?}"),
+                };
+                assert_matches!(body.as_ref(), Term::Num(n) if *n == Number::from(value));
+            }
+        }
+        t => panic!("expected a record, got {t:?}"),
+    }
+
+    // A placeholder merged with a whole value yields this value.
+    let (closure, _) = merge_lenient(
+        &mut CacheImpl::new(),
+        parse_error(),
+        record(vec![("a", mk_term::integer(1))]),
+    )
+    .unwrap();
+    assert_matches!(closure.body.as_ref(), Term::Record(..));
+
+    // The standard merge doesn't tolerate placeholders.
+    assert!(merge::merge(
+        &mut CacheImpl::new(),
+        parse_error(),
+        Environment::new(),
+        record(vec![("a", mk_term::integer(1))]),
+        Environment::new(),
+        TermPos::None,
+        MergeMode::Standard(MergeLabel::from(Label::dummy())),
+        &mut MergeContext::default(),
+        &mut CallStack::new(),
+    )
+    .is_err());
+}
+
+#[test]
+fn merge_annotated_record() {
+    use crate::label::MergeLabel;
+    use crate::term::{IndexMap, LabeledType, TypeAnnotation};
+    use crate::types::{TypeF, Types};
+    use assert_matches::assert_matches;
+    use merge::{MergeContext, MergeMode};
+
+    fn record(id: &str, value: i64) -> RichTerm {
+        let fields: IndexMap<Ident, RichTerm> = [(Ident::from(id), mk_term::integer(value))]
+            .into_iter()
+            .collect();
+        Term::Record(RecordData::with_field_values(fields)).into()
+    }
+
+    let contract = LabeledType {
+        types: Types::from(TypeF::Flat(mk_fun!("l", "x", mk_term::var("x")))),
+        label: Label::dummy(),
+    };
+    let annotated = RichTerm::from(Term::Annotated(
+        TypeAnnotation {
+            types: None,
+            contracts: vec![contract.clone()],
+        },
+        record("a", 1),
+    ));
+
+    for (t1, t2) in [
+        (annotated.clone(), record("b", 2)),
+        (record("b", 2), annotated.clone()),
+    ] {
+        let mut cache = CacheImpl::new();
+        let closure = merge::merge(
+            &mut cache,
+            t1,
+            Environment::new(),
+            t2,
+            Environment::new(),
+            TermPos::None,
+            MergeMode::Standard(MergeLabel::from(Label::dummy())),
+            &mut MergeContext::default(),
+            &mut CallStack::new(),
+        )
+        .unwrap();
+
+        // The contract of the annotation is kept, and applies to the merged record.
+        match closure.body.as_ref() {
+            Term::Annotated(annot, inner) => {
+                assert_eq!(annot.contracts, vec![contract.clone()]);
+
+                let merged = match inner.as_ref() {
+                    Term::Var(id) => cache.get(closure.env.get(id).unwrap().clone()).body,
+                    t => panic!("expected a closurized record, got {t:?}"),
+                };
+                assert_matches!(
+                    merged.as_ref(),
+                    Term::RecRecord(data, ..)
+                        if data.fields.contains_key(&Ident::from("a"))
+                            && data.fields.contains_key(&Ident::from("b"))
+                );
+            }
+            t => panic!("expected an annotated record, got {t:?}"),
+        }
+    }
+}
+
+#[test]
+fn merge_mode_display() {
+    use crate::label::{MergeKind, MergeLabel};
+    use crate::term::MergeStrategy;
+    use merge::{AbsentFields, MergeMode};
+
+    let merge_label = MergeLabel::from(Label::dummy());
+
+    assert_eq!(
+        MergeMode::Standard(merge_label.clone()).to_string(),
+        "Standard(span=0..1)"
+    );
+    assert_eq!(
+        MergeMode::FillDefaults(merge_label.clone()).to_string(),
+        "FillDefaults(span=0..1)"
+    );
+    assert_eq!(
+        MergeMode::LeftBiased(merge_label.clone()).to_string(),
+        "LeftBiased(span=0..1)"
+    );
+    assert_eq!(
+        MergeMode::Contract(Label::dummy(), AbsentFields::Lenient).to_string(),
+        "Contract(\"Number\")"
+    );
+    assert_eq!(
+        MergeMode::Contract(Label::dummy(), AbsentFields::Strict).to_string(),
+        "StrictContract(\"Number\")"
+    );
+
+    let merge_label = merge_label
+        .with_kind(MergeKind::PiecewiseDef)
+        .with_name("prod overlay");
+    let merge_label = MergeLabel {
+        path: merge_label
+            .path
+            .push(Ident::from("foo"))
+            .push(Ident::from("bar")),
+        strategy: MergeStrategy::Intersect,
+        ..merge_label
+    };
+    assert_eq!(
+        merge_label.to_string(),
+        "span=0..1, piecewise, path=foo.bar, strategy=intersect, name=\"prod overlay\""
+    );
+}
+
+#[test]
+fn nest_dotted_fields_into_records() {
+    use crate::label::MergeLabel;
+    use assert_matches::assert_matches;
+
+    fn nested(source: &str) -> RecordData {
+        match parse(source).unwrap().term.into_owned() {
+            Term::Record(record) | Term::RecRecord(record, ..) => merge::nest_dotted_fields(record),
+            t => panic!("expected a record, got {t:?}"),
+        }
+    }
+
+    fn eval_path(t: RichTerm, path: &str) -> Result<Term, EvalError> {
+        eval_no_import(path.split('.').fold(t, |t, id| {
+            mk_term::op1(UnaryOp::StaticAccess(Ident::from(id)), t)
+        }))
+    }
+
+    // Deeply dotted fields sharing a prefix are grouped in the same records.
+    let record = nested(r#"{"a.b.c" = 1, "a.b.d" = 2, "a.e" = 3, f = 4, "g..h" = 5}"#);
+    let names: Vec<_> = record.fields.keys().map(Ident::label).collect();
+    assert_eq!(names, vec!["a", "f", "g..h"]);
+
+    let t = RichTerm::from(Term::Record(record));
+    for (path, value) in [("a.b.c", 1), ("a.b.d", 2), ("a.e", 3), ("f", 4)] {
+        assert_eq!(
+            Ok(Term::Num(Number::from(value))),
+            eval_path(t.clone(), path)
+        );
+    }
+
+    // Once nested, flat records are combined by the standard recursive merge.
+    let merged = mk_term::op2(
+        BinaryOp::Merge(MergeLabel::from(Label::dummy())),
+        Term::Record(nested(r#"{"a.b.c" = 1}"#)),
+        Term::Record(nested(r#"{"a.b.d" = 2}"#)),
+    );
+    assert_eq!(
+        Ok(Term::Num(Number::from(1))),
+        eval_path(merged.clone(), "a.b.c")
+    );
+    assert_eq!(Ok(Term::Num(Number::from(2))), eval_path(merged, "a.b.d"));
+
+    // A dotted field colliding with a field defined directly is merged with it.
+    let t = RichTerm::from(Term::Record(nested(r#"{"a.b" = 1, a = {c = 2}}"#)));
+    assert_eq!(Ok(Term::Num(Number::from(1))), eval_path(t.clone(), "a.b"));
+    assert_eq!(Ok(Term::Num(Number::from(2))), eval_path(t, "a.c"));
+
+    let t = RichTerm::from(Term::Record(nested(r#"{a = {b = 1}, "a.b" = 2}"#)));
+    assert_matches!(
+        eval_path(t, "a.b"),
+        Err(EvalError::MergeIncompatibleArgs { .. })
+    );
+}
+
+#[test]
+fn merge_with_delta_classifies_fields() {
+    use crate::label::MergeLabel;
+    use crate::position::RawSpan;
+    use crate::term::{record::FieldMetadata, IndexMap, MergePriority};
+    use codespan::ByteIndex;
+    use merge::{DeltaKind, MergeContext, MergeMode};
+
+    fn record(fields: Vec<(&str, Field)>) -> RichTerm {
+        let fields: IndexMap<Ident, Field> = fields
+            .into_iter()
+            .map(|(id, field)| (Ident::from(id), field))
+            .collect();
+        Term::Record(RecordData::new(fields, Default::default(), None)).into()
+    }
+
+    let src_id = Files::new().add("<overlay>", String::from("8080"));
+    let overlay_pos = TermPos::Original(RawSpan {
+        src_id,
+        start: ByteIndex(0),
+        end: ByteIndex(4),
+    });
+
+    let base = record(vec![
+        ("kept", Field::from(mk_term::integer(1))),
+        (
+            "port",
+            Field {
+                value: Some(mk_term::integer(80)),
+                metadata: FieldMetadata {
+                    priority: MergePriority::Bottom,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ),
+        ("host", Field::from(mk_term::string("localhost"))),
+        (
+            "tls",
+            Field::from(record(vec![(
+                "enabled",
+                Field::from(RichTerm::from(Term::Bool(true))),
+            )])),
+        ),
+    ]);
+    let overlay = record(vec![
+        (
+            "port",
+            Field::from(mk_term::integer(8080).with_pos(overlay_pos)),
+        ),
+        ("host", Field::from(mk_term::string("localhost"))),
+        ("debug", Field::from(RichTerm::from(Term::Bool(false)))),
+        (
+            "tls",
+            Field::from(record(vec![(
+                "cert",
+                Field::from(mk_term::string("cert.pem")),
+            )])),
+        ),
+    ]);
+
+    let (_, delta) = merge::merge_with_delta(
+        &mut CacheImpl::new(),
+        base,
+        Environment::new(),
+        overlay,
+        Environment::new(),
+        TermPos::None,
+        MergeMode::Standard(MergeLabel::from(Label::dummy())),
+        &mut MergeContext::new(&EvalConfig::default()),
+        &mut CallStack::new(),
+    )
+    .unwrap();
+
+    let kinds: Vec<(String, DeltaKind)> = delta
+        .fields
+        .iter()
+        .map(|field| (field.path.to_string(), field.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (String::from("kept"), DeltaKind::Untouched),
+            (String::from("port"), DeltaKind::Changed),
+            (String::from("host"), DeltaKind::Unchanged),
+            (String::from("tls.enabled"), DeltaKind::Untouched),
+            (String::from("tls.cert"), DeltaKind::Added),
+            (String::from("debug"), DeltaKind::Added),
+        ]
+    );
+
+    let port = delta.fields_of_kind(DeltaKind::Changed).next().unwrap();
+    assert_eq!(port.old, Some(mk_term::integer(80)));
+    assert_eq!(port.new, Some(mk_term::integer(8080).with_pos(overlay_pos)));
+    assert_eq!(port.pos, overlay_pos);
+}
+
+#[test]
+fn merge_final_result() {
+    // The merge is directly forced by the full evaluation, and its fields are allocated in
+    // standard cache elements. Recursive fields must still see the overridden values.
+    let mut vm = VirtualMachine::<_, CacheImpl>::new(DummyResolver {}, std::io::sink());
+    let t = parse("{a = 1, b = a + 1, c = {d = b}} & {a | force = 2, c = {e = 0}}").unwrap();
+    let result = vm.eval_full(t, &Environment::new()).unwrap();
+
+    let Term::Record(data) = result.as_ref() else {
+        panic!("expected a record, got {result:?}");
+    };
+    let value = |id: &str| data.fields[&Ident::from(id)].value.clone().unwrap();
+
+    assert_eq!(value("a").as_ref(), &Term::Num(Number::from(2)));
+    assert_eq!(value("b").as_ref(), &Term::Num(Number::from(3)));
+
+    let c = value("c");
+    let Term::Record(c) = c.as_ref() else {
+        panic!("expected a record, got {c:?}");
+    };
+    assert_eq!(
+        c.fields[&Ident::from("d")]
+            .value
+            .as_ref()
+            .map(|v| v.as_ref()),
+        Some(&Term::Num(Number::from(3)))
+    );
+}
+
+#[test]
+fn merge_compatibility() {
+    use crate::term::{record::SealedTail, IndexMap};
+    use assert_matches::assert_matches;
+    use merge::MergeCompatibility;
+
+    let mut vm = VirtualMachine::<_, CacheImpl>::new(DummyResolver {}, std::io::sink());
+    let mut compatibility =
+        |t1: RichTerm, t2: RichTerm| vm.merge_compatibility(t1, t2, &Environment::new()).unwrap();
+
+    assert_eq!(
+        compatibility(
+            parse("{a = 1, b = {c = \"x\"}, d | default = 0}").unwrap(),
+            parse("{a = 1, b = {e = true}, d = 1 + 1}").unwrap(),
+        ),
+        MergeCompatibility::Clean
+    );
+
+    let MergeCompatibility::Conflicts(paths) = compatibility(
+        parse("{a = 1, b = {c = \"x\", e = 0}, f = fun x => x}").unwrap(),
+        parse("{a = 2, b = {c = \"y\", e = 0}, f = fun x => x}").unwrap(),
+    ) else {
+        panic!("expected conflicts");
+    };
+    let paths: Vec<String> = paths.iter().map(ToString::to_string).collect();
+    assert_eq!(paths, ["a", "b.c", "f"]);
+
+    let sealed = RichTerm::from(Term::Record(RecordData::new(
+        IndexMap::new(),
+        Default::default(),
+        Some(SealedTail::new(
+            0,
+            Label::dummy(),
+            mk_term::integer(0),
+            Vec::new(),
+        )),
+    )));
+    let record = |sealed: RichTerm| {
+        let fields: IndexMap<Ident, RichTerm> = [(Ident::from("a"), sealed)].into_iter().collect();
+        RichTerm::from(Term::Record(RecordData::with_field_values(fields)))
+    };
+
+    assert_eq!(
+        compatibility(sealed.clone(), parse("{b = 1}").unwrap()),
+        MergeCompatibility::SealedTail(Default::default())
+    );
+    assert_matches!(
+        compatibility(record(sealed), record(parse("{b = 1}").unwrap())),
+        MergeCompatibility::SealedTail(path) if path.to_string() == "a"
+    );
+}
+
+#[test]
+fn named_merge_diagnostics() {
+    use crate::error::IntoDiagnostics;
+    use crate::label::MergeLabel;
+    use crate::position::RawSpan;
+    use assert_matches::assert_matches;
+    use codespan::ByteIndex;
+
+    let mut files = Files::new();
+    let src_id = files.add("<generated>", String::from("base & overlay"));
+    let span = RawSpan {
+        src_id,
+        start: ByteIndex(0),
+        end: ByteIndex(14),
+    };
+
+    let merge = mk_term::op2(
+        BinaryOp::Merge(MergeLabel::new(span).with_name("prod overlay")),
+        mk_term::integer(1),
+        mk_term::integer(2),
+    );
+
+    let err = eval_no_import(merge).unwrap_err();
+    assert_matches!(
+        &err,
+        EvalError::MergeIncompatibleArgs { merge_label, .. }
+            if merge_label.name.as_deref() == Some("prod overlay")
+    );
+
+    let diagnostics = err.into_diagnostics(&mut files, None);
+    assert_eq!(
+        diagnostics[0].message,
+        "non mergeable terms while applying `prod overlay`"
+    );
+}
+
+#[test]
+fn merge_environments() {
+    let mut eval_cache = CacheImpl::new();
+    let shared = mk_env(vec![("shared", mk_term::integer(0))], &mut eval_cache);
+
+    let mut env1 = shared.clone();
+    env1.extend(mk_env(vec![("a", mk_term::integer(1))], &mut eval_cache));
+    let mut env2 = shared.clone();
+    env2.extend(mk_env(vec![("b", mk_term::integer(2))], &mut eval_cache));
+
+    let env = merge::merge_environments(env1, &env2);
+
+    let value = |id: &str| {
+        eval_cache
+            .get(env.get(&Ident::from(id)).unwrap().clone())
+            .body
+    };
+    assert_eq!(value("shared"), mk_term::integer(0));
+    assert_eq!(value("a"), mk_term::integer(1));
+    assert_eq!(value("b"), mk_term::integer(2));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "bound to different elements")]
+fn merge_environments_collision() {
+    let mut eval_cache = CacheImpl::new();
+    let env1 = mk_env(vec![("a", mk_term::integer(1))], &mut eval_cache);
+    let env2 = mk_env(vec![("a", mk_term::integer(2))], &mut eval_cache);
+
+    merge::merge_environments(env1, &env2);
+}
+
+#[test]
+fn merged_record_capacity() {
+    use crate::label::MergeLabel;
+    use crate::term::IndexMap;
+    use merge::{MergeContext, MergeMode};
+
+    fn record(fields: &[&str]) -> RichTerm {
+        let fields: IndexMap<Ident, RichTerm> = fields
+            .iter()
+            .map(|id| (Ident::from(*id), mk_term::integer(1)))
+            .collect();
+        Term::Record(RecordData::with_field_values(fields)).into()
+    }
+
+    let closure = merge::merge(
+        &mut CacheImpl::new(),
+        record(&["a", "b", "c"]),
+        Environment::new(),
+        record(&["c", "d"]),
+        Environment::new(),
+        TermPos::None,
+        MergeMode::Standard(MergeLabel::from(Label::dummy())),
+        &mut MergeContext::default(),
+        &mut CallStack::new(),
+    )
+    .unwrap();
+
+    match closure.body.as_ref() {
+        Term::RecRecord(data, ..) => {
+            assert_eq!(data.fields.len(), 4);
+            assert_eq!(data.fields.capacity(), data.fields.len());
+        }
+        t => panic!("expected a record, got {t:?}"),
+    }
+}
+
+#[test]
+fn merge_preview_copies_shared_thunks() {
+    use assert_matches::assert_matches;
+    use cache::lazy::ThunkState;
+
+    let mut eval_cache = CacheImpl::new();
+    let initial_env = mk_env(
+        vec![
+            ("base", parse("{a = 1 + 1}").unwrap()),
+            ("conflict", parse("{a = 1} & {a = 2}").unwrap()),
+        ],
+        &mut eval_cache,
+    );
+    let state = |id: &str| initial_env.get(&Ident::from(id)).unwrap().state();
+    let mut vm = VirtualMachine::new_with_cache(DummyResolver {}, eval_cache, std::io::sink());
+
+    let preview = vm.merge_preview(
+        parse("base & {b = 1}").unwrap(),
+        QueryPath(vec![Ident::from("a")]),
+        &initial_env,
+    );
+    assert_eq!(preview.map(RichTerm::without_pos), Ok(mk_term::integer(2)));
+    assert_eq!(state("base"), ThunkState::Suspended);
+
+    // A failing preview doesn't leave the shared thunk black-holed, which would turn the error of
+    // the main evaluation into an infinite recursion.
+    assert!(vm
+        .merge_preview(
+            mk_term::var("conflict"),
+            QueryPath(vec![Ident::from("a")]),
+            &initial_env,
+        )
+        .is_err());
+    assert_eq!(state("conflict"), ThunkState::Suspended);
+    assert_matches!(
+        vm.eval(parse("conflict.a").unwrap(), &initial_env),
+        Err(EvalError::MergeIncompatibleArgs { .. })
+    );
+}
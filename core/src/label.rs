@@ -11,7 +11,7 @@ use crate::{
     position::{RawSpan, TermPos},
     term::{
         record::{Field, RecordData},
-        RichTerm, SealingKey, Term,
+        MergeStrategy, RichTerm, SealingKey, Term,
     },
     typecheck::{ReifyAsUnifType, UnifType},
     types::{TypeF, Types},
@@ -303,6 +303,38 @@ pub struct Label {
     /// while first transforming a record as part of the pending contract generation.
     /// Contract applications outside of records will have this field set to `None`.
     pub field_name: Option<Ident>,
+    /// The record contract whose check failed, if any. This is set when a record contract is
+    /// applied through merging: on the contracts of its fields, and on the errors raised by the
+    /// record contract itself, such as extra fields. See [`ContractId`].
+    pub contract_id: Option<ContractId>,
+}
+
+/// A stable identifier of a record contract, derived from its label: two applications of the same
+/// contract annotation have the same identifier. The blame errors carry the identifier of the
+/// record contract they originate from (see [`Label::contract_id`]), which lets reporting tools
+/// group the violations of each contract, as in `MyServiceContract: 3 violations`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContractId {
+    /// The contract as written in the annotation, such as `MyServiceContract`.
+    pub name: String,
+    /// The position of the contract annotation.
+    pub span: RawSpan,
+}
+
+impl ContractId {
+    /// Return the identifier of the contract checked by `label`.
+    pub fn of(label: &Label) -> Self {
+        ContractId {
+            name: label.types.to_string(),
+            span: label.span,
+        }
+    }
+}
+
+impl std::fmt::Display for ContractId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
 }
 
 /// Data about type variables that is needed for polymorphic contracts to decide which actions to take.
@@ -497,6 +529,14 @@ impl Label {
     pub fn with_field_name(self, field_name: Option<Ident>) -> Self {
         Label { field_name, ..self }
     }
+
+    /// Set the identifier of the record contract whose check failed, see [`ContractId`].
+    pub fn with_contract_id(self, contract_id: ContractId) -> Self {
+        Label {
+            contract_id: Some(contract_id),
+            ..self
+        }
+    }
 }
 
 impl Default for Label {
@@ -516,6 +556,7 @@ impl Default for Label {
             type_environment: Default::default(),
             dualize: false,
             field_name: None,
+            contract_id: None,
         }
     }
 }
@@ -547,18 +588,181 @@ pub enum MergeKind {
 /// Additionally, the merging arrays currently generates a contract and its associated label for
 /// which we don't necessarily have a defined span at hand. The merge label makes it possible to
 /// fallback to the original position of the merge.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct MergeLabel {
     /// The span of the original merge (which might then decompose into many others).
     pub span: RawSpan,
     pub kind: MergeKind,
+    /// The merge strategy selected by the annotations of the field being merged, if any.
+    pub strategy: MergeStrategy,
+    /// The path of the field being merged, relative to the original merge.
+    pub path: MergePath,
+    /// A human-readable name for the original merge, such as `prod overlay`, which is shown in
+    /// diagnostics. Nickel never sets it, but embedders generating merges can use it to tell them
+    /// apart.
+    pub name: Option<Rc<str>>,
+    /// Whether the field being merged is optional in both operands. This is set by merging, which
+    /// may then tolerate conflicts between the two values (see
+    /// [`crate::eval::EvalConfig::lenient_optional_conflicts`]).
+    pub optional: bool,
 }
 
-impl From<Label> for MergeLabel {
-    fn from(label: Label) -> Self {
+impl MergeLabel {
+    /// Create a label for a standard merge located at `span`, with the default merge strategy and
+    /// an empty field path. This is the label to use when synthesizing a merge expression, for
+    /// example when building a Nickel term programmatically:
+    ///
+    /// ```
+    /// # use codespan::{ByteIndex, Files};
+    /// # use nickel_lang_core::{
+    /// #     label::{MergeKind, MergeLabel},
+    /// #     position::RawSpan,
+    /// #     term::{make as mk_term, record::RecordData, BinaryOp, Term},
+    /// # };
+    /// let mut files = Files::new();
+    /// let src_id = files.add("<generated>", "base & patch");
+    /// let span = RawSpan {
+    ///     src_id,
+    ///     start: ByteIndex(0),
+    ///     end: ByteIndex(12),
+    /// };
+    ///
+    /// let label = MergeLabel::new(span).with_kind(MergeKind::Standard);
+    /// let merge = mk_term::op2(
+    ///     BinaryOp::Merge(label),
+    ///     Term::Record(RecordData::empty()),
+    ///     Term::Record(RecordData::empty()),
+    /// );
+    /// # assert!(matches!(merge.as_ref(), Term::Op2(BinaryOp::Merge(..), ..)));
+    /// ```
+    ///
+    /// A merge label can also be obtained from a contract [`Label`], which only keeps its span, or
+    /// from a [`crate::eval::merge::MergeMode`] during evaluation, which returns the inner label of
+    /// a standard merge or converts the label of a contract.
+    pub fn new(span: RawSpan) -> Self {
         MergeLabel {
-            span: label.span,
+            span,
             kind: Default::default(),
+            strategy: Default::default(),
+            path: Default::default(),
+            name: None,
+            optional: false,
+        }
+    }
+
+    /// Set the kind of the merge, see [`MergeKind`].
+    pub fn with_kind(self, kind: MergeKind) -> Self {
+        MergeLabel { kind, ..self }
+    }
+
+    /// Set the name of the merge shown in diagnostics.
+    pub fn with_name(self, name: impl Into<Rc<str>>) -> Self {
+        MergeLabel {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+}
+
+impl From<Label> for MergeLabel {
+    fn from(label: Label) -> Self {
+        MergeLabel::new(label.span)
+    }
+}
+
+/// A compact rendering meant for logs, such as `span=3..12, path=foo.bar, strategy=intersect`.
+/// Only the span is always printed: the other attributes are omitted when they have their default
+/// value. The span is given as a byte range, as the name of the file isn't known without the file
+/// database.
+impl std::fmt::Display for MergeLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "span={}..{}",
+            self.span.start.to_usize(),
+            self.span.end.to_usize()
+        )?;
+
+        if self.kind == MergeKind::PiecewiseDef {
+            write!(f, ", piecewise")?;
+        }
+
+        if !self.path.is_empty() {
+            write!(f, ", path={}", self.path)?;
+        }
+
+        if self.strategy != MergeStrategy::Standard {
+            write!(f, ", strategy={}", self.strategy)?;
+        }
+
+        if self.optional {
+            write!(f, ", optional")?;
+        }
+
+        if let Some(name) = &self.name {
+            write!(f, ", name={name:?}")?;
         }
+
+        Ok(())
+    }
+}
+
+/// The path of a field inside nested records being merged, such as `config.services.web`.
+///
+/// Merging recursively descends into each common field, so the path is extended at each level.
+/// It's represented as a persistent linked list sharing its prefixes, such that extending it is
+/// cheap and never copies the parent path.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergePath(Option<Rc<MergePathNode>>);
+
+#[derive(Debug, Eq, PartialEq)]
+struct MergePathNode {
+    field: Ident,
+    parent: MergePath,
+}
+
+impl MergePath {
+    /// Return the path extended with the given field.
+    pub fn push(&self, field: Ident) -> Self {
+        MergePath(Some(Rc::new(MergePathNode {
+            field,
+            parent: self.clone(),
+        })))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Return the outermost field of the path, or `None` if the path is empty.
+    pub fn outermost(&self) -> Option<Ident> {
+        let mut current = self.0.as_ref()?;
+
+        while let Some(parent) = &current.parent.0 {
+            current = parent;
+        }
+
+        Some(current.field)
+    }
+
+    /// Return the fields of the path, from the outermost to the innermost one.
+    pub fn to_vec(&self) -> Vec<Ident> {
+        let mut fields = Vec::new();
+        let mut current = self;
+
+        while let Some(node) = &current.0 {
+            fields.push(node.field);
+            current = &node.parent;
+        }
+
+        fields.reverse();
+        fields
+    }
+}
+
+impl std::fmt::Display for MergePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fields: Vec<String> = self.to_vec().iter().map(Ident::to_string).collect();
+        write!(f, "{}", fields.join("."))
     }
 }
@@ -134,4 +134,6 @@ pub enum ParseError {
         /// The position of the type annotation.
         annot_span: RawSpan,
     },
+    /// A `merge` annotation used a name which doesn't correspond to any known merge strategy.
+    UnknownMergeStrategy(Ident),
 }
@@ -223,8 +223,14 @@ pub enum NormalToken<'input> {
     RecordInsert,
     #[token("%record_remove%")]
     RecordRemove,
+    #[token("%merge_fill_defaults%")]
+    MergeFillDefaults,
+    #[token("%merge_left_biased%")]
+    MergeLeftBiased,
     #[token("%record_empty_with_tail%")]
     RecordEmptyWithTail,
+    #[token("%record_freeze%")]
+    RecordFreeze,
     #[token("%record_seal_tail%")]
     RecordSealTail,
     #[token("%record_unseal_tail%")]
@@ -248,6 +254,8 @@ pub enum NormalToken<'input> {
 
     #[token("%has_field%")]
     HasField,
+    #[token("%record_common_fields%")]
+    RecordCommonFields,
     #[token("%map%")]
     Map,
     #[token("%elem_at%")]
@@ -273,6 +281,10 @@ pub enum NormalToken<'input> {
     Force,
     #[token("not_exported")]
     NotExported,
+    #[token("relax_optional")]
+    RelaxOptional,
+    #[token("frozen")]
+    Frozen,
 
     #[token("%hash%")]
     OpHash,
@@ -375,6 +387,8 @@ pub const KEYWORDS: &[&str] = &[
     "priority",
     "force",
     "not_exported",
+    "relax_optional",
+    "frozen",
 ];
 
 #[derive(Debug, Clone, PartialEq)]
@@ -306,8 +306,16 @@ impl UniRecord {
                                     contracts,
                                 },
                             opt: false,
+                            relax_opt: false,
+                            frozen: false,
                             not_exported: false,
                             priority: MergePriority::Neutral,
+                            merge_strategy: None,
+                            definition_sites: _,
+                            doc_sources: _,
+                            contributions: _,
+                            priority_resolution: _,
+                            introduced: _,
                         },
                     // At this stage, this field should always be empty. It's a run-time thing, and
                     // is only filled during program transformation.
@@ -341,8 +349,16 @@ impl UniRecord {
                                     contracts,
                                 },
                             opt: false,
+                            relax_opt: false,
+                            frozen: false,
                             not_exported: false,
                             priority: MergePriority::Neutral,
+                            merge_strategy: None,
+                            definition_sites: _,
+                            doc_sources: _,
+                            contributions: _,
+                            priority_resolution: _,
+                            introduced: _,
                         },
                     // At this stage, this field should always be empty. It's a run-time thing, and
                     // is only filled during program transformation.
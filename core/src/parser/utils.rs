@@ -554,11 +554,28 @@ where
 /// Merge two fields by performing the merge of both their value (dynamically, by introducing a
 /// merging operator) and their metadata (statically).
 fn merge_fields(id_span: RawSpan, field1: Field, field2: Field) -> Field {
+    let strategy = field1
+        .metadata
+        .merge_strategy
+        .clone()
+        .or_else(|| field2.metadata.merge_strategy.clone())
+        .unwrap_or_default();
+    let mut definition_sites = field1.definition_sites();
+    definition_sites.extend(field2.definition_sites());
+    let mut doc_sources = field1.doc_sources();
+    doc_sources.extend(field2.doc_sources());
+    let contributions = field1.contributions() + field2.contributions();
+    let optional = field1.metadata.opt && field2.metadata.opt;
+
     let value = match (field1.value, field2.value) {
         (Some(t1), Some(t2)) => Some(mk_term::op2(
             BinaryOp::Merge(MergeLabel {
                 span: id_span,
                 kind: MergeKind::PiecewiseDef,
+                strategy,
+                path: Default::default(),
+                name: None,
+                optional,
             }),
             t1,
             t2,
@@ -567,7 +584,12 @@ fn merge_fields(id_span: RawSpan, field1: Field, field2: Field) -> Field {
         (None, None) => None,
     };
 
-    let metadata = FieldMetadata::flatten(field1.metadata, field2.metadata);
+    let metadata = FieldMetadata {
+        definition_sites,
+        doc_sources,
+        contributions,
+        ..FieldMetadata::flatten(field1.metadata, field2.metadata)
+    };
 
     // At this stage, pending contracts aren't filled nor meaningful, and should all be empty.
     debug_assert!(field1.pending_contracts.is_empty() && field2.pending_contracts.is_empty());
@@ -600,13 +622,10 @@ pub fn mk_label(types: Types, src_id: FileId, l: usize, r: usize) -> Label {
     }
 }
 
-/// Same as `mk_span`, but for merge labels. The kind is set to the default one
-/// (`MergeKind::Standard`).
+/// Same as `mk_span`, but for merge labels. The kind, the strategy and the path are set to the
+/// default ones (`MergeKind::Standard`, `MergeStrategy::Standard` and the empty path).
 pub fn mk_merge_label(src_id: FileId, l: usize, r: usize) -> MergeLabel {
-    MergeLabel {
-        span: mk_span(src_id, l, r),
-        kind: Default::default(),
-    }
+    MergeLabel::new(mk_span(src_id, l, r))
 }
 
 /// Generate a `Let` or a `LetPattern` (depending on whether `assgn` has a record pattern) from
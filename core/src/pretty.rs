@@ -176,11 +176,18 @@ where
             } else {
                 self.nil()
             })
-            .append(if metadata.opt {
+            .append(if metadata.relax_opt {
+                self.line().append(self.text("| relax_optional"))
+            } else if metadata.opt {
                 self.line().append(self.text("| optional"))
             } else {
                 self.nil()
             })
+            .append(if metadata.frozen {
+                self.line().append(self.text("| frozen"))
+            } else {
+                self.nil()
+            })
             .append(match &metadata.priority {
                 MergePriority::Bottom => self.line().append(self.text("| default")),
                 MergePriority::Neutral => self.nil(),
@@ -191,6 +198,27 @@ where
                     .append(self.as_string(p)),
                 MergePriority::Top => self.line().append(self.text("| force")),
             })
+            .append(match &metadata.merge_strategy {
+                Some(strategy) => self
+                    .line()
+                    .append(self.text("| merge"))
+                    .append(self.space())
+                    .append(self.as_string(strategy))
+                    .append(match strategy {
+                        MergeStrategy::Concat {
+                            separator: Some(separator),
+                        } => self
+                            .space()
+                            .append(self.text("\""))
+                            .append(self.escaped_string(separator))
+                            .append(self.text("\"")),
+                        MergeStrategy::Compare { comparator } => self
+                            .space()
+                            .append(comparator.to_owned().pretty(self).parens()),
+                        _ => self.nil(),
+                    }),
+                None => self.nil(),
+            })
             .nest(2)
             .group()
     }
@@ -321,6 +349,7 @@ where
                 .append(allocator.as_string(id))
                 .append(allocator.space()),
             Force { .. } => allocator.text("%force%").append(allocator.space()),
+            RecordFreeze() => allocator.text("%record_freeze%").append(allocator.space()),
             op => allocator
                 .text(format!("%{op:?}%").to_lowercase())
                 .append(allocator.space()),
@@ -351,6 +380,8 @@ where
             LessOrEq() => allocator.text("<="),
 
             Merge(_) => allocator.text("&"),
+            MergeFillDefaults(_) => allocator.text("%merge_fill_defaults%"),
+            MergeLeftBiased(_) => allocator.text("%merge_left_biased%"),
 
             StrConcat() => allocator.text("++"),
             ArrayConcat() => allocator.text("@"),
@@ -26,7 +26,8 @@ use crate::eval;
 use crate::eval::cache::Cache as EvalCache;
 use crate::eval::VirtualMachine;
 use crate::identifier::Ident;
-use crate::term::{record::Field, RichTerm};
+use crate::serialize::{self, ExportFormat};
+use crate::term::{make as mk_term, record::Field, RichTerm, UnaryOp};
 use codespan::FileId;
 use codespan_reporting::term::termcolor::{Ansi, ColorChoice, StandardStream};
 use std::ffi::OsString;
@@ -195,6 +196,51 @@ impl<EC: EvalCache> Program<EC> {
             .map_err(|e| e.into())
     }
 
+    /// Same as `eval_full_for_export`, but only evaluates the value of the field at `path`, given
+    /// as a dot-separated path (see [`QueryPath::parse`]). The whole program is evaluated if `path`
+    /// is `None`.
+    ///
+    /// Since the fields of merged records are only merged when they are needed, the fields which
+    /// aren't on `path` or below are never merged nor evaluated.
+    pub fn eval_full_for_export_at(&mut self, path: Option<String>) -> Result<RichTerm, Error> {
+        let (t, initial_env) = self.prepare_eval()?;
+        let path = QueryPath::parse_opt(self.vm.import_resolver_mut(), path)?;
+        let t = path
+            .0
+            .into_iter()
+            .fold(t, |t, id| mk_term::op1(UnaryOp::StaticAccess(id), t));
+
+        self.vm.reset();
+        self.vm
+            .eval_full_for_export(t, &initial_env)
+            .map_err(|e| e.into())
+    }
+
+    /// Compute the fully evaluated value of the field at `path`, given as a dot-separated path
+    /// (see [`QueryPath::parse`]), without affecting the state of the evaluator. Unlike the other
+    /// evaluation functions, the evaluator isn't reset beforehand. See
+    /// [`VirtualMachine::merge_preview`].
+    pub fn merge_preview(&mut self, path: String) -> Result<RichTerm, Error> {
+        let (t, initial_env) = self.prepare_eval()?;
+        let path = QueryPath::parse(self.vm.import_resolver_mut(), path)?;
+
+        self.vm
+            .merge_preview(t, path, &initial_env)
+            .map_err(|e| e.into())
+    }
+
+    /// Evaluate the program and serialize the result to `writer`, without materializing the
+    /// whole result first. See [`serialize::stream::to_writer`].
+    pub fn export_streaming<W: Write>(
+        &mut self,
+        format: ExportFormat,
+        writer: W,
+    ) -> Result<(), Error> {
+        let (t, initial_env) = self.prepare_eval()?;
+        self.vm.reset();
+        serialize::stream::to_writer(&mut self.vm, t, &initial_env, format, writer)
+    }
+
     /// Same as `eval_full`, but does not substitute all variables.
     pub fn eval_deep(&mut self) -> Result<RichTerm, Error> {
         let (t, initial_env) = self.prepare_eval()?;
@@ -284,6 +330,44 @@ impl<EC: EvalCache> Program<EC> {
         self.color_opt = c;
     }
 
+    /// Only resolve the imports of this program when they are evaluated. See
+    /// [Cache::set_lazy_imports].
+    pub fn set_lazy_imports(&mut self, lazy: bool) {
+        self.vm.import_resolver_mut().set_lazy_imports(lazy);
+    }
+
+    /// Set the configuration of the evaluator used by this program.
+    pub fn set_eval_config(&mut self, config: eval::EvalConfig) {
+        self.vm.set_config(config);
+    }
+
+    /// Set an observer notified of each field merged during the evaluation of this program. See
+    /// [eval::merge::MergeObserver].
+    pub fn set_merge_observer(
+        &mut self,
+        observer: Option<std::rc::Rc<std::cell::RefCell<dyn eval::merge::MergeObserver>>>,
+    ) {
+        self.vm.set_merge_observer(observer);
+    }
+
+    /// Return the statistics about the merges performed during the last evaluation of this
+    /// program, or `None` if they aren't collected. See [eval::EvalConfig::collect_merge_stats].
+    pub fn merge_stats(&self) -> Option<eval::merge::MergeStats> {
+        self.vm.merge_stats()
+    }
+
+    /// Return the time spent merging each top-level field during the last evaluation of this
+    /// program. See [eval::EvalConfig::collect_field_timings].
+    pub fn field_timings(&self) -> Vec<(Ident, std::time::Duration)> {
+        self.vm.field_timings()
+    }
+
+    /// Return the documentations dropped by the merges performed during the last evaluation of
+    /// this program. See [eval::EvalConfig::warn_overridden_docs].
+    pub fn doc_override_warnings(&self) -> &[eval::merge::DocOverrideWarning] {
+        self.vm.doc_override_warnings()
+    }
+
     pub fn pprint_ast(
         &mut self,
         out: &mut std::io::BufWriter<Box<dyn std::io::Write>>,
@@ -269,6 +269,33 @@ fn render_query_result<R: QueryPrinter>(
         _ => (),
     }
 
+    match metadata.priority_resolution {
+        Some(resolution) if selected_attrs.value => {
+            renderer.write_metadata(out, "priority resolution", &resolution.to_string())?;
+            found = true;
+        }
+        _ => (),
+    }
+
+    if metadata.introduced && selected_attrs.value {
+        renderer.write_metadata(out, "introduced", "by the right operand of the last merge")?;
+        found = true;
+    }
+
+    match field.value.as_ref().map(|value| value.as_ref()) {
+        Some(Term::Record(record) | Term::RecRecord(record, ..))
+            if selected_attrs.value && record.attrs.merged_from > 1 =>
+        {
+            renderer.write_metadata(
+                out,
+                "merged from",
+                &format!("{} records", record.attrs.merged_from),
+            )?;
+            found = true;
+        }
+        _ => (),
+    }
+
     match metadata.doc {
         Some(ref s) if selected_attrs.doc => {
             renderer.write_doc(out, s)?;
@@ -277,6 +304,17 @@ fn render_query_result<R: QueryPrinter>(
         _ => (),
     }
 
+    // When several merged definitions are documented, we list all their documentations.
+    if selected_attrs.doc && metadata.doc_sources.len() > 1 {
+        for (index, (_, doc)) in metadata.doc_sources.iter().enumerate() {
+            renderer.write_metadata(
+                out,
+                &format!("documentation of definition {}", index + 1),
+                doc,
+            )?;
+        }
+    }
+
     if !found {
         println!("Requested metadata were not found for this value.");
     }
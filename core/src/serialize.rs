@@ -19,6 +19,8 @@ use malachite::num::conversion::traits::IsInteger;
 
 use std::{fmt, io, rc::Rc};
 
+pub mod stream;
+
 /// Available export formats.
 // If you add or remove variants, remember to update the CLI docs in `src/bin/nickel.rs'
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, clap::ValueEnum)]
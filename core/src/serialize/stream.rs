@@ -0,0 +1,280 @@
+//! Streaming serialization of the result of an evaluation.
+//!
+//! [super::to_writer] requires a fully evaluated term, which means that the whole result of the
+//! program is materialized in memory before the first byte is written. For large configurations,
+//! typically obtained by merging many records, this can be a lot. Instead, the exporter of this
+//! module walks the result field by field: each value is evaluated right before being serialized,
+//! and dropped as soon as it has been written.
+//!
+//! Only JSON and YAML can be streamed. Other formats fall back to the standard exporter.
+//!
+//! To be able to drop a value once it's written, the exporter evaluates a copy of its cache
+//! element (see `Streamer::detach`), and the original element is never updated. The flip side is
+//! that a field which is also used by a sibling, as `a` in `{a = expensive, b = a}`, is evaluated
+//! twice: once when it's written, and once when the sibling is.
+use std::{cell::RefCell, io};
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{validate, ExportFormat};
+use crate::{
+    cache::ImportResolver,
+    error::{Error, ExportError},
+    eval::{cache::Cache, Closure, Environment, IdentKind, VirtualMachine},
+    identifier::Ident,
+    term::{record::MissingFieldDefError, BindingType, RichTerm, RuntimeContract, Term},
+};
+
+/// Evaluate `t` and serialize the result to `writer`, evaluating each field or array element only
+/// when it's written. The fields marked `not_exported` are skipped, as for [super::validate], and
+/// the fields are written in alphabetical order, as for [super::to_writer], such that the output
+/// is the same as with the standard exporter.
+///
+/// If the evaluation fails in the middle of the serialization, `writer` may have received a
+/// partial output.
+pub fn to_writer<R, C, W>(
+    vm: &mut VirtualMachine<R, C>,
+    t: RichTerm,
+    initial_env: &Environment,
+    format: ExportFormat,
+    writer: W,
+) -> Result<(), Error>
+where
+    R: ImportResolver,
+    C: Cache,
+    W: io::Write,
+{
+    if !matches!(format, ExportFormat::Json | ExportFormat::Yaml) {
+        let rt = vm.eval_full_for_export(t, initial_env)?;
+        validate(format, &rt)?;
+        return super::to_writer(writer, format, &rt).map_err(Error::from);
+    }
+
+    let streamer = RefCell::new(Streamer {
+        vm,
+        initial_env,
+        format,
+        error: None,
+    });
+
+    let value = Streamed {
+        streamer: &streamer,
+        value: Closure::atomic_closure(t),
+    };
+
+    let result = if format == ExportFormat::Json {
+        value
+            .serialize(&mut serde_json::Serializer::pretty(writer))
+            .map_err(|err| err.to_string())
+    } else {
+        value
+            .serialize(&mut serde_yaml::Serializer::new(writer))
+            .map_err(|err| err.to_string())
+    };
+
+    // An error raised by the evaluation is reported as such, instead of the generic serialization
+    // error it has been smuggled through.
+    match (result, streamer.into_inner().error) {
+        (_, Some(err)) => Err(err),
+        (Err(msg), None) => Err(ExportError::Other(msg).into()),
+        (Ok(()), None) => Ok(()),
+    }
+}
+
+/// The state shared by the values being serialized.
+struct Streamer<'a, R: ImportResolver, C: Cache> {
+    vm: &'a mut VirtualMachine<R, C>,
+    initial_env: &'a Environment,
+    format: ExportFormat,
+    /// The error which interrupted the serialization, if any. Serde only lets us return errors of
+    /// the serializer's type, so the actual error is stored here.
+    error: Option<Error>,
+}
+
+impl<'a, R: ImportResolver, C: Cache> Streamer<'a, R, C> {
+    /// Record an error interrupting the serialization.
+    fn fail<E: serde::ser::Error>(&mut self, error: impl Into<Error>) -> E {
+        self.error = Some(error.into());
+        E::custom("streaming export interrupted")
+    }
+
+    /// Copy the cache element of a value which is a variable, and bind the copy to a fresh
+    /// variable in `env`. Evaluating the copy doesn't update the original element, which is
+    /// shared with the record or the array: the evaluated value is dropped once it has been
+    /// written, instead of being kept alive until the end of the export.
+    fn detach(&mut self, value: RichTerm, env: &mut Environment) -> RichTerm {
+        let Term::Var(id) = value.as_ref() else {
+            return value;
+        };
+        let Some(idx) = env.get(id) else {
+            return value;
+        };
+
+        let closure = self.vm.cache.get(idx.clone());
+        let fresh = Ident::fresh();
+        env.insert(
+            fresh,
+            self.vm
+                .cache
+                .add(closure, IdentKind::Record, BindingType::Normal),
+        );
+
+        RichTerm::new(Term::Var(fresh), value.pos)
+    }
+}
+
+/// A value which is evaluated when it's serialized.
+struct Streamed<'s, 'a, R: ImportResolver, C: Cache> {
+    streamer: &'s RefCell<Streamer<'a, R, C>>,
+    value: Closure,
+}
+
+impl<'s, 'a, R: ImportResolver, C: Cache> Streamed<'s, 'a, R, C> {
+    /// Detach `value` (see [Streamer::detach]) and apply the given pending contracts to it.
+    fn child<'c>(
+        &self,
+        value: RichTerm,
+        contracts: impl Iterator<Item = &'c RuntimeContract>,
+        env: &Environment,
+    ) -> Self {
+        let mut env = env.clone();
+        let value = self.streamer.borrow_mut().detach(value, &mut env);
+        let pos = value.pos;
+
+        Streamed {
+            streamer: self.streamer,
+            value: Closure {
+                body: RuntimeContract::apply_all(value, contracts.cloned(), pos),
+                env,
+            },
+        }
+    }
+}
+
+impl<'s, 'a, R: ImportResolver, C: Cache> Serialize for Streamed<'s, 'a, R, C> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let evaluated = {
+            let mut streamer = self.streamer.borrow_mut();
+            let initial_env = streamer.initial_env;
+
+            match streamer
+                .vm
+                .eval_closure_for_export(self.value.clone(), initial_env)
+            {
+                Ok(evaluated) => evaluated,
+                Err(err) => return Err(streamer.fail(err)),
+            }
+        };
+
+        let (rt, env) = evaluated;
+
+        match rt.as_ref() {
+            Term::Record(record) => {
+                let mut fields: Vec<_> = record
+                    .fields
+                    .iter()
+                    .filter(|(_, field)| {
+                        !(field.is_empty_optional() || field.metadata.not_exported)
+                    })
+                    .collect();
+                fields.sort_by_key(|(id, _)| **id);
+
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+
+                for (id, field) in fields {
+                    let Some(value) = &field.value else {
+                        let err = MissingFieldDefError {
+                            id: *id,
+                            metadata: field.metadata.clone(),
+                        }
+                        .into_eval_err(rt.pos, self.value.body.pos);
+                        return Err(self.streamer.borrow_mut().fail(err));
+                    };
+
+                    let child = self.child(value.clone(), field.pending_contracts.iter(), &env);
+                    map.serialize_entry(&id.to_string(), &child)?;
+                }
+
+                map.end()
+            }
+            Term::Array(array, attrs) => {
+                let mut seq = serializer.serialize_seq(Some(array.len()))?;
+
+                for elem in array.iter() {
+                    let child = self.child(elem.clone(), attrs.pending_contracts.iter(), &env);
+                    seq.serialize_element(&child)?;
+                }
+
+                seq.end()
+            }
+            _ => {
+                let format = self.streamer.borrow().format;
+
+                if let Err(err) = validate(format, &rt) {
+                    return Err(self.streamer.borrow_mut().fail(err));
+                }
+
+                rt.serialize(serializer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::EvalError, eval::cache::CacheImpl, program::Program};
+    use std::io::Cursor;
+
+    fn program(source: &str) -> Program<CacheImpl> {
+        Program::new_from_source(Cursor::new(source), "<test>", std::io::stderr()).unwrap()
+    }
+
+    fn export(source: &str, format: ExportFormat) -> Result<String, Error> {
+        let mut buffer = Vec::new();
+        program(source).export_streaming(format, &mut buffer)?;
+        Ok(String::from_utf8(buffer).unwrap())
+    }
+
+    #[test]
+    fn same_output_as_standard_export() {
+        let source = "{
+            b = {y = [1, {z = null}], x | not_exported = 'Foo},
+            a | default = \"a\",
+            c | not_exported = fun x => x,
+            d | optional,
+        } & {b.w | Number = 1, a = \"a\"}";
+
+        for format in [ExportFormat::Json, ExportFormat::Yaml] {
+            let expected =
+                super::super::to_string(format, &program(source).eval_full_for_export().unwrap())
+                    .unwrap();
+            assert_eq!(export(source, format).unwrap(), expected);
+        }
+
+        // Other formats fall back to the standard exporter.
+        assert_eq!(
+            export("{b = 1, a = \"a\"}", ExportFormat::Toml).unwrap(),
+            "a = \"a\"\nb = 1\n"
+        );
+    }
+
+    #[test]
+    fn evaluation_errors_are_reported() {
+        assert!(matches!(
+            export("{a = 1, b = {c | String = 1}}", ExportFormat::Json),
+            Err(Error::EvalError(EvalError::BlameError { .. }))
+        ));
+        assert!(matches!(
+            export("{a = 1, b | Number}", ExportFormat::Yaml),
+            Err(Error::EvalError(EvalError::MissingFieldDef { .. }))
+        ));
+        assert!(matches!(
+            export("{a = fun x => x}", ExportFormat::Json),
+            Err(Error::ExportError(ExportError::NonSerializable(..)))
+        ));
+    }
+}
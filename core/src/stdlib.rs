@@ -81,7 +81,19 @@ pub mod internals {
     generate_accessor!(forall_tail);
     generate_accessor!(dyn_tail);
     generate_accessor!(empty_tail);
-    generate_accessor!(stdlib_contract_equal);
+
+    // `$array_merge_equal` is used by each merge of two arrays, so we build the term only once and
+    // share it.
+    pub fn array_merge_equal() -> RichTerm {
+        thread_local! {
+            static ARRAY_MERGE_EQUAL: RichTerm = mk_term::var("$array_merge_equal");
+        }
+
+        ARRAY_MERGE_EQUAL.with(RichTerm::clone)
+    }
+
+    generate_accessor!(array_merge_equal_with);
+    generate_accessor!(array_set_merge);
 
     generate_accessor!(rec_default);
     generate_accessor!(rec_force);
@@ -286,7 +286,9 @@ impl RuntimeContract {
         .with_pos(pos)
     }
 
-    /// Apply a series of contracts to a term, in order.
+    /// Apply a series of contracts to a term, in order. The result behaves as `rt | C1 | .. | Cn`:
+    /// the first contract is the innermost one, such that it's checked first as long as the
+    /// following contracts inspect their argument before deciding to blame.
     pub fn apply_all<I>(rt: RichTerm, contracts: I, pos: TermPos) -> RichTerm
     where
         I: Iterator<Item = Self>,
@@ -352,7 +354,8 @@ pub enum MergePriority {
     /// Act as the value `MergePriority::Numeral(0)` with respect to ordering and equality
     /// testing. The only way to discriminate this variant is to pattern match on it.
     Neutral,
-    /// A numeral priority.
+    /// A numeral priority. Numbers are arbitrary precision rationals, such that a priority can
+    /// always be inserted between two others.
     Numeral(Number),
     /// The priority of values that override everything else and can't be overridden.
     Top,
@@ -421,6 +424,96 @@ impl fmt::Display for MergePriority {
     }
 }
 
+/// The strategy used to merge the values of a field, as selected by a `merge` annotation, for
+/// example `{foo | merge element-wise = [1, 2]}`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum MergeStrategy {
+    /// The standard merge semantics. Arrays can only be merged if they are equal.
+    #[default]
+    Standard,
+    /// Arrays of the same length are merged element by element.
+    ElementWise,
+    /// Arrays are concatenated and deduplicated, keeping the first occurrence of each element.
+    Set,
+    /// A record merged with an array of records is merged with each element of the array.
+    Broadcast,
+    /// Strings are concatenated, with an optional separator inserted in between.
+    Concat { separator: Option<String> },
+    /// Only the fields defined in both records are kept, and recursively merged.
+    Intersect,
+    /// Numbers are added.
+    Sum,
+    /// The greatest number is kept.
+    Max,
+    /// The smallest number is kept.
+    Min,
+    /// Records with a sealed polymorphic tail are unsealed before being merged, exposing the
+    /// fields of their tail. This gives up on parametricity, see [crate::eval::merge].
+    Unsealed,
+    /// Arrays of the same length can be merged if the comparator, a function of two arguments
+    /// returning a boolean, holds for each pair of elements. The comparator is evaluated in the
+    /// initial environment: it can only refer to the standard library.
+    Compare { comparator: RichTerm },
+    /// Records can only be merged if they don't define any common field. Overlapping fields are
+    /// an error instead of being recursively merged.
+    Disjoint,
+    /// The values are merged by a function registered by the host application under the given
+    /// name, selected by a `merge "name"` annotation. See
+    /// [`crate::eval::EvalConfig::register_merge_fn`].
+    Custom { name: String },
+    /// Two different numbers, strings, booleans or enum tags of the same priority are resolved
+    /// by keeping the value of the left operand, instead of failing. This overrides
+    /// [`crate::eval::EvalConfig::conflict_policy`] for the annotated field.
+    Left,
+    /// Same as [MergeStrategy::Left], but the value of the right operand is kept.
+    Right,
+}
+
+impl MergeStrategy {
+    /// Return the strategy corresponding to a name as written in a `merge` annotation, or `None`
+    /// if there is no such strategy.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(MergeStrategy::Standard),
+            "element-wise" => Some(MergeStrategy::ElementWise),
+            "set" => Some(MergeStrategy::Set),
+            "broadcast" => Some(MergeStrategy::Broadcast),
+            "concat" => Some(MergeStrategy::Concat { separator: None }),
+            "intersect" => Some(MergeStrategy::Intersect),
+            "sum" => Some(MergeStrategy::Sum),
+            "max" => Some(MergeStrategy::Max),
+            "min" => Some(MergeStrategy::Min),
+            "unsealed" => Some(MergeStrategy::Unsealed),
+            "disjoint" => Some(MergeStrategy::Disjoint),
+            "left" => Some(MergeStrategy::Left),
+            "right" => Some(MergeStrategy::Right),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeStrategy::Standard => write!(f, "standard"),
+            MergeStrategy::ElementWise => write!(f, "element-wise"),
+            MergeStrategy::Set => write!(f, "set"),
+            MergeStrategy::Broadcast => write!(f, "broadcast"),
+            MergeStrategy::Concat { .. } => write!(f, "concat"),
+            MergeStrategy::Intersect => write!(f, "intersect"),
+            MergeStrategy::Sum => write!(f, "sum"),
+            MergeStrategy::Max => write!(f, "max"),
+            MergeStrategy::Min => write!(f, "min"),
+            MergeStrategy::Unsealed => write!(f, "unsealed"),
+            MergeStrategy::Compare { .. } => write!(f, "compare"),
+            MergeStrategy::Disjoint => write!(f, "disjoint"),
+            MergeStrategy::Custom { name } => write!(f, "\"{name}\""),
+            MergeStrategy::Left => write!(f, "left"),
+            MergeStrategy::Right => write!(f, "right"),
+        }
+    }
+}
+
 /// A type or a contract together with its corresponding label.
 #[derive(Debug, PartialEq, Clone)]
 pub struct LabeledType {
@@ -1072,6 +1165,12 @@ pub enum UnaryOp {
     /// tail of its argument.
     RecordEmptyWithTail(),
 
+    /// Freeze a record, such that merging can't add new fields to it. See
+    /// [`record::RecordAttrs::frozen`].
+    ///
+    /// Generated by the program transformations from a `frozen` field annotation.
+    RecordFreeze(),
+
     /// Print a message when encountered during evaluation and proceed with the evaluation of the argument
     /// on the top of the stack. Operationally the same as the identity
     /// function
@@ -1209,6 +1308,9 @@ pub enum BinaryOp {
     DynAccess(),
     /// Test if a record has a specific field.
     HasField(),
+    /// Return the names of the fields defined in both of two records, in alphabetical order. As
+    /// for `FieldsOf`, optional fields without a definition are ignored.
+    RecordCommonFields(),
     /// Concatenate two arrays.
     ArrayConcat(),
     /// Access the n-th element of an array.
@@ -1218,6 +1320,16 @@ pub enum BinaryOp {
     /// purpose.
     Merge(MergeLabel),
 
+    /// Merge two records, filling the first one with the fields of the second one it doesn't
+    /// define (see [crate::eval::merge::MergeMode::FillDefaults]). Parametrized by a
+    /// [crate::label::MergeLabel] like `Merge`.
+    MergeFillDefaults(MergeLabel),
+
+    /// Merge two records recursively, the left operand winning over the right one for the values
+    /// which aren't both records (see [crate::eval::merge::MergeMode::LeftBiased]). Parametrized
+    /// by a [crate::label::MergeLabel] like `Merge`.
+    MergeLeftBiased(MergeLabel),
+
     /// Hash a string.
     Hash(),
     /// Serialize a value to a string.
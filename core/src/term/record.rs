@@ -1,19 +1,83 @@
 use super::*;
-use crate::{error::EvalError, identifier::Ident, label::Label};
+use crate::{
+    error::{EvalError, MergeSide},
+    identifier::Ident,
+    label::Label,
+};
 use std::{collections::HashSet, rc::Rc};
 
 /// Additional attributes for record.
-#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Default, Eq, Copy, Clone)]
 pub struct RecordAttrs {
     /// If the record is an open record, ie ending with `..`. Open records have a different
     /// behavior when used as a record contract: they allow additional fields to be present.
     pub open: bool,
+    /// The number of records which have been merged together to produce this record, or `0` if
+    /// this record isn't the result of a merge. This is purely informative (it is reported by
+    /// metadata queries) and is ignored by equality.
+    pub merged_from: usize,
+    /// If the record is frozen, typically by a `frozen` annotation on the field it's the value
+    /// of. The fields of a frozen record can be overridden by merging, but merging can't add new
+    /// fields to it.
+    pub frozen: bool,
+}
+
+impl PartialEq for RecordAttrs {
+    fn eq(&self, other: &Self) -> bool {
+        self.open == other.open && self.frozen == other.frozen
+    }
 }
 
 impl RecordAttrs {
+    /// Combine the attributes of two merged records. The merge of two record contracts is open as
+    /// soon as one of them is open, such that `Contract & {..}` is an open version of `Contract`.
+    ///
+    /// Applying the merge of two contracts `A & B` to a value is thus equivalent to applying `B`
+    /// and then `A` when both are open or when they define the same fields. Otherwise, the
+    /// sequential application is stricter: each closed contract rejects the fields it doesn't
+    /// list, including the fields which are given a value by the other contract.
+    ///
+    /// The merge of two records is frozen as soon as one of them is. Merging can't extend a frozen
+    /// record, which is checked beforehand by [RecordAttrs::check_extension].
+    ///
+    /// The merge counter of the first record is kept as it is: applying a record contract or
+    /// filling default values doesn't merge records together in the user's sense. Standard merges
+    /// use [RecordAttrs::merge_counted] instead.
     pub fn merge(attrs1: RecordAttrs, attrs2: RecordAttrs) -> RecordAttrs {
         RecordAttrs {
             open: attrs1.open || attrs2.open,
+            merged_from: attrs1.merged_from,
+            frozen: attrs1.frozen || attrs2.frozen,
+        }
+    }
+
+    /// Same as [RecordAttrs::merge], but count both records in the merge counter of the result. A
+    /// record which isn't the result of a merge counts as one.
+    pub fn merge_counted(attrs1: RecordAttrs, attrs2: RecordAttrs) -> RecordAttrs {
+        RecordAttrs {
+            merged_from: attrs1
+                .merged_from
+                .max(1)
+                .saturating_add(attrs2.merged_from.max(1)),
+            ..RecordAttrs::merge(attrs1, attrs2)
+        }
+    }
+
+    /// Check that merging two records doesn't extend a frozen one. `extends1` tells if the second
+    /// record defines fields which the first one doesn't, and conversely for `extends2`. Return
+    /// the side of the frozen record which would be extended otherwise.
+    pub fn check_extension(
+        attrs1: RecordAttrs,
+        attrs2: RecordAttrs,
+        extends1: bool,
+        extends2: bool,
+    ) -> Result<(), MergeSide> {
+        if attrs1.frozen && extends1 {
+            Err(MergeSide::Left)
+        } else if attrs2.frozen && extends2 {
+            Err(MergeSide::Right)
+        } else {
+            Ok(())
         }
     }
 }
@@ -73,6 +137,33 @@ pub struct RecordDeps {
     pub dyn_fields: Vec<FieldDeps>,
 }
 
+/// How the priority of a field resulting from a merge has been determined. This is purely
+/// informative, and is reported by metadata queries.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PriorityResolution {
+    /// Both definitions have a value of the same priority, and the values have been merged.
+    MergedEqual,
+    /// The value of the right definition has a higher priority and overrode the left one.
+    OverrodeLeft,
+    /// The value of the left definition has a higher priority and overrode the right one.
+    OverrodeRight,
+    /// At most one of the definitions has a value, which is kept together with its priority.
+    DefaultOnly,
+}
+
+impl std::fmt::Display for PriorityResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriorityResolution::MergedEqual => write!(f, "merged definitions of equal priority"),
+            PriorityResolution::OverrodeLeft => write!(f, "right definition overrode the left one"),
+            PriorityResolution::OverrodeRight => {
+                write!(f, "left definition overrode the right one")
+            }
+            PriorityResolution::DefaultOnly => write!(f, "single definition with a value"),
+        }
+    }
+}
+
 /// The metadata attached to record fields.
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct FieldMetadata {
@@ -80,9 +171,34 @@ pub struct FieldMetadata {
     pub annotation: TypeAnnotation,
     /// If the field is optional.
     pub opt: bool,
+    /// If the field is made optional by a `relax_optional` annotation. When merged with a
+    /// definition of lower or equal priority which requires the field, the result is still
+    /// optional.
+    pub relax_opt: bool,
+    /// If the value of the field is frozen by a `frozen` annotation, see [RecordAttrs::frozen].
+    pub frozen: bool,
     /// If the field is serialized.
     pub not_exported: bool,
     pub priority: MergePriority,
+    /// The merge strategy selected by a `merge` annotation, if any.
+    pub merge_strategy: Option<MergeStrategy>,
+    /// The positions of the definitions of this field, if it is the result of merging several
+    /// definitions together. Empty otherwise.
+    pub definition_sites: Vec<TermPos>,
+    /// The documentations of the definitions of this field together with the position of each
+    /// definition, if it is the result of merging several definitions together. Empty otherwise.
+    /// `doc` is the documentation of the merged field, which only keeps one of them.
+    pub doc_sources: Vec<(TermPos, String)>,
+    /// The number of definitions merged into this field, if it is the result of merging several
+    /// definitions together. Zero otherwise. This includes the definitions which are overridden
+    /// because of their lower priority.
+    pub contributions: usize,
+    /// How the priority of this field has been determined, if it is the result of a merge.
+    pub priority_resolution: Option<PriorityResolution>,
+    /// If this field was only defined in the right operand of the merge which produced it, that
+    /// is if it was added by the latest overlay. Only tracked when
+    /// [crate::eval::EvalConfig::track_introduced_fields] is enabled.
+    pub introduced: bool,
 }
 
 impl FieldMetadata {
@@ -132,8 +248,24 @@ impl FieldMetadata {
                 contracts: outer.annotation.contracts,
             },
             opt: outer.opt || inner.opt,
+            relax_opt: outer.relax_opt || inner.relax_opt,
+            frozen: outer.frozen || inner.frozen,
             not_exported: outer.not_exported || inner.not_exported,
             priority,
+            merge_strategy: outer.merge_strategy.or(inner.merge_strategy),
+            definition_sites: outer
+                .definition_sites
+                .into_iter()
+                .chain(inner.definition_sites)
+                .collect(),
+            doc_sources: outer
+                .doc_sources
+                .into_iter()
+                .chain(inner.doc_sources)
+                .collect(),
+            contributions: outer.contributions + inner.contributions,
+            priority_resolution: outer.priority_resolution.or(inner.priority_resolution),
+            introduced: outer.introduced || inner.introduced,
         }
     }
 }
@@ -144,7 +276,7 @@ pub struct Field {
     /// The value is optional because record field may not have a definition (e.g. optional fields).
     pub value: Option<RichTerm>,
     pub metadata: FieldMetadata,
-    /// List of contracts yet to be applied.
+    /// List of contracts yet to be applied, in order (see [RuntimeContract::apply_all]).
     /// These are only observed when data enter or leave the record.
     pub pending_contracts: Vec<RuntimeContract>,
 }
@@ -159,6 +291,43 @@ impl From<RichTerm> for Field {
 }
 
 impl Field {
+    /// Return the positions of the definitions of this field: the ones accumulated by previous
+    /// merges if any, or the position of its value otherwise.
+    pub fn definition_sites(&self) -> Vec<TermPos> {
+        if self.metadata.definition_sites.is_empty() {
+            self.value
+                .iter()
+                .map(|value| value.pos)
+                .filter(TermPos::is_def)
+                .collect()
+        } else {
+            self.metadata.definition_sites.clone()
+        }
+    }
+
+    /// Return the number of definitions of this field: the one accumulated by previous merges if
+    /// any, or one otherwise.
+    pub fn contributions(&self) -> usize {
+        self.metadata.contributions.max(1)
+    }
+
+    /// Return the documentations of the definitions of this field with the position of each
+    /// definition: the ones accumulated by previous merges if any, or its own documentation
+    /// otherwise.
+    pub fn doc_sources(&self) -> Vec<(TermPos, String)> {
+        if self.metadata.doc_sources.is_empty() {
+            let pos = self.value.as_ref().map_or(TermPos::None, |value| value.pos);
+
+            self.metadata
+                .doc
+                .iter()
+                .map(|doc| (pos, doc.clone()))
+                .collect()
+        } else {
+            self.metadata.doc_sources.clone()
+        }
+    }
+
     /// Map a function over the value of the field, if any.
     pub fn map_value(self, f: impl FnOnce(RichTerm) -> RichTerm) -> Self {
         Field {
@@ -211,6 +380,12 @@ impl Traverse<RichTerm> for Field {
         F: Fn(RichTerm, &mut S) -> Result<RichTerm, E>,
     {
         let annotation = self.metadata.annotation.traverse(f, state, order)?;
+        let merge_strategy = match self.metadata.merge_strategy {
+            Some(MergeStrategy::Compare { comparator }) => Some(MergeStrategy::Compare {
+                comparator: comparator.traverse(f, state, order)?,
+            }),
+            merge_strategy => merge_strategy,
+        };
         let value = self
             .value
             .map(|v| v.traverse(f, state, order))
@@ -218,6 +393,7 @@ impl Traverse<RichTerm> for Field {
 
         let metadata = FieldMetadata {
             annotation,
+            merge_strategy,
             ..self.metadata
         };
 
@@ -307,6 +483,24 @@ impl RecordData {
         }
     }
 
+    /// Returns the record resulting from prefixing the name of each field with `prefix`, for
+    /// example to namespace the fields of a record before merging it with another one.
+    ///
+    /// Only the top-level field names are rewritten: the metadata, the values and the pending
+    /// contracts of the fields are preserved, as well as the attributes and the sealed tail of the
+    /// record. Note that recursive references to the renamed fields are not rewritten. This
+    /// function should thus be used on evaluated records, where recursive references have
+    /// already been resolved, rather than on recursive record literals.
+    pub fn prefix_fields(self, prefix: &str) -> Self {
+        let fields = self
+            .fields
+            .into_iter()
+            .map(|(id, field)| (Ident::from(format!("{prefix}{}", id.label())), field))
+            .collect();
+
+        RecordData { fields, ..self }
+    }
+
     /// Returns the record resulting from applying the provided function
     /// to each field.
     ///
@@ -469,6 +663,13 @@ impl SealedTail {
         }
     }
 
+    /// Returns the sealed term without checking the sealing key. This breaks the parametricity
+    /// guarantees of the polymorphic contract which sealed the tail, and must only be used when
+    /// explicitly requested by the user (see [crate::term::MergeStrategy::Unsealed]).
+    pub fn into_unsealed(self) -> RichTerm {
+        self.term
+    }
+
     pub fn has_field(&self, field: &Ident) -> bool {
         self.fields.contains(field)
     }
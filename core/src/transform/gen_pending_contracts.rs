@@ -16,7 +16,7 @@ use crate::{
     match_sharedterm,
     term::{
         record::{Field, RecordData},
-        IndexMap, RichTerm, RuntimeContract, Term,
+        IndexMap, RichTerm, RuntimeContract, Term, UnaryOp,
     },
     types::UnboundTypeVariableError,
 };
@@ -27,9 +27,19 @@ pub fn transform_one(rt: RichTerm) -> Result<RichTerm, UnboundTypeVariableError>
         let pending_contracts = field.metadata.annotation.pending_contracts()?;
         // Type annotations are different: the contract is generated statically, because as opposed
         // to contract annotations, type anntotations don't propagate.
+        //
+        // A `frozen` annotation is applied statically as well: it only freezes the value of this
+        // definition, which is then merged with the other definitions of the field as usual.
         let value = field
             .value
             .map(|v| -> Result<RichTerm, UnboundTypeVariableError> {
+                let v = if field.metadata.frozen {
+                    let pos = v.pos;
+                    RichTerm::new(Term::Op1(UnaryOp::RecordFreeze(), v), pos)
+                } else {
+                    v
+                };
+
                 if let Some(labeled_ty) = &field.metadata.annotation.types {
                     let pos = v.pos;
                     let contract = RuntimeContract::try_from(labeled_ty.clone())?;
@@ -194,6 +194,11 @@ pub fn get_uop_type(
             (ty.clone(), ty)
         }
         UnaryOp::RecordEmptyWithTail() => (mk_uniftype::dynamic(), mk_uniftype::dynamic()),
+        // forall a. a -> a
+        UnaryOp::RecordFreeze() => {
+            let ty = state.table.fresh_type_uvar();
+            (ty.clone(), ty)
+        }
 
         // forall a. Str -> a -> a
         UnaryOp::Trace() => {
@@ -307,6 +312,16 @@ pub fn get_bop_type(
                 mk_uniftype::bool(),
             )
         }
+        // forall a b. {_: a} -> {_: b} -> Array Str
+        BinaryOp::RecordCommonFields() => {
+            let ty_elt1 = UnifType::UnifVar(state.table.fresh_type_var_id());
+            let ty_elt2 = UnifType::UnifVar(state.table.fresh_type_var_id());
+            (
+                mk_uniftype::dict(ty_elt1),
+                mk_uniftype::dict(ty_elt2),
+                mk_uniftype::array(mk_uniftype::str()),
+            )
+        }
         // forall a. Array a -> Array a -> Array a
         BinaryOp::ArrayConcat() => {
             let ty_elt = UnifType::UnifVar(state.table.fresh_type_var_id());
@@ -322,6 +337,12 @@ pub fn get_bop_type(
                 ty_elt,
             )
         }
+        // {_: Dyn} -> {_: Dyn} -> {_: Dyn}
+        BinaryOp::MergeFillDefaults(_) | BinaryOp::MergeLeftBiased(_) => (
+            mk_uniftype::dict(mk_uniftype::dynamic()),
+            mk_uniftype::dict(mk_uniftype::dynamic()),
+            mk_uniftype::dict(mk_uniftype::dynamic()),
+        ),
         // Dyn -> Dyn -> Dyn
         BinaryOp::Merge(_) => (
             mk_uniftype::dynamic(),
@@ -0,0 +1,123 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use nickel_lang_core::serialize::{self, ExportFormat};
+use nickel_lang_utils::test_program::TestProgram;
+
+/// A global allocator keeping track of the peak memory usage. This is why these tests live in a
+/// binary of their own: tests running concurrently would skew the measurements.
+struct PeakAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+impl PeakAlloc {
+    fn grow(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(current, Ordering::Relaxed);
+    }
+
+    fn shrink(size: usize) {
+        CURRENT.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        PeakAlloc::grow(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        PeakAlloc::shrink(layout.size());
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() {
+            PeakAlloc::grow(new_size - layout.size());
+        } else {
+            PeakAlloc::shrink(layout.size() - new_size);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+/// A merge of two records of `FIELDS` fields each. Each field of the result holds a string of a
+/// few hundred kilobytes, which is only computed when the field is evaluated.
+const SOURCE: &str = r#"
+let fields = 100 in
+let payload = fun i =>
+  std.array.fold_left (fun s _ => s ++ s) "%{std.string.from_number i}-" (std.array.range 0 16)
+in
+let base =
+  std.array.generate
+    (fun i => { field = "f%{std.string.from_number i}", value = { name = payload i, index = i } })
+    fields
+  |> std.record.from_array
+in
+let overlay =
+  std.array.generate
+    (fun i => { field = "f%{std.string.from_number i}", value = { enabled = true } })
+    fields
+  |> std.record.from_array
+in
+base & overlay
+"#;
+
+/// Export [SOURCE] to JSON and return the peak memory usage of the export, relative to the memory
+/// used before it started.
+fn export_peak(streaming: bool) -> usize {
+    let mut program =
+        TestProgram::new_from_source(SOURCE.as_bytes(), "export_memory", io::stderr()).unwrap();
+    // Parsing and typechecking the program and the standard library isn't what we measure.
+    program.typecheck().unwrap();
+
+    let before = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+
+    if streaming {
+        program
+            .export_streaming(ExportFormat::Json, io::sink())
+            .unwrap();
+    } else {
+        let rt = program.eval_full_for_export().unwrap();
+        serialize::validate(ExportFormat::Json, &rt).unwrap();
+        serialize::to_writer(io::sink(), ExportFormat::Json, &rt).unwrap();
+    }
+
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn streaming_export_has_bounded_peak_memory() {
+    // Each payload of [SOURCE] is the prefix `{i}-` doubled 16 times, which is between
+    // `2 * 2^16` bytes (for `0-`) and `3 * 2^16` bytes (for `99-`), that is 128 to 192 KiB.
+    const MIN_PAYLOAD: usize = 2 << 16;
+    const MAX_PAYLOAD: usize = 3 << 16;
+
+    let materialized = export_peak(false);
+    let streamed = export_peak(true);
+
+    // The materialized result holds the 100 payloads at once, which is at least 12.5 MiB.
+    assert!(
+        materialized >= 100 * MIN_PAYLOAD,
+        "standard export peaked at {materialized} bytes, which doesn't hold every payload"
+    );
+
+    // The streaming export only holds one payload at a time, along with the string it's built
+    // from, which amounts to less than two payloads. We leave as much room again for the
+    // reallocations of the concatenation and for the evaluation itself, and round up to 8
+    // payloads, that is 1.5 MiB: still eight times less than the materialized result.
+    assert!(
+        streamed < 8 * MAX_PAYLOAD,
+        "streaming export peaked at {streamed} bytes, against {materialized} bytes for the \
+        standard export"
+    );
+}
@@ -56,3 +56,22 @@ fn dictionary() {
         err => panic!("expected blame error, got {err:#?}"),
     }
 }
+
+#[test]
+fn record_disjoint_with_lists_common_fields() {
+    let res = eval(
+        "{timeout = 10, retries = 3, name = \"a\"} \
+        | std.record.disjoint_with {timeout = 20, retries = 5, level = 1}",
+    );
+    match &res {
+        Err(Error::EvalError(EvalError::BlameError { ref label, .. })) => {
+            assert_eq!(
+                label
+                    .current_diagnostic()
+                    .and_then(|diagnostic| diagnostic.message.as_deref()),
+                Some("fields defined on both sides: `retries`, `timeout`")
+            )
+        }
+        err => panic!("expected blame error, got {err:?}"),
+    }
+}
@@ -0,0 +1,454 @@
+//! Tests of the evaluator configuration knobs ([EvalConfig]) which tune or restrict merging.
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    eval::{
+        merge::{KeyNormalization, MergeStats},
+        EvalConfig,
+    },
+    identifier::Ident,
+    term::{make as mk_term, RichTerm, Term},
+};
+
+use crate::util::{eval_with, program_with};
+
+/// Generate the fields `{prefix}0` to `{prefix}{size - 1}`, separated by commas.
+fn fields(prefix: &str, size: usize) -> String {
+    (0..size)
+        .map(|i| format!("{prefix}{i} = {i}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Generate a program merging two records with `size` distinct fields each.
+fn large_merge(size: usize) -> String {
+    format!("{{{}}} & {{{}}}", fields("a", size), fields("b", size))
+}
+
+/// Return the field names of a record, in order.
+fn field_names(term: &RichTerm) -> Vec<String> {
+    match term.as_ref() {
+        Term::Record(record) => record.fields.keys().map(|id| id.to_string()).collect(),
+        _ => panic!("expected a record, got {term}"),
+    }
+}
+
+fn merge_fuel(merge_fuel: Option<usize>) -> EvalConfig {
+    EvalConfig {
+        merge_fuel,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn merges_unlimited_by_default() {
+    assert!(eval_with(&large_merge(100), EvalConfig::default()).is_ok());
+}
+
+#[test]
+fn merge_fuel_within_budget() {
+    assert!(eval_with(&large_merge(10), merge_fuel(Some(1000))).is_ok());
+}
+
+#[test]
+fn merge_fuel_exhausted() {
+    assert_matches!(
+        eval_with(&large_merge(100), merge_fuel(Some(50))),
+        Err(Error::EvalError(EvalError::MergeFuelExhausted {
+            budget: 50,
+            ..
+        }))
+    );
+}
+
+#[test]
+fn merge_fuel_exhausted_by_empty_record_merge() {
+    let source = format!("{{}} & {{{}}}", fields("a", 100));
+    assert_matches!(
+        eval_with(&source, merge_fuel(Some(50))),
+        Err(Error::EvalError(EvalError::MergeFuelExhausted {
+            budget: 50,
+            ..
+        }))
+    );
+}
+
+fn max_fields(max_fields: Option<usize>) -> EvalConfig {
+    EvalConfig {
+        max_fields,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn max_fields_within_cap() {
+    // Common fields are only counted once.
+    let source = format!("{{{}}} & {{{}}}", fields("a", 10), fields("a", 10));
+    assert!(eval_with(&source, max_fields(Some(10))).is_ok());
+}
+
+#[test]
+fn max_fields_exceeded() {
+    assert_matches!(
+        eval_with(&large_merge(10), max_fields(Some(15))),
+        Err(Error::EvalError(EvalError::RecordTooLarge {
+            fields: 20,
+            max_fields: 15,
+            ..
+        }))
+    );
+}
+
+#[test]
+fn max_fields_exceeded_by_empty_record_merge() {
+    let source = format!("{{}} & {{{}}}", fields("a", 20));
+    assert_matches!(
+        eval_with(&source, max_fields(Some(15))),
+        Err(Error::EvalError(EvalError::RecordTooLarge {
+            fields: 20,
+            max_fields: 15,
+            ..
+        }))
+    );
+}
+
+#[test]
+fn max_fields_exceeded_in_nested_record() {
+    let source = format!(
+        "{{inner = {{{}}}}} & {{inner = {{{}}}}}",
+        fields("a", 10),
+        fields("b", 10)
+    );
+
+    match eval_with(&source, max_fields(Some(15))) {
+        Err(Error::EvalError(err @ EvalError::RecordTooLarge { fields: 20, .. })) => {
+            assert_eq!(
+                err.merge_label().unwrap().path.to_vec(),
+                vec![Ident::from("inner")]
+            );
+        }
+        other => panic!("expected a record too large error, got {other:?}"),
+    }
+}
+
+fn merge_stats(source: &str, config: EvalConfig) -> (RichTerm, Option<MergeStats>) {
+    let mut program = program_with(source, config);
+    let result = program.eval_full().unwrap();
+    (result, program.merge_stats())
+}
+
+fn collect_merge_stats() -> EvalConfig {
+    EvalConfig {
+        collect_merge_stats: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn merge_stats_disabled_by_default() {
+    let (_, stats) = merge_stats("{a = 1} & {b = 2}", EvalConfig::default());
+    assert_eq!(stats, None);
+}
+
+#[test]
+fn merge_stats_count_merges() {
+    let (_, stats) = merge_stats("{a.b = 1, c = 1} & {a.d = 2, e = 1}", collect_merge_stats());
+    let stats = stats.unwrap();
+
+    // The top-level merge and the merge of the two `a` fields
+    assert_eq!(stats.record_merges, 2);
+    // `a`, `c` and `e` at the top level, then `b` and `d`
+    assert_eq!(stats.fields_split, 5);
+    assert_eq!(stats.field_merges, 1);
+    assert_eq!(stats.reverts, 4);
+}
+
+#[test]
+fn merge_stats_contract_passthrough() {
+    let (_, stats) = merge_stats(
+        "{a = 1, b = 2, c = a + 1} | {a | Number, ..}",
+        collect_merge_stats(),
+    );
+    let stats = stats.unwrap();
+
+    assert_eq!(stats.record_merges, 1);
+    assert_eq!(stats.field_merges, 1);
+    // `b` isn't recursive and isn't mentioned by the contract: it's passed through as it is. `c`
+    // depends on `a` and must be reverted, as well as the value of `a`.
+    assert_eq!(stats.reverts, 2);
+}
+
+#[test]
+fn merge_stats_skip_flat_reverts() {
+    let config = || EvalConfig {
+        skip_flat_reverts: true,
+        ..collect_merge_stats()
+    };
+
+    // None of the fields depends on another one: nothing is reverted.
+    let (_, stats) = merge_stats("{a.b = 1, c = 1} & {a.d = 2, e = 1}", config());
+    let stats = stats.unwrap();
+    assert_eq!(stats.record_merges, 2);
+    assert_eq!(stats.reverts, 0);
+
+    // `c` depends on `a` and must still be reverted, as well as the value of `a`, such that the
+    // override is propagated.
+    let (result, stats) = merge_stats(
+        "({a = 1, b = 2, c = a + 1} & {a | force = 5, d = 1}).c",
+        config(),
+    );
+    assert_eq!(stats.unwrap().reverts, 2);
+    assert_eq!(result.without_pos(), mk_term::integer(6));
+}
+
+#[test]
+fn merge_stats_export_at_path() {
+    let source = "{
+        config.services.web = {a = 1} & {b = 2},
+        config.services.db = {a = 1} & {b = 2},
+        config.other = {a = 1} & {b = 2},
+    }";
+
+    let record_merges = |path: Option<&str>| {
+        let mut program = program_with(source, collect_merge_stats());
+        program
+            .eval_full_for_export_at(path.map(String::from))
+            .unwrap();
+        program.merge_stats().unwrap().record_merges
+    };
+
+    // The merges of `config.services.db` and `config.other` aren't forced when only exporting
+    // `config.services.web`.
+    assert_eq!(
+        record_merges(None) - record_merges(Some("config.services.web")),
+        2
+    );
+}
+
+#[test]
+fn field_timings() {
+    let source = "{a = {x = 1}, b = 2} & {a = {y = 1}, c = 3}";
+    let timed_fields = |collect_field_timings: bool| {
+        let mut program = program_with(
+            source,
+            EvalConfig {
+                collect_field_timings,
+                ..Default::default()
+            },
+        );
+        program.eval_full().unwrap();
+
+        let mut fields: Vec<String> = program
+            .field_timings()
+            .into_iter()
+            .map(|(id, _)| id.to_string())
+            .collect();
+        fields.sort();
+        fields
+    };
+
+    assert!(timed_fields(false).is_empty());
+    // The nested fields `x` and `y` are attributed to the top-level field `a`.
+    assert_eq!(timed_fields(true), ["a", "b", "c"]);
+}
+
+/// Convert camel case names to snake case.
+fn snake_case(key: &str) -> String {
+    let mut normalized = String::with_capacity(key.len());
+
+    for c in key.chars() {
+        if c.is_uppercase() {
+            normalized.push('_');
+            normalized.extend(c.to_lowercase());
+        } else {
+            normalized.push(c);
+        }
+    }
+
+    normalized
+}
+
+fn eval_normalized(source: &str) -> Result<RichTerm, Error> {
+    eval_with(
+        source,
+        EvalConfig {
+            key_normalization: Some(KeyNormalization::new(snake_case)),
+            ..Default::default()
+        },
+    )
+}
+
+/// Return the sorted field names of a record.
+fn sorted_field_names(term: &RichTerm) -> Vec<String> {
+    let mut names = field_names(term);
+    names.sort();
+    names
+}
+
+#[test]
+fn fields_match_under_normalization() {
+    let result = eval_normalized("{fooBar = 1, a = 1} & {foo_bar = 1, b = 2}").unwrap();
+
+    // The merged field keeps the name of the left one.
+    assert_eq!(sorted_field_names(&result), vec!["a", "b", "fooBar"]);
+
+    // Matched fields are merged as usual.
+    assert_matches!(
+        eval_normalized("{fooBar = 1} & {foo_bar = 2}"),
+        Err(Error::EvalError(EvalError::MergeIncompatibleArgs { .. }))
+    );
+    let result = eval_normalized("({fooBar | default = 1} & {foo_bar = 2}).fooBar").unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(2));
+}
+
+#[test]
+fn nested_fields_match_under_normalization() {
+    let result = eval_normalized(
+        "({server = {listenPort = 80}} & {server = {listen_port = 80, host = \"a\"}}).server",
+    )
+    .unwrap();
+
+    assert_eq!(sorted_field_names(&result), vec!["host", "listenPort"]);
+}
+
+#[test]
+fn unmatched_fields_keep_their_names() {
+    let result = eval_normalized("{fooBar = 1} & {foo_baz = 2}").unwrap();
+    assert_eq!(sorted_field_names(&result), vec!["fooBar", "foo_baz"]);
+
+    // Without normalization, fields with different names are never merged.
+    let result = eval_with("{fooBar = 1} & {foo_bar = 2}", EvalConfig::default()).unwrap();
+    assert_eq!(sorted_field_names(&result), vec!["fooBar", "foo_bar"]);
+}
+
+#[test]
+fn colliding_fields_are_rejected() {
+    let result = eval_normalized("{fooBar = 1, foo_bar = 2} & {baz = 3}");
+
+    match result {
+        Err(Error::EvalError(err @ EvalError::MergeKeyCollision { .. })) => {
+            assert!(err.is_merge_error());
+            assert_matches!(
+                err,
+                EvalError::MergeKeyCollision { first, second, .. }
+                    if first.label() == "fooBar" && second.label() == "foo_bar"
+            );
+        }
+        result => panic!("expected a key collision, got {result:?}"),
+    }
+
+    // Collisions in the right record are rejected as well.
+    assert_matches!(
+        eval_normalized("{baz = 3} & {fooBar = 1, foo_bar = 2}"),
+        Err(Error::EvalError(EvalError::MergeKeyCollision { .. }))
+    );
+}
+
+fn strict_contracts() -> EvalConfig {
+    EvalConfig {
+        strict_contracts: true,
+        ..Default::default()
+    }
+}
+
+/// A configuration whose `server` field breaks its contract, but which is never used.
+fn server_config(port: &str) -> String {
+    format!(
+        "let base = {{ server = {{ port | Number, host | String }} }} in
+        let overlay = {{ server = {{ port = {port}, host = \"localhost\" }}, name = \"web\" }} in
+        (base & overlay).name"
+    )
+}
+
+#[test]
+fn lazy_contracts_by_default() {
+    assert!(!EvalConfig::default().strict_contracts);
+    let result = eval_with(&server_config("\"8080\""), EvalConfig::default()).unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("web"));
+}
+
+#[test]
+fn strict_contracts_are_checked_at_merge_time() {
+    assert_matches!(
+        eval_with(&server_config("\"8080\""), strict_contracts()),
+        Err(Error::EvalError(EvalError::BlameError { .. }))
+    );
+
+    // Valid configurations are unaffected.
+    let result = eval_with(&server_config("8080"), strict_contracts()).unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("web"));
+}
+
+fn case_insensitive_contracts() -> EvalConfig {
+    EvalConfig {
+        case_insensitive_contracts: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn contract_fields_matched_by_case_fold() {
+    let result = eval_with(
+        "{username = \"nick\", id = 1} | {userName | String, id | Number}",
+        case_insensitive_contracts(),
+    )
+    .unwrap();
+
+    // The matched field takes the casing of the contract.
+    assert_eq!(field_names(&result), vec!["userName", "id"]);
+
+    let result = eval_with(
+        "({USERNAME = \"nick\"} | {userName | String}).userName",
+        case_insensitive_contracts(),
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("nick"));
+}
+
+#[test]
+fn case_folded_contract_fields_are_checked() {
+    assert_matches!(
+        eval_with(
+            "{username = 1} | {userName | String}",
+            case_insensitive_contracts()
+        ),
+        Err(Error::EvalError(EvalError::BlameError { .. }))
+    );
+}
+
+#[test]
+fn contracts_case_sensitive_by_default() {
+    assert_matches!(
+        eval_with(
+            "{username = \"nick\"} | {userName | String}",
+            EvalConfig::default()
+        ),
+        Err(Error::EvalError(EvalError::BlameError { .. }))
+    );
+}
+
+#[test]
+fn standard_merges_are_case_sensitive() {
+    let result = eval_with(
+        "{username = 1} & {userName = 2}",
+        case_insensitive_contracts(),
+    )
+    .unwrap();
+    assert_eq!(field_names(&result), vec!["username", "userName"]);
+}
+
+#[test]
+fn case_folded_collision_in_value() {
+    match eval_with(
+        "{username = \"a\", USERNAME = \"b\"} | {userName | String}",
+        case_insensitive_contracts(),
+    ) {
+        Err(Error::EvalError(EvalError::MergeKeyCollision { first, second, .. })) => {
+            assert_eq!(
+                (first.to_string(), second.to_string()),
+                (String::from("username"), String::from("USERNAME"))
+            );
+        }
+        other => panic!("expected a key collision, got {other:?}"),
+    }
+}
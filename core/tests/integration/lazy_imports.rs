@@ -0,0 +1,62 @@
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError, ImportError},
+    term::make as mk_term,
+};
+use nickel_lang_utils::{project_root::project_root, test_program::TestProgram};
+
+fn lazy_program(file: &str) -> TestProgram {
+    let path = project_root()
+        .join("core/tests/integration/imports")
+        .join(file);
+    let mut program = TestProgram::new_from_file(path, std::io::stderr()).unwrap();
+    program.set_lazy_imports(true);
+    program
+}
+
+#[test]
+fn merge_with_lazy_import() {
+    let result = lazy_program("lazy_merge.ncl").eval_full().unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("localhost:8080"));
+}
+
+#[test]
+fn eager_imports_resolve_unused_imports() {
+    // The imported record refers to a missing file in a field which is never used. This is only
+    // fine when imports are lazy.
+    let path = project_root().join("core/tests/integration/imports/lazy_merge.ncl");
+    let mut program = TestProgram::new_from_file(path, std::io::stderr()).unwrap();
+    assert_matches!(
+        program.eval_full(),
+        Err(Error::ImportError(ImportError::IOError(..)))
+    );
+}
+
+#[test]
+fn lazy_import_of_non_record() {
+    let mut program = lazy_program("lazy_merge_non_record.ncl");
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeIncompatibleArgs { .. })
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("lazy_merge_non_record.ncl:2"), "{report}");
+}
+
+#[test]
+fn missing_lazy_import() {
+    let mut program = lazy_program("lazy_merge_missing.ncl");
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::ImportFailed(import_err))
+            if matches!(**import_err, Error::ImportError(ImportError::IOError(..)))
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("missing.ncl"), "{report}");
+}
@@ -13,10 +13,26 @@ use serde::Deserialize;
 use test_generator::test_resources;
 
 mod contract_label_path;
+mod eval_config;
 mod free_vars;
+mod lazy_imports;
+mod merge_cancellation;
+mod merge_conflict_policy;
+mod merge_contract_composition;
+mod merge_determinism;
+mod merge_doc_warnings;
+mod merge_enum_contracts;
+mod merge_errors;
+mod merge_fns;
+mod merge_observer;
+mod merge_preview;
+mod merge_report;
 mod pretty;
 mod query;
+mod record_contract_absent_fields;
+mod record_contract_diagnostics;
 mod stdlib_typecheck;
+mod util;
 
 #[test_resources("core/tests/integration/**/*.ncl")]
 fn check_annotated_nickel_file(path: &str) {
@@ -140,6 +156,8 @@ enum ErrorExpectation {
     EvalMissingFieldDef { field: String },
     #[serde(rename = "EvalError::MergeIncompatibleArgs")]
     EvalMergeIncompatibleArgs,
+    #[serde(rename = "EvalError::MergeArrayLengthMismatch")]
+    EvalMergeArrayLengthMismatch,
     #[serde(rename = "TypecheckError::UnboundIdentifier")]
     TypecheckUnboundIdentifier { identifier: String },
     #[serde(rename = "TypecheckError::UnboundTypeVariable")]
@@ -192,6 +210,10 @@ impl PartialEq<Error> for ErrorExpectation {
                 EvalMergeIncompatibleArgs,
                 Error::EvalError(EvalError::MergeIncompatibleArgs { .. }),
             )
+            | (
+                EvalMergeArrayLengthMismatch,
+                Error::EvalError(EvalError::MergeArrayLengthMismatch { .. }),
+            )
             | (EvalOther, Error::EvalError(EvalError::Other(..)))
             | (TypecheckRowMismatch, Error::TypecheckError(TypecheckError::RowMismatch(..)))
             | (
@@ -298,6 +320,7 @@ impl std::fmt::Display for ErrorExpectation {
             EvalEqError => "EvalError::EqError".to_owned(),
             EvalOther => "EvalError::Other".to_owned(),
             EvalMergeIncompatibleArgs => "EvalError::MergeIncompatibleArgs".to_owned(),
+            EvalMergeArrayLengthMismatch => "EvalError::MergeArrayLengthMismatch".to_owned(),
             EvalNAryPrimopTypeError => "EvalError::NAryPrimopTypeError".to_owned(),
             EvalInfiniteRecursion => "EvalError::InfiniteRecursion".to_owned(),
             EvalIllegalPolymorphicTailAccess => {
@@ -0,0 +1,73 @@
+use std::{cell::RefCell, rc::Rc};
+
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    eval::{
+        merge::{CancellationToken, FieldMergeEvent, MergeObserver},
+        EvalConfig,
+    },
+};
+
+use crate::util::{eval_with, program_with};
+
+/// An observer cancelling the evaluation as soon as the first field is merged.
+struct Canceller {
+    token: CancellationToken,
+    merged: usize,
+}
+
+impl MergeObserver for Canceller {
+    fn field_merged(&mut self, _event: FieldMergeEvent<'_>) {
+        self.merged += 1;
+        self.token.cancel();
+    }
+}
+
+/// Generate a program merging two records with the same `size` fields.
+fn shared_merge(size: usize) -> String {
+    let fields = (0..size)
+        .map(|i| format!("a{i} = {i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("{{{fields}}} & {{{fields}}}")
+}
+
+#[test]
+fn merge_is_cancelled_promptly() {
+    let token = CancellationToken::new();
+    let canceller = Rc::new(RefCell::new(Canceller {
+        token: token.clone(),
+        merged: 0,
+    }));
+    let mut program = program_with(
+        &shared_merge(100),
+        EvalConfig {
+            cancellation: Some(token.clone()),
+            ..Default::default()
+        },
+    );
+    program.set_merge_observer(Some(canceller.clone()));
+
+    assert_matches!(
+        program.eval_full(),
+        Err(Error::EvalError(EvalError::Cancelled))
+    );
+    assert!(token.is_cancelled());
+    // The merge stops at the field following the cancellation.
+    assert_eq!(canceller.borrow().merged, 1);
+}
+
+#[test]
+fn merge_completes_without_cancellation() {
+    let result = eval_with(
+        &shared_merge(100),
+        EvalConfig {
+            cancellation: Some(CancellationToken::new()),
+            ..Default::default()
+        },
+    );
+
+    assert!(result.is_ok());
+}
@@ -0,0 +1,144 @@
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    eval::{merge::ConflictPolicy, EvalConfig},
+    term::{make as mk_term, RichTerm},
+};
+
+use crate::util::eval_with;
+
+fn eval_with_policy(source: &str, conflict_policy: ConflictPolicy) -> Result<RichTerm, Error> {
+    eval_with(
+        source,
+        EvalConfig {
+            conflict_policy,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn error_policy_rejects_conflicts() {
+    for source in ["({a = 1} & {a = 2}).a", "({a = \"x\"} & {a = \"y\"}).a"] {
+        assert_matches!(
+            eval_with_policy(source, ConflictPolicy::Error),
+            Err(Error::EvalError(EvalError::MergeIncompatibleArgs { .. }))
+        );
+    }
+
+    // The error policy is the default one.
+    assert_eq!(EvalConfig::default().conflict_policy, ConflictPolicy::Error);
+}
+
+#[test]
+fn left_wins_policy() {
+    let result = eval_with_policy("({a = 1} & {a = 2}).a", ConflictPolicy::LeftWins).unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(1));
+
+    let result =
+        eval_with_policy("({a = \"x\"} & {a = \"y\"}).a", ConflictPolicy::LeftWins).unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("x"));
+}
+
+#[test]
+fn right_wins_policy() {
+    let result = eval_with_policy("({a = 1} & {a = 2}).a", ConflictPolicy::RightWins).unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(2));
+
+    let result =
+        eval_with_policy("({a = \"x\"} & {a = \"y\"}).a", ConflictPolicy::RightWins).unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("y"));
+}
+
+#[test]
+fn policy_only_applies_to_equal_priorities() {
+    let result =
+        eval_with_policy("({a | force = 1} & {a = 2}).a", ConflictPolicy::RightWins).unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(1));
+
+    // Values which can be combined are still combined.
+    let result = eval_with_policy(
+        "({a | merge sum = 1} & {a = 2}).a",
+        ConflictPolicy::LeftWins,
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(3));
+}
+
+#[test]
+fn strategy_overrides_policy() {
+    let result = eval_with_policy(
+        "({a | merge left = 1} & {a = 2}).a",
+        ConflictPolicy::RightWins,
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(1));
+
+    let result = eval_with_policy(
+        "({a | merge right = \"x\"} & {a = \"y\"}).a",
+        ConflictPolicy::Error,
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("y"));
+
+    // The strategy only applies to the annotated field, and not to its subfields.
+    assert_matches!(
+        eval_with_policy(
+            "({a | merge left = {b = 1}} & {a = {b = 2}}).a.b",
+            ConflictPolicy::Error
+        ),
+        Err(Error::EvalError(EvalError::MergeIncompatibleArgs { .. }))
+    );
+}
+
+fn eval_lenient_optional(source: &str) -> Result<RichTerm, Error> {
+    eval_with(
+        source,
+        EvalConfig {
+            lenient_optional_conflicts: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn lenient_optional_conflicts() {
+    // Both definitions are optional: the right one wins.
+    let result = eval_lenient_optional("({a | optional = 1} & {a | optional = 2}).a").unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(2));
+
+    let result =
+        eval_lenient_optional("({a | optional = {b = \"x\"}} & {a | optional = {b = \"y\"}}).a.b");
+    assert_matches!(
+        result,
+        Err(Error::EvalError(EvalError::MergeIncompatibleArgs { .. })),
+        "only the optional field itself is lenient, not its subfields"
+    );
+
+    // Piecewise definitions of an optional field are lenient as well.
+    let result = eval_lenient_optional("{a | optional = 1, a | optional = 2}.a").unwrap();
+    assert_eq!(result.without_pos(), mk_term::integer(2));
+
+    // Required fields still conflict, even if only one side is required.
+    for source in [
+        "({a = 1} & {a = 2}).a",
+        "({a | optional = 1} & {a = 2}).a",
+        "({a = 1} & {a | optional = 2}).a",
+    ] {
+        assert_matches!(
+            eval_lenient_optional(source),
+            Err(Error::EvalError(EvalError::MergeIncompatibleArgs { .. })),
+            "{source}"
+        );
+    }
+
+    // Without the option, optional fields conflict as usual.
+    assert_matches!(
+        eval_with_policy(
+            "({a | optional = 1} & {a | optional = 2}).a",
+            ConflictPolicy::Error
+        ),
+        Err(Error::EvalError(EvalError::MergeIncompatibleArgs { .. }))
+    );
+    assert!(!EvalConfig::default().lenient_optional_conflicts);
+}
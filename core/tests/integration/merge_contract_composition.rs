@@ -0,0 +1,136 @@
+use std::mem::discriminant;
+
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    term::RichTerm,
+};
+use nickel_lang_utils::test_program::TestProgram;
+
+fn eval(source: &str) -> Result<RichTerm, EvalError> {
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+
+    match program.eval_full() {
+        Ok(value) => Ok(value.without_pos()),
+        Err(Error::EvalError(err)) => Err(err),
+        Err(err) => panic!("unexpected error: {err:?}"),
+    }
+}
+
+/// Check that applying `a & b` to `value` behaves as applying `b` and then `a`: both succeed with
+/// the same result, or both fail with the same kind of error. Return the result of applying
+/// `a & b`.
+fn assert_composes(a: &str, b: &str, value: &str) -> Result<RichTerm, EvalError> {
+    let merged = eval(&format!("{value} | ({a} & {b})"));
+    let sequential = eval(&format!("({value} | {b}) | {a}"));
+
+    match (&merged, &sequential) {
+        (Ok(merged), Ok(sequential)) => assert_eq!(merged, sequential),
+        (Err(merged), Err(sequential)) => assert_eq!(
+            discriminant(merged),
+            discriminant(sequential),
+            "applying `{a} & {b}` to `{value}` fails with {merged:?}, but applying them in \
+            sequence fails with {sequential:?}"
+        ),
+        _ => panic!(
+            "applying `{a} & {b}` to `{value}` gives {merged:?}, but applying them in sequence \
+            gives {sequential:?}"
+        ),
+    }
+
+    merged
+}
+
+#[test]
+fn closed_contracts_with_the_same_fields() {
+    let a = "{a | Number, b | String}";
+    let b = "{a | Number, b | String | default = \"x\"}";
+
+    assert_matches!(assert_composes(a, b, "{a = 1}"), Ok(_));
+    assert_matches!(assert_composes(a, b, "{a = 1, b = \"y\"}"), Ok(_));
+    assert_matches!(
+        assert_composes(a, b, "{a = \"1\"}"),
+        Err(EvalError::BlameError { .. })
+    );
+    assert_matches!(
+        assert_composes(a, b, "{a = 1, c = 2}"),
+        Err(EvalError::BlameError { .. })
+    );
+}
+
+#[test]
+fn open_contracts() {
+    let a = "{a | Number, ..}";
+    let b = "{b | String | default = \"x\", ..}";
+
+    assert_matches!(assert_composes(a, b, "{a = 1, c = true}"), Ok(_));
+    assert_matches!(assert_composes(b, a, "{a = 1, c = true}"), Ok(_));
+    assert_matches!(
+        assert_composes(a, b, "{a = 1, b = 2}"),
+        Err(EvalError::BlameError { .. })
+    );
+}
+
+#[test]
+fn fields_required_by_the_other_contract() {
+    // The field `c` required by the open contract isn't defined by the value. It's reported as a
+    // missing field, and isn't rejected as an extra field by the closed contract.
+    assert_matches!(
+        assert_composes("{a | Number}", "{c | Number, ..}", "{a = 1}"),
+        Err(EvalError::MissingFieldDef { .. })
+    );
+    assert_matches!(
+        assert_composes("{a | Number}", "{c | Number | optional, ..}", "{a = 1}"),
+        Ok(_)
+    );
+}
+
+/// Return the message of the contract blamed when evaluating `source`.
+fn blamed_contract(source: &str) -> String {
+    match eval(source) {
+        Err(EvalError::BlameError { label, .. }) => label
+            .current_diagnostic()
+            .and_then(|diagnostic| diagnostic.message.clone())
+            .expect("the contracts of these tests always set a message"),
+        result => panic!("expected a blame error, got {result:?}"),
+    }
+}
+
+#[test]
+fn left_contracts_are_checked_first() {
+    let contracts = r#"
+        let Check = fun name pred label value =>
+          if pred value then value else std.contract.blame_with_message name label
+        in
+        let Left1 = Check "left1" (fun x => x > 10) in
+        let Left2 = Check "left2" (fun x => x > 5) in
+        let Right = Check "right" (fun x => x > 0) in
+    "#;
+
+    assert_eq!(
+        blamed_contract(&format!(
+            "{contracts} ({{x | Left1 | Left2 = -1}} & {{x | Right}}).x"
+        )),
+        "left1"
+    );
+    assert_eq!(
+        blamed_contract(&format!(
+            "{contracts} ({{x | Right}} & {{x | Left1 | Left2 = -1}}).x"
+        )),
+        "right"
+    );
+    assert_eq!(
+        blamed_contract(&format!(
+            "{contracts} ({{x | Left2}} & {{x | Left1}} & {{x | Right = 3}}).x"
+        )),
+        "left2"
+    );
+    // The contracts are accumulated in the same order through nested merges.
+    assert_eq!(
+        blamed_contract(&format!(
+            "{contracts} ({{x | Left2}} & ({{x | Right}} & {{x | Left1 = -1}})).x"
+        )),
+        "left2"
+    );
+}
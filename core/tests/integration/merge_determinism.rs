@@ -0,0 +1,91 @@
+use nickel_lang_core::{
+    serialize::{self, ExportFormat},
+    term::{RichTerm, Term},
+};
+use nickel_lang_utils::test_program::TestProgram;
+
+fn export(source: &str) -> String {
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let rt = program.eval_full_for_export().map(RichTerm::from).unwrap();
+    serialize::to_string(ExportFormat::Json, &rt).unwrap()
+}
+
+#[test]
+fn merge_output_is_reproducible() {
+    // Each program gets its own hash maps, which are randomly seeded. Many fields, recursive
+    // references and nested merges make it likely to reveal a dependency on hashing.
+    let fields: Vec<String> = (0..50)
+        .map(|i| format!("f{i} = {{ value = {i}, next = g{i} + 1 }}, g{i} | default = {i}"))
+        .collect();
+    let overrides: Vec<String> = (0..50)
+        .rev()
+        .map(|i| format!("f{i} = {{ extra = \"e{i}\" }}, g{i} = {}", i + 1))
+        .collect();
+    let source = format!(
+        "{{ {} }} & {{ {} }}",
+        fields.join(", "),
+        overrides.join(", ")
+    );
+
+    let expected = export(&source);
+
+    for _ in 0..10 {
+        assert_eq!(export(&source), expected);
+    }
+}
+
+/// Render a flat record as an env file, following the order of its fields. Unlike the export
+/// formats, which sort the fields by name, this shows the order produced by the merge.
+fn export_env(source: &str) -> String {
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+
+    match Term::from(program.eval_full_for_export().unwrap()) {
+        Term::Record(record) => record
+            .fields
+            .into_iter()
+            .map(|(id, field)| {
+                let value = match field.value.as_ref().map(|value| value.as_ref()) {
+                    Some(Term::Num(n)) => n.to_string(),
+                    Some(Term::Str(s)) => s.to_string(),
+                    t => panic!("unexpected value for {id}: {t:?}"),
+                };
+                format!("{id}={value}\n")
+            })
+            .collect(),
+        t => panic!("expected a record, got {t:?}"),
+    }
+}
+
+#[test]
+fn merge_preserves_field_order() {
+    // The fields of the left operand keep their position, even when they're overridden, and the
+    // fields only defined in the right operand are appended in their own order.
+    let source = r#"
+        {
+            zeta = 1,
+            port | default = 80,
+            alpha = "a",
+            host | default = "localhost",
+        }
+        & {
+            user = "admin",
+            host = "example.org",
+            beta = 2,
+            port = 8080,
+        }
+    "#;
+
+    assert_eq!(
+        export_env(source),
+        "zeta=1\nport=8080\nalpha=a\nhost=example.org\nuser=admin\nbeta=2\n"
+    );
+
+    // The same holds for nested merges and when keys are shared by several layers.
+    let source = r#"
+        ({ c = 1, a = 2 } & { b = 3, c = 1 }) & { d = 4, a | force = 5 }
+    "#;
+
+    assert_eq!(export_env(source), "c=1\na=5\nb=3\nd=4\n");
+}
@@ -0,0 +1,51 @@
+use nickel_lang_core::{
+    error::MergeSide,
+    eval::{merge::DocOverrideWarning, EvalConfig},
+};
+use nickel_lang_utils::test_program::TestProgram;
+
+use crate::util::program_with;
+
+const SOURCE: &str = r#"({x | doc "terse" = 2} & {x | doc "Long explanation" | default = 1}).x"#;
+
+fn program(source: &str, warn_overridden_docs: bool) -> TestProgram {
+    program_with(
+        source,
+        EvalConfig {
+            warn_overridden_docs,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn doc_override_warnings_disabled_by_default() {
+    assert!(!EvalConfig::default().warn_overridden_docs);
+
+    let mut program = program(SOURCE, false);
+    program.eval_full().unwrap();
+    assert!(program.doc_override_warnings().is_empty());
+}
+
+#[test]
+fn warn_on_overridden_doc() {
+    let mut program = program(SOURCE, true);
+    program.eval_full().unwrap();
+
+    let warnings: Vec<DocOverrideWarning> = program.doc_override_warnings().to_vec();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].path.to_string(), "x");
+    assert_eq!(warnings[0].doc, "Long explanation");
+    assert_eq!(warnings[0].overridden, MergeSide::Right);
+
+    let report = program.report_as_str(warnings[0].clone());
+    assert!(report.contains("documentation on field `x` was overridden during merge"));
+}
+
+#[test]
+fn no_warning_for_identical_docs() {
+    let source = r#"({x | doc "same" = 2} & {x | doc "same" | default = 1}).x"#;
+    let mut program = program(source, true);
+    program.eval_full().unwrap();
+    assert!(program.doc_override_warnings().is_empty());
+}
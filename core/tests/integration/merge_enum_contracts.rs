@@ -0,0 +1,53 @@
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    eval::EvalConfig,
+    identifier::Ident,
+    term::{RichTerm, Term},
+};
+
+use crate::util::eval_with;
+
+fn eval_with_union(source: &str, union_enum_contracts: bool) -> Result<RichTerm, Error> {
+    eval_with(
+        source,
+        EvalConfig {
+            union_enum_contracts,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn enum_contracts_are_combined_by_union() {
+    let result = eval_with_union("({x | [| 'A |]} & {x | [| 'B |] = 'B}).x", true).unwrap();
+    assert_eq!(result.as_ref(), &Term::Enum(Ident::from("B")));
+
+    let result = eval_with_union("({x | [| 'A |] = 'A} & {x | [| 'B, 'C |]}).x", true).unwrap();
+    assert_eq!(result.as_ref(), &Term::Enum(Ident::from("A")));
+
+    // The union still rejects the tags which aren't accepted by either side.
+    assert_matches!(
+        eval_with_union("({x | [| 'A |]} & {x | [| 'B |] = 'C}).x", true),
+        Err(Error::EvalError(EvalError::BlameError { .. }))
+    );
+}
+
+#[test]
+fn enum_contracts_are_conjoined_by_default() {
+    assert_matches!(
+        eval_with_union("({x | [| 'A |]} & {x | [| 'B |] = 'B}).x", false),
+        Err(Error::EvalError(EvalError::BlameError { .. }))
+    );
+
+    let result = eval_with_union("({x | [| 'A, 'B |]} & {x | [| 'B |] = 'B}).x", false).unwrap();
+    assert_eq!(result.as_ref(), &Term::Enum(Ident::from("B")));
+}
+
+#[test]
+fn other_contracts_are_kept_with_union() {
+    assert_matches!(
+        eval_with_union("({x | [| 'A |] | String} & {x | [| 'B |] = 'B}).x", true),
+        Err(Error::EvalError(EvalError::BlameError { .. }))
+    );
+}
@@ -0,0 +1,464 @@
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError, MergeRanges, MergeSide},
+    eval::{merge::ArrayMergeNote, EvalConfig},
+    identifier::Ident,
+    position::RawSpan,
+    term::MergeStrategy,
+};
+use nickel_lang_utils::{project_root::project_root, test_program::TestProgram};
+
+use crate::util::{eval_with, program_with};
+
+fn eval_merge_error(source: &str) -> EvalError {
+    eval_merge_error_with_config(source, EvalConfig::default())
+}
+
+fn eval_merge_error_with_config(source: &str, config: EvalConfig) -> EvalError {
+    match eval_with(source, config) {
+        Err(Error::EvalError(err)) => err,
+        result => panic!("expected an evaluation error, got {result:?}"),
+    }
+}
+
+#[test]
+fn merge_conflict_exposes_merge_label() {
+    let err = eval_merge_error("{a.b = 1} & {a.b = 2}");
+
+    assert!(err.is_merge_error());
+    assert_matches!(err, EvalError::MergeIncompatibleArgs { .. });
+
+    let label = err
+        .merge_label()
+        .expect("merge conflicts carry a merge label");
+    assert_eq!(
+        label.path.to_vec(),
+        vec![Ident::from("a"), Ident::from("b")]
+    );
+}
+
+#[test]
+fn array_length_mismatch_is_structured() {
+    let err = eval_merge_error("({foo | merge element-wise = [1, 2]} & {foo = [1, 2, 3]}).foo");
+
+    assert!(err.is_merge_error());
+    assert_matches!(
+        err,
+        EvalError::MergeArrayLengthMismatch {
+            left_len: 2,
+            right_len: 3,
+            ..
+        }
+    );
+}
+
+#[test]
+fn disjoint_merge_reports_overlapping_fields() {
+    let source =
+        "({foo | merge disjoint = {a = 1, b = 1, c = 1}} & {foo = {b = 1, c = 2, d = 1}}).foo";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeOverlappingFields { fields, merge_label })
+            if fields == &vec![Ident::from("b"), Ident::from("c")]
+                && merge_label.path.to_vec() == vec![Ident::from("foo")]
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("fields defined in both layers of a disjoint merge: `b`, `c`"));
+}
+
+#[test]
+fn frozen_record_cannot_be_extended() {
+    let source = "({foo | frozen = {a = 1}} & {foo = {a | force = 2, b = 1, c = 1}}).foo";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::CannotExtendFrozenRecord { side: MergeSide::Left, fields, merge_label })
+            if fields == &vec![Ident::from("b"), Ident::from("c")]
+                && merge_label.path.to_vec() == vec![Ident::from("foo")]
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("cannot add fields `b`, `c` to a frozen record"));
+
+    // The frozen record can be on the right side as well.
+    assert_matches!(
+        eval_merge_error("({foo = {b = 1}} & {foo | frozen = {a = 1}}).foo"),
+        EvalError::CannotExtendFrozenRecord {
+            side: MergeSide::Right,
+            ..
+        }
+    );
+}
+
+#[test]
+fn merged_field_has_a_position() {
+    let source = "(({x = {a = 1}} & {x = {b = 2}}) | {x | Number}).x";
+
+    match eval_merge_error(source) {
+        EvalError::BlameError {
+            evaluated_arg: Some(evaluated_arg),
+            ..
+        } => {
+            let span = evaluated_arg
+                .pos
+                .into_opt()
+                .expect("the merged field has a position");
+            assert_eq!(
+                span.start.to_usize(),
+                source.find("{a = 1}").unwrap(),
+                "the merged field should point to its first definition"
+            );
+        }
+        err => panic!("expected a blame error with an evaluated argument, got {err:?}"),
+    }
+}
+
+fn array_merge_notes(array_merge_note: ArrayMergeNote) -> Vec<String> {
+    let config = EvalConfig {
+        array_merge_note,
+        ..Default::default()
+    };
+
+    match eval_merge_error_with_config("[1, 2] & [1, 3]", config) {
+        EvalError::BlameError { label, .. } => label
+            .diagnostics
+            .into_iter()
+            .flat_map(|diagnostic| diagnostic.notes)
+            .collect(),
+        err => panic!("expected a blame error, got {err:?}"),
+    }
+}
+
+#[test]
+fn array_merge_note_is_configurable() {
+    let is_default_note = |note: &String| note.contains("auto-generated from a merge operation");
+
+    assert!(array_merge_notes(ArrayMergeNote::Default)
+        .iter()
+        .any(is_default_note));
+
+    let custom = array_merge_notes(ArrayMergeNote::Custom("custom note".into()));
+    assert!(custom.iter().any(|note| note == "custom note"));
+    assert!(!custom.iter().any(is_default_note));
+
+    assert!(!array_merge_notes(ArrayMergeNote::Disabled)
+        .iter()
+        .any(is_default_note));
+}
+
+fn array_merge_diagnostic(source: &str) -> (Option<String>, Vec<String>) {
+    match eval_merge_error(source) {
+        EvalError::BlameError { label, .. } => {
+            // The equality contract is applied to each element with a fresh diagnostic, so we
+            // look for the last non-empty one.
+            let diagnostic = label
+                .diagnostics
+                .into_iter()
+                .rev()
+                .find(|diagnostic| !diagnostic.is_empty())
+                .expect("array merge errors carry a diagnostic");
+            (diagnostic.message, diagnostic.notes)
+        }
+        err => panic!("expected a blame error, got {err:?}"),
+    }
+}
+
+#[test]
+fn array_merge_reports_length_mismatch() {
+    let (message, notes) = array_merge_diagnostic("[1, 2, 3] & [1, 2, 3, 4]");
+
+    assert_eq!(
+        message.as_deref(),
+        Some("cannot merge unequal arrays: array length mismatch")
+    );
+    assert!(notes
+        .iter()
+        .any(|note| note == "The left array has length `3`, but the right array has length `4`."));
+}
+
+#[test]
+fn array_merge_reports_differing_index() {
+    let (message, notes) = array_merge_diagnostic("[1, 2, 3] & [1, 2, 4]");
+
+    assert_eq!(
+        message.as_deref(),
+        Some("cannot merge unequal arrays: elements at index `2` differ")
+    );
+    assert!(notes
+        .iter()
+        .any(|note| note == "The left array has `3` at index `2`, but the right array has `4`."));
+}
+
+fn array_merge_contract(source: &str, display_merged_arrays: bool) -> String {
+    let config = EvalConfig {
+        display_merged_arrays,
+        ..Default::default()
+    };
+
+    match eval_merge_error_with_config(source, config) {
+        EvalError::BlameError { label, .. } => label.types.to_string(),
+        err => panic!("expected a blame error, got {err:?}"),
+    }
+}
+
+#[test]
+fn array_merge_contract_shows_array_on_demand() {
+    let source = "[1, 2] & [1, 2, 3]";
+
+    let contract = array_merge_contract(source, false);
+    assert!(contract.contains("some_array"), "{contract}");
+
+    let contract = array_merge_contract(source, true);
+    assert!(!contract.contains("some_array"), "{contract}");
+    assert!(contract.contains("[ 1, 2 ]"), "{contract}");
+}
+
+#[test]
+fn array_merge_contract_truncates_long_arrays() {
+    let left: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+    let source = format!("[{}] & [0]", left.join(", "));
+
+    let contract = array_merge_contract(&source, true);
+    assert!(contract.contains("19"), "{contract}");
+    assert!(!contract.contains("20"), "{contract}");
+    assert!(contract.contains("..."), "{contract}");
+}
+
+#[test]
+fn merge_reports_cycles_between_fields() {
+    // Neither record has a cycle, but each one overrides the definition the other one relies on.
+    let source = "
+        let r1 = {a | default = 1, b = a, c = 3} in
+        let r2 = {a = b, b | default = 2} in
+        let merged = r1 & r2 in
+        [r1.b, r2.a, merged.c, merged.a]
+    ";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeCycle { fields, .. })
+            if fields == &vec![Ident::from("a"), Ident::from("b")]
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("merging created a cycle between the fields `a`, `b`"));
+
+    // Longer chains of aliases are followed as well.
+    let err = eval_merge_error("({a | default = 1, b = a, c = b} & {a = c, c | default = 3}).a");
+    assert_matches!(
+        err,
+        EvalError::MergeCycle { fields, .. }
+            if fields == vec![Ident::from("a"), Ident::from("b"), Ident::from("c")]
+    );
+
+    // A cycle which already exists in one of the operands isn't reported as a merge error.
+    let err = eval_merge_error("({a = b, b = a} & {a | default = 1}).a");
+    assert_matches!(err, EvalError::InfiniteRecursion(..));
+
+    // Neither is a cycle going through other expressions than aliases.
+    let err = eval_merge_error("({a = b + 1} & {b = a}).a");
+    assert_matches!(err, EvalError::InfiniteRecursion(..));
+}
+
+#[test]
+fn merge_conflict_names_both_files() {
+    let path = project_root().join("core/tests/integration/imports/merge_conflict.ncl");
+    let mut program = TestProgram::new_from_file(path, std::io::stderr()).unwrap();
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeIncompatibleArgs { .. })
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("merge_conflict_base.ncl:4"), "{report}");
+    assert!(report.contains("merge_conflict_prod.ncl:3"), "{report}");
+
+    // When both values come from the same file, the labels are enough.
+    let mut program = TestProgram::new_from_source(
+        "{a = 1} & {a = 2}".as_bytes(),
+        "regr_tests",
+        std::io::stderr(),
+    )
+    .unwrap();
+    let err = program.eval_full().unwrap_err();
+    let report = program.report_as_str(err);
+    assert!(!report.contains("The left value comes from"), "{report}");
+}
+
+#[test]
+fn strict_required_fields_report_missing_values_at_merge() {
+    let source = "({x | String, y = 1} & {x | String}).y";
+    let strict = EvalConfig {
+        strict_required_fields: true,
+        ..Default::default()
+    };
+
+    // By default, the missing value is only reported when `x` is used.
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    assert!(program.eval_full().is_ok());
+
+    let mut program = program_with(source, strict.clone());
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeMissingFieldDef { left_pos, right_pos, merge_label })
+            if left_pos.is_def()
+                && right_pos.is_def()
+                && left_pos != right_pos
+                && merge_label.path.to_vec() == vec![Ident::from("x")]
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("missing definition for `x` after merging"));
+
+    // An optional declaration doesn't make the field optional if the other side requires it.
+    assert_matches!(
+        eval_merge_error_with_config("({x | optional, y = 1} & {x | String}).y", strict.clone()),
+        EvalError::MergeMissingFieldDef { .. }
+    );
+
+    // Fields which are optional on both sides, or given a value by either side, are fine.
+    for source in [
+        "({x | optional, y = 1} & {x | optional}).y",
+        "({x | String, y = 1} & {x = \"a\"}).y",
+    ] {
+        assert!(eval_with(source, strict.clone()).is_ok());
+    }
+}
+
+#[test]
+fn contradictory_contracts_reported_at_merge() {
+    let source = "({x | Number, y = 1} & {x | String}).y";
+    let check = EvalConfig {
+        check_contradictory_contracts: true,
+        ..Default::default()
+    };
+
+    // By default, the contracts are only checked when `x` is used.
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    assert!(program.eval_full().is_ok());
+
+    let mut program = program_with(source, check.clone());
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeContradictoryContracts { span1, span2, merge_label, .. })
+            if span1 != span2 && merge_label.path.to_vec() == vec![Ident::from("x")]
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("contradictory contracts for `x` after merging"));
+
+    // A type annotation counts as a contract as well.
+    assert_matches!(
+        eval_merge_error_with_config("({x : Bool = true} & {x | String}).x", check.clone()),
+        EvalError::MergeContradictoryContracts { .. }
+    );
+
+    // Compatible contracts, contracts which aren't primitive types, and fields which are optional
+    // without a value, are fine.
+    for source in [
+        "({x | Number, y = 1} & {x | Number}).y",
+        "({x | Number, y = 1} & {x | Dyn}).y",
+        "({x | Number, y = 1} & {x | std.number.Integer}).y",
+        "({x | Array Number, y = 1} & {x | Array String}).y",
+        "({x | Number | optional, y = 1} & {x | String | optional}).y",
+    ] {
+        assert!(eval_with(source, check.clone()).is_ok(), "{source}");
+    }
+}
+
+#[test]
+fn conflicting_strategies_reported_at_merge() {
+    let source = "({foo | merge sum = 1} & {foo | merge max = 2}).foo";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::MergeConflictingStrategies {
+            left: MergeStrategy::Sum,
+            right: MergeStrategy::Max,
+            merge_label,
+        }) if merge_label.path.to_vec() == vec![Ident::from("foo")]
+    );
+
+    let report = program.report_as_str(err);
+    assert!(report.contains("conflicting merge strategies `sum` and `max`"));
+
+    // Definitions without a value select a strategy as well.
+    assert_matches!(
+        eval_merge_error("({foo | merge left} & {foo | merge right = 1}).foo"),
+        EvalError::MergeConflictingStrategies { .. }
+    );
+    assert_matches!(
+        eval_merge_error("({foo | merge concat = \"a\"} & {foo | merge concat \" \" = \"b\"}).foo"),
+        EvalError::MergeConflictingStrategies { .. }
+    );
+}
+
+/// Return the text covered by the primary range of the merge error raised by `source`, its
+/// message, and the text and message of each related range.
+fn merge_ranges_text(source: &str) -> (&str, String, Vec<(&str, String)>) {
+    let MergeRanges {
+        primary,
+        primary_message,
+        related,
+    } = eval_merge_error(source).merge_ranges().unwrap();
+    let text = |span: RawSpan| &source[span.start.to_usize()..span.end.to_usize()];
+
+    (
+        text(primary),
+        primary_message,
+        related
+            .into_iter()
+            .map(|(span, message)| (text(span), message))
+            .collect(),
+    )
+}
+
+#[test]
+fn merge_conflict_ranges() {
+    let source = "{a = 1} & {a = 2}";
+    assert_eq!(
+        merge_ranges_text(source),
+        (
+            source,
+            String::from("originally merged here"),
+            vec![
+                ("1", String::from("cannot merge this expression")),
+                ("2", String::from("with this expression")),
+            ]
+        )
+    );
+
+    let (primary, primary_message, related) = merge_ranges_text("{a = 1, a = 2}");
+    assert_eq!(primary, "a");
+    assert_eq!(
+        primary_message,
+        "when combining the definitions of this field"
+    );
+    let related: Vec<_> = related.into_iter().map(|(text, _)| text).collect();
+    assert_eq!(related, ["1", "2"]);
+
+    // Other errors don't have merge ranges.
+    assert_eq!(EvalError::Cancelled.merge_ranges(), None);
+}
@@ -0,0 +1,82 @@
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    eval::EvalConfig,
+    term::{make as mk_term, RichTerm, Term},
+};
+use nickel_lang_utils::test_program::parse;
+
+use crate::util::eval_with;
+
+/// Evaluate a program with a `semver-max` merge function, which keeps the greatest of two
+/// versions written as `"major.minor.patch"`.
+fn eval_with_merge_fns(source: &str) -> Result<RichTerm, Error> {
+    let mut config = EvalConfig::default();
+    config.register_merge_fn("semver-max", |left, right| {
+        let version = |rt: &RichTerm| -> Result<Vec<u64>, String> {
+            let Term::Str(s) = rt.as_ref() else {
+                return Err(format!("expected a version string, got {rt}"));
+            };
+            s.split('.')
+                .map(|part| part.parse().map_err(|_| format!("invalid version `{s}`")))
+                .collect()
+        };
+
+        Ok(if version(left)? >= version(right)? {
+            left.clone()
+        } else {
+            right.clone()
+        })
+    });
+
+    eval_with(source, config)
+}
+
+#[test]
+fn registered_merge_fn_merges_annotated_fields() {
+    let result = eval_with_merge_fns(
+        "({deps.foo | merge \"semver-max\" = \"1.10.0\"} & {deps.foo = \"1.9.2\"}).deps.foo",
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("1.10.0"));
+
+    // The annotation can be on either side, and the merge function is applied to evaluated
+    // values.
+    let result = eval_with_merge_fns(
+        "let v = \"2.0\" in ({foo = \"1.%{\"0\"}\"} & {foo | merge \"semver-max\" = v}).foo",
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("2.0"));
+
+    // Priorities are resolved before merging the values.
+    let result = eval_with_merge_fns(
+        "({foo | merge \"semver-max\" | force = \"1.0.0\"} & {foo = \"2.0.0\"}).foo",
+    )
+    .unwrap();
+    assert_eq!(result.without_pos(), mk_term::string("1.0.0"));
+}
+
+#[test]
+fn merge_fn_errors_are_reported() {
+    assert_matches!(
+        eval_with_merge_fns("({foo | merge \"semver-max\" = \"1.0\"} & {foo = 1}).foo"),
+        Err(Error::EvalError(EvalError::MergeFnFailed { name, message, merge_label }))
+            if name == "semver-max"
+                && message == "expected a version string, got 1"
+                && merge_label.path.to_string() == "foo"
+    );
+
+    // Unknown merge functions are reported when the record is merged, even if the field isn't
+    // used.
+    assert_matches!(
+        eval_with_merge_fns("({foo | merge \"semver-min\" = \"1.0\", bar = 1} & {foo = \"2.0\"}).bar"),
+        Err(Error::EvalError(EvalError::UnknownMergeFn { name, .. })) if name == "semver-min"
+    );
+}
+
+#[test]
+fn merge_fn_annotation_is_pretty_printed() {
+    let rt = parse("{ foo | merge \"semver-max\" = \"1.0\" }").unwrap();
+
+    assert!(rt.to_string().contains("| merge \"semver-max\""));
+}
@@ -0,0 +1,57 @@
+use std::{cell::RefCell, rc::Rc};
+
+use nickel_lang_core::eval::merge::{FieldMergeEvent, FieldMergeKind, MergeObserver};
+use nickel_lang_utils::test_program::TestProgram;
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<(String, FieldMergeKind, usize, usize)>,
+}
+
+impl MergeObserver for Recorder {
+    fn field_merged(&mut self, event: FieldMergeEvent<'_>) {
+        self.events.push((
+            event.path.to_string(),
+            event.kind,
+            event.left_sites.len(),
+            event.right_sites.len(),
+        ));
+    }
+}
+
+#[test]
+fn observer_is_notified_of_field_merges() {
+    let source = "{a = 1, b | default = 2, c.d = 1, e = 1} & {a = 1, b = 3, c.d = 1, f = 1}";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+
+    let recorder = Rc::new(RefCell::new(Recorder::default()));
+    program.set_merge_observer(Some(recorder.clone()));
+    program.eval_full().unwrap();
+
+    let events = &recorder.borrow().events;
+
+    for expected in [
+        ("a", FieldMergeKind::Merge),
+        ("b", FieldMergeKind::Right),
+        ("c", FieldMergeKind::Merge),
+        ("c.d", FieldMergeKind::Merge),
+    ] {
+        assert!(
+            events
+                .iter()
+                .any(|(path, kind, ..)| (path.as_str(), *kind) == expected),
+            "missing event {expected:?} in {events:?}"
+        );
+    }
+
+    // Each side of `a` has exactly one definition.
+    assert!(events
+        .iter()
+        .any(|(path, _, left, right)| path.as_str() == "a" && *left == 1 && *right == 1));
+
+    // Fields defined on one side only aren't merged.
+    assert!(!events
+        .iter()
+        .any(|(path, ..)| path.as_str() == "e" || path.as_str() == "f"));
+}
@@ -0,0 +1,53 @@
+use nickel_lang_core::{
+    eval::{merge::MergeStats, EvalConfig},
+    term::make as mk_term,
+};
+use nickel_lang_utils::test_program::TestProgram;
+
+use crate::util::program_with;
+
+const SOURCE: &str = "{a.b = 1, c = a.b + 1} & {a.b | force = 2}";
+
+fn program() -> TestProgram {
+    program_with(
+        SOURCE,
+        EvalConfig {
+            collect_merge_stats: true,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn preview_computes_merged_value() {
+    let mut program = program();
+
+    assert_eq!(
+        program
+            .merge_preview(String::from("c"))
+            .unwrap()
+            .without_pos(),
+        mk_term::integer(3)
+    );
+}
+
+#[test]
+fn preview_leaves_state_untouched() {
+    let mut program = program();
+
+    // The merges of a preview allocate their revertible elements in a scratch cache, and are
+    // counted in a scratch merge context, which is dropped afterwards.
+    program.merge_preview(String::from("c")).unwrap();
+    assert_eq!(program.merge_stats(), Some(MergeStats::default()));
+
+    program.eval_full().unwrap();
+    let stats = program.merge_stats();
+    assert_ne!(stats, Some(MergeStats::default()));
+
+    program.merge_preview(String::from("a")).unwrap();
+    assert_eq!(program.merge_stats(), stats);
+
+    // The state is restored even if the preview fails.
+    assert!(program.merge_preview(String::from("d")).is_err());
+    assert_eq!(program.merge_stats(), stats);
+}
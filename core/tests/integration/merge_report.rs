@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use nickel_lang_core::{
+    error::MergeSide,
+    eval::merge::{field_contributions, merge_report, Conflict, IncrementalMerge},
+    identifier::Ident,
+    term::{record::RecordData, Term},
+};
+use nickel_lang_utils::test_program::TestProgram;
+
+/// Fully evaluate a program to a record.
+fn eval_record(source: &str) -> RecordData {
+    let result = TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr())
+        .unwrap()
+        .eval_full()
+        .unwrap();
+
+    match result.as_ref() {
+        Term::Record(record) => record.clone(),
+        _ => panic!("expected the program to evaluate to a record"),
+    }
+}
+
+/// Return the paths and the values of the conflicts as strings, sorted by path.
+fn describe(conflicts: &[Conflict]) -> Vec<(String, String, String)> {
+    let mut described: Vec<_> = conflicts
+        .iter()
+        .map(|conflict| {
+            (
+                conflict.path.to_string(),
+                conflict.left.to_string(),
+                conflict.right.to_string(),
+            )
+        })
+        .collect();
+
+    described.sort();
+    described
+}
+
+fn field_value<'a>(record: &'a RecordData, field: &str) -> Option<&'a Term> {
+    record.fields[&Ident::from(field)]
+        .value
+        .as_ref()
+        .map(AsRef::as_ref)
+}
+
+#[test]
+fn reports_all_nested_conflicts() {
+    let left = eval_record(
+        r#"{
+          name = "web",
+          server = { port = 80, host = "localhost", tls = { enabled = false } },
+          replicas = 1,
+        }"#,
+    );
+    let right = eval_record(
+        r#"{
+          name = "web",
+          server = { port = 8080, host = "localhost", tls = { enabled = true } },
+          replicas = 2,
+          region = "eu",
+        }"#,
+    );
+
+    let (record, conflicts) = merge_report(left, right);
+
+    assert_eq!(
+        describe(&conflicts),
+        vec![
+            ("replicas".into(), "1".into(), "2".into()),
+            ("server.port".into(), "80".into(), "8080".into()),
+            ("server.tls.enabled".into(), "false".into(), "true".into()),
+        ]
+    );
+
+    // Fields without conflict are merged as usual, and conflicting ones are placeholders.
+    assert!(record.fields.contains_key(&Ident::from("region")));
+    assert!(matches!(field_value(&record, "name"), Some(Term::Str(_))));
+    assert!(matches!(
+        field_value(&record, "replicas"),
+        Some(Term::RuntimeError(_))
+    ));
+}
+
+#[test]
+fn priorities_resolve_conflicts() {
+    let left = eval_record("{ a | default = 1, b = { c | force = 2, d = 3 } }");
+    let right = eval_record("{ a = 2, b = { c = 3, d = 4 } }");
+
+    let (_, conflicts) = merge_report(left, right);
+
+    assert_eq!(
+        describe(&conflicts),
+        vec![("b.d".into(), "3".into(), "4".into())]
+    );
+}
+
+#[test]
+fn no_conflicts() {
+    let left = eval_record("{ a = 1, b = { c = [1, 2] } }");
+    let right = eval_record("{ a = 1, b = { c = [1, 2], d = 3 } }");
+
+    let (record, conflicts) = merge_report(left, right);
+
+    assert!(conflicts.is_empty());
+    assert_eq!(record.fields.len(), 2);
+}
+
+#[test]
+fn incremental_merge_matches_full_merge() {
+    let left = eval_record("{ a = 1, b = { c = 1 }, c = 1, d = 1 }");
+    let right = eval_record("{ a = 1, b = { c = 1 }, d = 1 }");
+
+    let mut merge = IncrementalMerge::new(left.clone(), right);
+    assert!(merge.conflicts().is_empty());
+
+    // `b` is modified, `d` is removed and `e` is added.
+    let right = eval_record("{ a = 1, b = { c = 2 }, e = 1 }");
+    merge.update(
+        MergeSide::Right,
+        right.clone(),
+        &HashSet::from(["b", "d", "e"].map(Ident::from)),
+    );
+
+    let (record, conflicts) = merge_report(left, right);
+    assert_eq!(merge.result(), record);
+    assert_eq!(describe(&merge.conflicts()), describe(&conflicts));
+    assert_eq!(
+        describe(&conflicts),
+        vec![("b.c".into(), "1".into(), "2".into())]
+    );
+}
+
+#[test]
+fn incremental_merge_reuses_unchanged_fields() {
+    let left = eval_record("{ a = 1, b = 1 }");
+    let mut merge = IncrementalMerge::new(left, eval_record("{ a = 1, b = 1 }"));
+
+    // Only `a` is declared as changed: the previous result of `b` is kept.
+    merge.update(
+        MergeSide::Right,
+        eval_record("{ a = 2, b = 2 }"),
+        &HashSet::from([Ident::from("a")]),
+    );
+
+    assert_eq!(
+        describe(&merge.conflicts()),
+        vec![("a".into(), "1".into(), "2".into())]
+    );
+    assert!(matches!(
+        field_value(&merge.result(), "b"),
+        Some(Term::Num(_))
+    ));
+}
+
+#[test]
+fn field_contributions_count_definitions() {
+    let record = eval_record(
+        "{x | default = 1, a.b = 1} & {x = 2, a.c = 1} & {x | force = 3, a.b | force = 2, y = 1}",
+    );
+
+    let contributions: Vec<_> = field_contributions(&record)
+        .into_iter()
+        .map(|(path, count)| {
+            let path: Vec<_> = path.iter().map(Ident::label).collect();
+            (path.join("."), count)
+        })
+        .collect();
+
+    assert_eq!(
+        contributions,
+        vec![
+            (String::from("x"), 3),
+            (String::from("a.b"), 2),
+            (String::from("a.c"), 1),
+            (String::from("y"), 1),
+        ]
+    );
+}
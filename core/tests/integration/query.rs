@@ -1,10 +1,15 @@
-use nickel_lang_core::term::{
-    make as mk_term,
-    record::{Field, FieldMetadata},
-    TypeAnnotation,
+use nickel_lang_core::{
+    eval::EvalConfig,
+    term::{
+        make as mk_term,
+        record::{Field, FieldMetadata, PriorityResolution, RecordAttrs},
+        Term, TypeAnnotation,
+    },
 };
 use nickel_lang_utils::test_program::TestProgram;
 
+use crate::util::program_with;
+
 #[test]
 pub fn test_query_metadata_basic() {
     let mut program = TestProgram::new_from_source(
@@ -107,3 +112,204 @@ pub fn test_query_with_wildcard() {
         path,
     );
 }
+
+#[test]
+pub fn test_query_merged_from_count() {
+    let mut program = TestProgram::new_from_source(
+        "{val = {a = 1} & {b = 2} & {c = 3} & {d = 4}}".as_bytes(),
+        "regr_tests",
+        std::io::stderr(),
+    )
+    .unwrap();
+    let result = program.query(Some(String::from("val"))).unwrap();
+
+    match result.value.as_ref().map(|value| value.as_ref()) {
+        Some(Term::Record(record)) => {
+            assert_eq!(record.attrs.merged_from, 4);
+            // The merge counter is purely informative and doesn't impact equality
+            assert_eq!(record.attrs, RecordAttrs::default());
+        }
+        _ => panic!("expected the queried value to be a record"),
+    }
+}
+
+#[test]
+pub fn test_query_merged_from_count_ignores_contracts() {
+    let merged_from = |source: &str| {
+        let mut program =
+            TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr())
+                .unwrap();
+        let result = program.query(Some(String::from("val"))).unwrap();
+
+        match result.value.as_ref().map(|value| value.as_ref()) {
+            Some(Term::Record(record) | Term::RecRecord(record, ..)) => record.attrs.merged_from,
+            _ => panic!("expected the queried value to be a record"),
+        }
+    };
+
+    // Applying a record contract doesn't merge records together: the value is still a single
+    // record, which isn't the result of a merge.
+    assert_eq!(merged_from("{val = {a = 1} | {a | Number}}"), 0);
+    assert_eq!(
+        merged_from("{val = ({a = 1} & {b = 2}) | {a | Number, b | Number}}"),
+        2
+    );
+}
+
+#[test]
+pub fn test_query_definition_sites() {
+    let source = "{val = 1} & {val | default = 2} & {val | priority -1 = 3}";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let result = program.query(Some(String::from("val"))).unwrap();
+
+    let starts: Vec<usize> = result
+        .metadata
+        .definition_sites
+        .iter()
+        .map(|pos| pos.unwrap().start.to_usize())
+        .collect();
+    let expected: Vec<usize> = ["1", "2", "3"]
+        .iter()
+        .map(|value| source.find(value).unwrap())
+        .collect();
+
+    assert_eq!(starts, expected);
+}
+
+#[test]
+pub fn test_query_overridden_type_annotation() {
+    let query_annotation = |source: &str| {
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr())
+            .unwrap()
+            .query(Some(String::from("val")))
+            .unwrap()
+            .metadata
+            .annotation
+    };
+
+    // The type annotation of an overridden value is dropped...
+    let annotation = query_annotation("{val : Number = 1} & {val | force = 2}");
+    assert!(annotation.types.is_none());
+    assert!(annotation.contracts.is_empty());
+
+    // ...but not the type annotation of the value which wins...
+    let annotation = query_annotation("{val | default = 1} & {val : Number = 2}");
+    assert!(annotation.types.is_some());
+
+    // ...and contract annotations are always kept.
+    let annotation = query_annotation("{val | Number = 1} & {val | force = 2}");
+    assert_eq!(annotation.contracts.len(), 1);
+
+    // When the values are merged, type annotations are accumulated as before.
+    let annotation = query_annotation("{val : Number = 1} & {val : Number = 1}");
+    assert!(annotation.types.is_some());
+    assert_eq!(annotation.contracts.len(), 1);
+}
+
+#[test]
+pub fn test_query_priority_resolution() {
+    let query_resolution = |source: &str| {
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr())
+            .unwrap()
+            .query(Some(String::from("val")))
+            .unwrap()
+            .metadata
+            .priority_resolution
+    };
+
+    assert_eq!(query_resolution("{val = 1}"), None);
+    assert_eq!(
+        query_resolution("{val = 1} & {val = 1}"),
+        Some(PriorityResolution::MergedEqual)
+    );
+    assert_eq!(
+        query_resolution("{val | default = 1} & {val = 2}"),
+        Some(PriorityResolution::OverrodeLeft)
+    );
+    assert_eq!(
+        query_resolution("{val | force = 1} & {val = 2}"),
+        Some(PriorityResolution::OverrodeRight)
+    );
+    assert_eq!(
+        query_resolution("{val | Number} & {val = 2}"),
+        Some(PriorityResolution::DefaultOnly)
+    );
+}
+
+#[test]
+pub fn test_query_doc_sources() {
+    let query_field = |source: &str| {
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr())
+            .unwrap()
+            .query(Some(String::from("val")))
+            .unwrap()
+    };
+
+    let field = query_field("{val | doc \"base\" = 1} & {val | doc \"prod\" | force = 2}");
+    let docs: Vec<_> = field
+        .metadata
+        .doc_sources
+        .iter()
+        .map(|(_, doc)| doc.as_str())
+        .collect();
+    assert_eq!(docs, vec!["base", "prod"]);
+    assert!(field
+        .metadata
+        .doc_sources
+        .iter()
+        .all(|(pos, _)| pos.is_def()));
+    // The rendered documentation is unchanged.
+    assert_eq!(field.metadata.doc.as_deref(), Some("base"));
+
+    // Undocumented definitions aren't listed.
+    let field = query_field("{val | doc \"base\" = 1} & {val = 1}");
+    assert_eq!(field.metadata.doc_sources.len(), 1);
+}
+
+#[test]
+pub fn test_query_introduced_fields() {
+    let query_introduced = |source: &str, path: &str, track: bool| {
+        program_with(
+            source,
+            EvalConfig {
+                track_introduced_fields: track,
+                ..Default::default()
+            },
+        )
+        .query(Some(String::from(path)))
+        .unwrap()
+        .metadata
+        .introduced
+    };
+
+    let source = "{left = 1, both = 1} & {both = 1, right = 1}";
+    assert!(!query_introduced(source, "left", true));
+    assert!(!query_introduced(source, "both", true));
+    assert!(query_introduced(source, "right", true));
+    // The flag isn't set unless requested.
+    assert!(!query_introduced(source, "right", false));
+
+    // Only the latest overlay counts.
+    let source = "{left = 1} & {middle = 1} & {right = 1}";
+    assert!(!query_introduced(source, "middle", true));
+    assert!(query_introduced(source, "right", true));
+
+    // Merging with an empty record is accounted for as well.
+    assert!(query_introduced("{} & {right = 1}", "right", true));
+    assert!(!query_introduced(
+        "({left = 1} & {right = 1}) & {}",
+        "right",
+        true
+    ));
+
+    // Nested records are flagged when they are merged in turn.
+    let source = "{sub.left = 1} & {sub.right = 1}";
+    assert!(!query_introduced(source, "sub", true));
+    assert!(query_introduced(source, "sub.right", true));
+
+    // Applying a contract doesn't introduce any field.
+    let source = "({left = 1} & {right = 1}) | {left | Number, right | Number}";
+    assert!(!query_introduced(source, "left", true));
+    assert!(query_introduced(source, "right", true));
+}
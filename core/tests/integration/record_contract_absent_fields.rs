@@ -0,0 +1,108 @@
+use assert_matches::assert_matches;
+use nickel_lang_core::{
+    error::{Error, EvalError},
+    eval::{merge::AbsentFields, EvalConfig},
+    term::{make as mk_term, RichTerm, Term},
+};
+
+use crate::util::eval_with;
+
+fn eval(source: &str, absent_fields: AbsentFields) -> Result<RichTerm, Error> {
+    eval_with(
+        source,
+        EvalConfig {
+            absent_fields,
+            ..Default::default()
+        },
+    )
+    .map(|result| result.without_pos())
+}
+
+/// The declarations of `port` tested by the matrix below.
+const DEFAULTED: &str = "{name, port | default = 80}";
+const OPTIONAL: &str = "{name, port | optional}";
+const REQUIRED: &str = "{name, port}";
+
+#[test]
+fn lenient_by_default() {
+    assert_eq!(EvalConfig::default().absent_fields, AbsentFields::Lenient);
+}
+
+#[test]
+fn present_fields_are_checked_in_both_modes() {
+    for mode in [AbsentFields::Lenient, AbsentFields::Strict] {
+        for contract in [DEFAULTED, OPTIONAL, REQUIRED] {
+            let source = format!("({{name = \"x\", port = 8080}} | {contract}).port");
+            assert_eq!(eval(&source, mode).unwrap(), mk_term::integer(8080));
+        }
+    }
+}
+
+#[test]
+fn defaulted_fields_are_filled_in_both_modes() {
+    for mode in [AbsentFields::Lenient, AbsentFields::Strict] {
+        let source = format!("({{name = \"x\"}} | {DEFAULTED}).port");
+        assert_eq!(eval(&source, mode).unwrap(), mk_term::integer(80));
+    }
+}
+
+#[test]
+fn optional_fields() {
+    let source = format!("std.record.has_field \"port\" ({{name = \"x\"}} | {OPTIONAL})");
+
+    assert_eq!(
+        eval(&source, AbsentFields::Lenient).unwrap(),
+        Term::Bool(false).into()
+    );
+    assert_matches!(
+        eval(&source, AbsentFields::Strict),
+        Err(Error::EvalError(EvalError::BlameError { label, .. }))
+            if label.current_diagnostic().and_then(|d| d.message.as_deref())
+                == Some("missing field `port`")
+    );
+}
+
+#[test]
+fn required_fields() {
+    // In lenient mode, a missing value is only reported when the field is used.
+    let unused = format!("({{name = \"x\"}} | {REQUIRED}).name");
+    let used = format!("({{name = \"x\"}} | {REQUIRED}).port");
+
+    assert_eq!(
+        eval(&unused, AbsentFields::Lenient).unwrap(),
+        mk_term::string("x")
+    );
+    assert_matches!(
+        eval(&used, AbsentFields::Lenient),
+        Err(Error::EvalError(EvalError::MissingFieldDef { .. }))
+    );
+
+    // In strict mode, it's reported when the contract is applied.
+    assert_matches!(
+        eval(&unused, AbsentFields::Strict),
+        Err(Error::EvalError(EvalError::BlameError { label, .. }))
+            if label.current_diagnostic().and_then(|d| d.message.as_deref())
+                == Some("missing field `port`")
+    );
+}
+
+#[test]
+fn extra_fields_report_missing_fields_of_the_mode() {
+    let source = format!("({{name = \"x\", prot = 8080}} | {OPTIONAL}).name");
+
+    let message = |mode| match eval(&source, mode) {
+        Err(Error::EvalError(EvalError::BlameError { label, .. })) => label
+            .current_diagnostic()
+            .and_then(|diagnostic| diagnostic.message.clone()),
+        result => panic!("expected a blame error, got {result:?}"),
+    };
+
+    assert_eq!(
+        message(AbsentFields::Lenient).as_deref(),
+        Some("extra field `prot`")
+    );
+    assert_eq!(
+        message(AbsentFields::Strict).as_deref(),
+        Some("extra field `prot`; missing field `port`")
+    );
+}
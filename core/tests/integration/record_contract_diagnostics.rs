@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use nickel_lang_core::error::{Error, EvalError, IllegalPolymorphicTailAction, MergeSide};
+use nickel_lang_utils::test_program::{eval, TestProgram};
+
+/// Evaluate a program which is expected to fail with a blame error, and return the notes of the
+/// last diagnostic of the label.
+fn blame_notes(source: &str) -> Vec<String> {
+    match eval(source) {
+        Err(Error::EvalError(EvalError::BlameError { label, .. })) => label
+            .diagnostics
+            .last()
+            .map(|diagnostic| diagnostic.notes.clone())
+            .unwrap_or_default(),
+        res => panic!("expected blame error, got {res:?}"),
+    }
+}
+
+#[test]
+fn extra_field_lists_accepted_fields() {
+    let notes =
+        blame_notes("%deep_seq% ({c = 1, extra = 2} | {c | Number, b | Number, a | Number}) null");
+
+    assert!(notes
+        .iter()
+        .any(|note| note == "The record contract accepts the following fields: `a`, `b`, `c`."));
+}
+
+#[test]
+fn extra_field_truncates_accepted_fields() {
+    let contract_fields: Vec<String> = (0..25).map(|i| format!("f{i:02}")).collect();
+    let contract: Vec<String> = contract_fields
+        .iter()
+        .map(|field| format!("{field} | Dyn"))
+        .collect();
+    let notes = blame_notes(&format!(
+        "%deep_seq% ({{extra = 1}} | {{{}}}) null",
+        contract.join(", ")
+    ));
+
+    let expected: Vec<String> = contract_fields[..20]
+        .iter()
+        .map(|field| format!("`{field}`"))
+        .collect();
+
+    assert!(notes.iter().any(|note| note
+        == &format!(
+            "The record contract accepts the following fields: {} and 5 more.",
+            expected.join(", ")
+        )));
+}
+
+#[test]
+fn extra_fields_message_is_bounded() {
+    let extra: Vec<String> = (0..500).map(|i| format!("f{i:03} = {i}")).collect();
+
+    match eval(format!(
+        "%deep_seq% ({{{}}} | {{a | optional}}) null",
+        extra.join(", ")
+    )) {
+        Err(Error::EvalError(EvalError::BlameError { label, .. })) => {
+            let message = label
+                .diagnostics
+                .last()
+                .and_then(|diagnostic| diagnostic.message.clone())
+                .unwrap();
+
+            assert!(message.starts_with("extra fields `f000`, `f001`"));
+            assert!(message.ends_with("`f009` and 490 more"));
+            assert!(!message.contains("f010"));
+        }
+        res => panic!("expected blame error, got {res:?}"),
+    }
+}
+
+#[test]
+fn extra_and_missing_fields_are_reported_together() {
+    match eval("%deep_seq% ({a = 1, extra = 2} | {a | Number, b | Number, c | optional}) null") {
+        Err(Error::EvalError(EvalError::BlameError { label, .. })) => assert_eq!(
+            label
+                .diagnostics
+                .last()
+                .and_then(|diagnostic| diagnostic.message.as_deref()),
+            Some("extra field `extra`; missing field `b`")
+        ),
+        res => panic!("expected blame error, got {res:?}"),
+    }
+}
+
+#[test]
+fn null_against_record_contract() {
+    match eval("let C = {a | Number} in %deep_seq% {config | C = null} null") {
+        Err(Error::EvalError(EvalError::BlameError { label, .. })) => {
+            let diagnostic = label.diagnostics.last().unwrap();
+
+            assert_eq!(
+                diagnostic.message.as_deref(),
+                Some("expected a record, but the value is null")
+            );
+            assert!(diagnostic
+                .notes
+                .iter()
+                .any(|note| note == "Did you forget to provide this field?"));
+        }
+        res => panic!("expected blame error, got {res:?}"),
+    }
+}
+
+#[test]
+fn sealed_tail_merge_reports_side() {
+    let merge_sealed = |body: &str| {
+        eval(format!(
+            "let f | forall r. {{ a : Number; r }} -> {{ a : Number; r }} = fun r => {body} \
+            in f {{ a | default = 100, b = 1 }}"
+        ))
+    };
+
+    assert_matches!(
+        merge_sealed("r & { a | force = 0 }"),
+        Err(Error::EvalError(EvalError::IllegalPolymorphicTailAccess {
+            action: IllegalPolymorphicTailAction::Merge {
+                side: MergeSide::Left,
+                ..
+            },
+            ..
+        }))
+    );
+    assert_matches!(
+        merge_sealed("{ a | force = 0 } & r"),
+        Err(Error::EvalError(EvalError::IllegalPolymorphicTailAccess {
+            action: IllegalPolymorphicTailAction::Merge {
+                side: MergeSide::Right,
+                ..
+            },
+            ..
+        }))
+    );
+}
+
+#[test]
+fn sealed_tail_merge_reports_path_and_positions() {
+    let source = "\
+let f | forall r. { a : Number; r } -> { a : Number; r } = fun r =>
+  %seq% ({ config.inner = r } & { config.inner = { a | force = 0 } }).config.inner r
+in f { a = 1, b = 2 }";
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    let err = program.eval_full().unwrap_err();
+
+    assert_matches!(
+        &err,
+        Error::EvalError(EvalError::IllegalPolymorphicTailAccess {
+            action: IllegalPolymorphicTailAction::Merge { path, .. },
+            ..
+        }) if path.to_string() == "config.inner"
+    );
+
+    let report = program.report_as_str(err);
+
+    // The polymorphic contract which sealed the tail
+    assert!(report.contains("forall r. { a : Number; r }"));
+    // The merge which tried to access the sealed tail
+    assert!(report.contains("{ config.inner = r } & { config.inner = { a | force = 0 } }"));
+    assert!(report.contains("while merging here"));
+    assert!(report.contains(
+        "The record with a sealed tail is the value of the field `config.inner` of the merged \
+        records."
+    ));
+    // A suggestion on how to restructure the code
+    assert!(report.contains("restructure the code such that the merge happens before the record"));
+}
+
+#[test]
+fn blame_errors_are_grouped_by_record_contract() {
+    let config = "\
+let Service = { port | Number, host | String, replicas | Number } in
+let Database = { name | String, user | { id | Number } } in
+{
+  service = { port = \"80\", host = 1, replicas = \"3\" } | Service,
+  database = { name = 1, user.id = \"root\" } | Database,
+  extra = { name = \"db\", password = \"\" } | Database,
+}";
+
+    let paths = [
+        "service.port",
+        "service.host",
+        "service.replicas",
+        "database.name",
+        "database.user.id",
+        "extra",
+    ];
+
+    let mut violations: HashMap<String, usize> = HashMap::new();
+
+    for path in paths {
+        let err = eval(format!("%deep_seq% ({config}).{path} null")).unwrap_err();
+        let Error::EvalError(err) = err else {
+            panic!("expected an evaluation error, got {err:?}");
+        };
+        let contract_id = err
+            .contract_id()
+            .expect("expected a record contract identifier");
+        *violations.entry(contract_id.to_string()).or_default() += 1;
+    }
+
+    assert_eq!(violations.get("Service"), Some(&3));
+    // The nested contract of `user` takes precedence over `Database` for `user.id`, while the
+    // extra field of `extra` is reported against the `Database` contract itself.
+    assert_eq!(violations.get("Database"), Some(&2));
+    assert_eq!(violations.len(), 3);
+}
@@ -0,0 +1,16 @@
+//! Helpers shared by the integration tests.
+use nickel_lang_core::{error::Error, eval::EvalConfig, term::RichTerm};
+use nickel_lang_utils::test_program::TestProgram;
+
+/// Create a test program from `source`, evaluated with the given configuration.
+pub fn program_with(source: &str, config: EvalConfig) -> TestProgram {
+    let mut program =
+        TestProgram::new_from_source(source.as_bytes(), "regr_tests", std::io::stderr()).unwrap();
+    program.set_eval_config(config);
+    program
+}
+
+/// Fully evaluate `source` with the given configuration.
+pub fn eval_with(source: &str, config: EvalConfig) -> Result<RichTerm, Error> {
+    program_with(source, config).eval_full()
+}
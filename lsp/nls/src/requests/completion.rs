@@ -1096,8 +1096,16 @@ mod tests {
                 contracts: Vec::new(),
             },
             opt: false,
+            relax_opt: false,
+            frozen: false,
             not_exported: false,
             priority: MergePriority::Neutral,
+            merge_strategy: None,
+            definition_sites: Vec::new(),
+            doc_sources: Vec::new(),
+            contributions: 0,
+            priority_resolution: None,
+            introduced: false,
         };
 
         let c = make_lin_item(ItemId { file_id, index: 2 }, TermKind::Structure, None);
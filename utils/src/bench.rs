@@ -18,6 +18,8 @@ pub enum EvalMode {
     Normal,
     DeepSeq,
     TypeCheck,
+    /// Fully evaluate the term as for an export, skipping fields marked as `not_exported`.
+    Export,
 }
 
 pub struct Bench<'b> {
@@ -121,13 +123,17 @@ pub fn bench_terms<'r>(rts: Vec<Bench<'r>>) -> Box<dyn Fn(&mut Criterion) + 'r>
                             c_local.typecheck(id, &type_ctxt).unwrap();
                         } else {
                             c_local.prepare(id, &type_ctxt).unwrap();
-                            VirtualMachine::new_with_cache(
+                            let mut vm = VirtualMachine::new_with_cache(
                                 c_local,
                                 eval_cache.clone(),
                                 std::io::sink(),
-                            )
-                            .eval(t, &eval_env)
-                            .unwrap();
+                            );
+
+                            if bench.eval_mode == EvalMode::Export {
+                                vm.eval_full_for_export(t, &eval_env).unwrap();
+                            } else {
+                                vm.eval(t, &eval_env).unwrap();
+                            }
                         }
                     },
                     criterion::BatchSize::LargeInput,